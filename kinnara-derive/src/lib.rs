@@ -0,0 +1,204 @@
+//! Derive macros for kinnara, use them through the `kinnara` crate's
+//! `derive` feature rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericParam, LitInt, LitStr, Token};
+
+/// Implement `kinnara::PushConstant` for a `#[repr(C)]` struct with named
+/// fields. The struct must also be `bytemuck::Pod`, its bytes are handed to
+/// the pass as they are, and its field offsets are what kinnara checks
+/// against the shader's push constant block.
+#[proc_macro_derive(PushConstant)]
+pub fn derive_push_constant(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    push_constant(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn push_constant(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "PushConstant can't be derived for generic types",
+        ));
+    }
+
+    if !is_repr_c(input)? {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "PushConstant requires #[repr(C)], rust is free to reorder fields otherwise",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "PushConstant requires named fields to match against the shader",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "PushConstant can only be derived for structs",
+            ))
+        }
+    };
+
+    let layout = fields.iter().map(|field| {
+        let name = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let name_str = name.to_string();
+        quote! {
+            ::kinnara::PushConstantField {
+                name: #name_str,
+                offset: ::core::mem::offset_of!(#ident, #name) as u32,
+                size: ::core::mem::size_of::<#ty>() as u32,
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl ::kinnara::PushConstant for #ident {
+            fn bytes(&self) -> &[u8] {
+                ::kinnara::bytemuck::bytes_of(self)
+            }
+
+            fn fields(&self) -> &[::kinnara::PushConstantField] {
+                const FIELDS: &[::kinnara::PushConstantField] = &[#(#layout),*];
+                FIELDS
+            }
+        }
+    })
+}
+
+/// Implement `kinnara::Bindings` for a struct with named fields borrowing
+/// its resources for one lifetime. A field fills the binding given by
+/// `#[binding(set, binding)]`, the binding of the shader variable given by
+/// `#[binding(name = "...")]`, or the binding named like the field.
+///
+/// Fields may hold anything `kinnara::FieldResource` converts from:
+/// buffers, buffer bindings, texture views, samplers, and slices of them
+/// for binding arrays.
+#[proc_macro_derive(Bindings, attributes(binding))]
+pub fn derive_bindings(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    bindings(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn bindings(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    let mut params = input.generics.params.iter();
+    let lifetime = match (params.next(), params.next()) {
+        (Some(GenericParam::Lifetime(param)), None) => &param.lifetime,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "Bindings requires exactly one lifetime parameter, borrowed by the resources",
+            ))
+        }
+    };
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "Bindings requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "Bindings can only be derived for structs",
+            ))
+        }
+    };
+
+    let entries = fields
+        .iter()
+        .map(|field| {
+            let name = field.ident.as_ref().expect("named field");
+            let name_str = name.to_string();
+
+            let mut target = quote! { ::kinnara::BindingTarget::Name(#name_str) };
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("binding"))
+            {
+                target = attr.parse_args_with(binding_target)?;
+            }
+
+            Ok(quote! {
+                ::kinnara::BindingField {
+                    field: #name_str,
+                    target: #target,
+                    resource: ::kinnara::FieldResource::from(
+                        ::core::clone::Clone::clone(&self.#name),
+                    ),
+                }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl<#lifetime> ::kinnara::Bindings<#lifetime> for #ident<#lifetime> {
+            fn bindings(&self) -> ::std::vec::Vec<::kinnara::BindingField<#lifetime>> {
+                ::std::vec![#(#entries),*]
+            }
+        }
+    })
+}
+
+/// `set, binding` or `name = "..."`
+fn binding_target(input: syn::parse::ParseStream) -> syn::Result<TokenStream2> {
+    if input.peek(syn::Ident) {
+        let key: syn::Ident = input.parse()?;
+        if key != "name" {
+            return Err(syn::Error::new_spanned(key, "expected `name = \"...\"`"));
+        }
+        input.parse::<Token![=]>()?;
+        let name: LitStr = input.parse()?;
+        return Ok(quote! { ::kinnara::BindingTarget::Name(#name) });
+    }
+
+    let set: LitInt = input.parse()?;
+    input.parse::<Token![,]>()?;
+    let binding: LitInt = input.parse()?;
+    let (set, binding) = (set.base10_parse::<u32>()?, binding.base10_parse::<u32>()?);
+    Ok(quote! { ::kinnara::BindingTarget::Loc(#set, #binding) })
+}
+
+fn is_repr_c(input: &DeriveInput) -> syn::Result<bool> {
+    let mut repr_c = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        attr.parse_nested_meta(|meta| {
+            repr_c |= meta.path.is_ident("C");
+            // skip the arguments of `align(N)` and `packed(N)`
+            if meta.input.peek(syn::token::Paren) {
+                let _args;
+                syn::parenthesized!(_args in meta.input);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}
@@ -1,12 +1,22 @@
 mod naga_utils;
 pub mod requirements;
+mod resource_check;
+mod samplers;
+mod shared;
 
-use crate::preprocessing::{Directives, UniformHint};
+pub(crate) use resource_check::check_slot;
+pub use resource_check::{DescribedView, ViewInfo};
+pub use samplers::Samplers;
+pub(crate) use shared::{build_bind_group, Labels, Reflected};
+
+use crate::preprocessing::{Directives, SampleTypeHint, SamplerHint, TextureHint, UniformHint};
 use naga_utils::sample_kind;
+use std::ops::Range;
 use thiserror::Error;
 use wgpu::{
     naga::{
-        self, AddressSpace, FastHashMap, ResourceBinding, ShaderStage, StorageAccess, TypeInner,
+        self, AddressSpace, FastHashMap, FastHashSet, ResourceBinding, ShaderStage, StorageAccess,
+        TypeInner,
     },
     BindGroupLayoutEntry, BindingType, BufferBindingType, PushConstantRange, ShaderStages,
 };
@@ -15,6 +25,9 @@ pub struct BindingInfo {
     pub entry: BindGroupLayoutEntry,
     pub binding: naga::ResourceBinding,
     pub name: Option<String>,
+    pub label: Option<String>,
+    /// the hint samplers are created from, only set for single samplers
+    pub sampler: Option<SamplerHint>,
 }
 
 #[derive(Debug, Clone)]
@@ -22,12 +35,20 @@ struct EntryMetaData {
     set_idx: usize,
     entry_idx: usize,
     name: Option<String>,
+    label: Option<String>,
+    sampler: Option<SamplerHint>,
 }
 
 #[derive(Debug, Clone)]
 struct EntryPointMetaData {
     stage: ShaderStage,
     work_groups: Option<[u32; 3]>,
+    /// globals referenced from the entry point's call graph
+    globals: FastHashSet<naga::Handle<naga::GlobalVariable>>,
+    /// bindings in the entry point's own layouts, those it references
+    /// and those no entry point references
+    bindings: FastHashSet<ResourceBinding>,
+    push_constants: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -35,8 +56,10 @@ pub struct BindGroups {
     entry_map: FastHashMap<naga::ResourceBinding, EntryMetaData>,
     entry_points: FastHashMap<String, EntryPointMetaData>,
     bindings: Vec<Vec<wgpu::BindGroupLayoutEntry>>,
-    // TODO: give each stage present in the pipeline it's own section of the range
-    // if the user notes it. such a small portion of the use case...
+    /// the bytes of the push constants each stage reads
+    push_constant_spans: Vec<(ShaderStages, Range<u32>)>,
+    push_constant_size: u32,
+    /// one range per group of stages, see [`split_push_constants`]
     pub push_constant_range: Option<Vec<wgpu::PushConstantRange>>,
 }
 
@@ -58,6 +81,14 @@ pub enum BindGroupError {
     TooManyEntryPoints,
     #[error("Missing Bind Group Entry - Set: {0} Binding: {1}")]
     MissingBindGroupEntry(u32, u32),
+    #[error("No entry point named `{0}` in module")]
+    NoSuchEntryPoint(String),
+    #[error("Binding - Set: {0} Binding: {1} is declared differently in each stage")]
+    StageConflict(u32, u32),
+    #[error("Texture sample type hint {0:?} is incompatible with the shader's {1:?}")]
+    SampleTypeMismatch(SampleTypeHint, wgpu::TextureSampleType),
+    #[error("Texture sample count hint {0} needs a multisampled texture in the shader")]
+    SampleCountMismatch(u32),
 }
 
 impl BindGroups {
@@ -66,20 +97,15 @@ impl BindGroups {
             return Err(BindGroupError::NoEntryPoint);
         };
 
-        let mut visibility = ShaderStages::NONE;
+        let mut all_stages = ShaderStages::NONE;
         let mut entry_points = FastHashMap::default();
         let mut entry_map = FastHashMap::default();
         let mut bindings = Vec::new();
-        let mut push_constant_range: Option<Vec<_>> = None;
+        let mut push_constant_spans = vec![];
+        let mut push_constant_size = 0;
 
         for ep in module.entry_points.iter() {
-            // TODO: in the future, we should try to infer visibility from usage
-            // and hints, for now it will be all or nothing
-            match ep.stage {
-                naga::ShaderStage::Vertex => visibility |= ShaderStages::VERTEX,
-                naga::ShaderStage::Fragment => visibility |= ShaderStages::FRAGMENT,
-                naga::ShaderStage::Compute => visibility |= ShaderStages::COMPUTE,
-            };
+            all_stages |= naga_utils::stage_flags(ep.stage);
 
             let work_groups = if ep.workgroup_size.contains(&0) {
                 None
@@ -90,36 +116,220 @@ impl BindGroups {
             let ep_meta = EntryPointMetaData {
                 stage: ep.stage,
                 work_groups,
+                globals: naga_utils::used_globals(module, ep),
+                bindings: FastHashSet::default(),
+                push_constants: false,
             };
 
             entry_points.insert(ep.name.clone(), ep_meta);
         }
 
-        for (_, global) in module.global_variables.iter() {
+        for (handle, global) in module.global_variables.iter() {
+            let visibility = entry_points
+                .values()
+                .filter(|ep| ep.globals.contains(&handle))
+                .fold(ShaderStages::NONE, |stages, ep| {
+                    stages | naga_utils::stage_flags(ep.stage)
+                });
+            // bindings no entry point references stay visible to every stage
+            let visibility = if visibility.is_empty() {
+                all_stages
+            } else {
+                visibility
+            };
+
             match GlobalVar::process_global_var(directives, module, global, visibility)? {
-                Some(GlobalVar::PushConstant(pc)) => match push_constant_range.as_mut() {
-                    Some(v) => v.push(pc),
-                    None => push_constant_range = Some(vec![pc]),
-                },
-                Some(GlobalVar::Uniform(uniform)) => {
+                Some(GlobalVar::PushConstant(pc)) => {
+                    push_constant_size = push_constant_size.max(pc.range.end);
+
+                    let users = module
+                        .entry_points
+                        .iter()
+                        .filter(|ep| entry_points[&ep.name].globals.contains(&handle));
+                    let mut used = false;
+                    for ep in users {
+                        if let Some(span) = naga_utils::used_span(module, ep, handle) {
+                            let stages = naga_utils::stage_flags(ep.stage);
+                            add_span(&mut push_constant_spans, stages, span);
+                            used = true;
+                        }
+                    }
+                    if !used {
+                        add_span(&mut push_constant_spans, pc.stages, pc.range);
+                    }
+                }
+                Some(GlobalVar::Uniform(mut uniform)) => {
+                    let names = || naga_utils::global_names(module, global);
+                    uniform.label = directives.get_binding_label(names()).map(str::to_owned);
+                    if matches!(uniform.entry.ty, BindingType::Sampler(_))
+                        && uniform.entry.count.is_none()
+                    {
+                        uniform.sampler = Some(directives.get_sampler_hint(names()));
+                    }
                     update_entry_map(uniform, &mut bindings, &mut entry_map)
                 }
                 None => {}
             }
         }
 
+        let unreferenced: Vec<_> = module
+            .global_variables
+            .iter()
+            .filter(|(handle, _)| !entry_points.values().any(|ep| ep.globals.contains(handle)))
+            .map(|(_, global)| global)
+            .collect();
+
+        for ep in entry_points.values_mut() {
+            let globals = ep
+                .globals
+                .iter()
+                .map(|handle| &module.global_variables[*handle])
+                .chain(unreferenced.iter().copied());
+
+            for global in globals {
+                match &global.binding {
+                    Some(binding) => {
+                        ep.bindings.insert(binding.clone());
+                    }
+                    None => ep.push_constants |= global.space == AddressSpace::PushConstant,
+                }
+            }
+        }
+
+        let push_constant_range = (!push_constant_spans.is_empty())
+            .then(|| split_push_constants(push_constant_size, &push_constant_spans));
+
         Ok(Self {
             bindings,
             entry_map,
             entry_points,
+            push_constant_spans,
+            push_constant_size,
             push_constant_range,
         })
     }
 
+    /// Merge the bindings of another stage's module into these, bindings
+    /// declared by both must agree and are made visible to both stages.
+    /// Entry points of the same name are merged into one.
+    pub fn merge(&mut self, other: BindGroups) -> Result<(), BindGroupError> {
+        for (binding, meta_data) in other.entry_map {
+            let entry = other.bindings[meta_data.set_idx][meta_data.entry_idx];
+
+            let Some(existing) = self.entry_map.get_mut(&binding) else {
+                let info = BindingInfo {
+                    entry,
+                    binding,
+                    name: meta_data.name,
+                    label: meta_data.label,
+                    sampler: meta_data.sampler,
+                };
+                update_entry_map(info, &mut self.bindings, &mut self.entry_map);
+                continue;
+            };
+
+            let merged = &mut self.bindings[existing.set_idx][existing.entry_idx];
+            if merged.ty != entry.ty || merged.count != entry.count {
+                return Err(BindGroupError::StageConflict(
+                    binding.group,
+                    binding.binding,
+                ));
+            }
+            merged.visibility |= entry.visibility;
+
+            existing.name = existing.name.take().or(meta_data.name);
+            existing.label = existing.label.take().or(meta_data.label);
+            existing.sampler = existing.sampler.or(meta_data.sampler);
+        }
+
+        for (stages, span) in other.push_constant_spans {
+            add_span(&mut self.push_constant_spans, stages, span);
+        }
+        self.push_constant_size = self.push_constant_size.max(other.push_constant_size);
+        if !self.push_constant_spans.is_empty() {
+            self.push_constant_range = Some(split_push_constants(
+                self.push_constant_size,
+                &self.push_constant_spans,
+            ));
+        }
+
+        for (name, ep) in other.entry_points {
+            match self.entry_points.get_mut(&name) {
+                Some(existing) => {
+                    existing.bindings.extend(ep.bindings);
+                    existing.push_constants |= ep.push_constants;
+                }
+                None => {
+                    self.entry_points.insert(name, ep);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn entry_points(&self) -> impl Iterator<Item = &String> {
         self.entry_points.keys()
     }
 
+    fn entry_point(&self, entry_point: &str) -> Result<&EntryPointMetaData, BindGroupError> {
+        self.entry_points
+            .get(entry_point)
+            .ok_or_else(|| BindGroupError::NoSuchEntryPoint(entry_point.to_owned()))
+    }
+
+    /// The layout entries of `set` used by an entry point, or every entry
+    /// of the set if it is `shared`.
+    pub fn entry_point_layout_entries(
+        &self,
+        entry_point: &str,
+        set: u32,
+        shared: bool,
+    ) -> Result<Vec<BindGroupLayoutEntry>, BindGroupError> {
+        let ep = self.entry_point(entry_point)?;
+        let entries = self.get_bind_group_layout_entries(set);
+
+        Ok(entries
+            .iter()
+            .filter(|entry| {
+                shared
+                    || ep.bindings.contains(&ResourceBinding {
+                        group: set,
+                        binding: entry.binding,
+                    })
+            })
+            .copied()
+            .collect())
+    }
+
+    /// Number of bind group layouts in an entry point's pipeline layout,
+    /// shared sets are always included.
+    pub fn entry_point_set_count(
+        &self,
+        entry_point: &str,
+        shared_sets: &[u32],
+    ) -> Result<usize, BindGroupError> {
+        let ep = self.entry_point(entry_point)?;
+        let shared = shared_sets
+            .iter()
+            .filter(|set| self.bind_group_entries_count(**set) > 0);
+
+        Ok(ep
+            .bindings
+            .iter()
+            .map(|binding| binding.group)
+            .chain(shared.copied())
+            .max()
+            .map_or(0, |set| set as usize + 1))
+    }
+
+    pub fn entry_point_uses_push_constants(
+        &self,
+        entry_point: &str,
+    ) -> Result<bool, BindGroupError> {
+        Ok(self.entry_point(entry_point)?.push_constants)
+    }
+
     pub fn work_group_size(&self, entry_point: &str) -> Option<[u32; 3]> {
         self.entry_points
             .get(entry_point)
@@ -169,6 +379,67 @@ impl BindGroups {
             .get(&binding)
             .map(|meta_data| self.bindings[meta_data.set_idx][meta_data.entry_idx])
     }
+
+    /// The shader variable bound at `(set, binding)`.
+    pub fn binding_name(&self, set: u32, binding: u32) -> Option<&str> {
+        let binding = ResourceBinding {
+            group: set,
+            binding,
+        };
+
+        self.entry_map
+            .get(&binding)
+            .and_then(|meta_data| meta_data.name.as_deref())
+    }
+
+    /// The `(set, binding)` of the shader variable called `name`.
+    pub fn find_binding(&self, name: &str) -> Option<(u32, u32)> {
+        self.entry_map
+            .iter()
+            .find(|(_, meta_data)| meta_data.name.as_deref() == Some(name))
+            .map(|(binding, _)| (binding.group, binding.binding))
+    }
+
+    /// The label pragma given to a binding, falling back to its variable name.
+    pub fn binding_label(&self, set: u32, binding: u32) -> Option<&str> {
+        let binding = ResourceBinding {
+            group: set,
+            binding,
+        };
+
+        self.entry_map
+            .get(&binding)
+            .and_then(|meta_data| meta_data.label.as_ref().or(meta_data.name.as_ref()))
+            .map(String::as_str)
+    }
+
+    /// The descriptor of the sampler created for a single sampler binding.
+    pub fn sampler_descriptor(
+        &self,
+        set: u32,
+        binding: u32,
+    ) -> Option<wgpu::SamplerDescriptor<'static>> {
+        let entry = self.get_bind_group_layout_entry(set, binding)?;
+        let comparison = matches!(
+            entry.ty,
+            BindingType::Sampler(wgpu::SamplerBindingType::Comparison)
+        );
+
+        let meta_data = self.entry_map.get(&ResourceBinding {
+            group: set,
+            binding,
+        })?;
+        meta_data
+            .sampler
+            .map(|hint| hint.descriptor(None, comparison))
+    }
+
+    /// Every sampler binding kinnara can create a sampler for, with its hint.
+    pub fn sampler_hints(&self) -> impl Iterator<Item = (&ResourceBinding, &SamplerHint)> {
+        self.entry_map
+            .iter()
+            .filter_map(|(binding, meta_data)| Some((binding, meta_data.sampler.as_ref()?)))
+    }
 }
 
 enum GlobalVar {
@@ -186,29 +457,32 @@ impl GlobalVar {
         match global.space {
             AddressSpace::Function | AddressSpace::Private | AddressSpace::WorkGroup => Ok(None),
             AddressSpace::Uniform => {
-                let Some(binding) = global.binding.as_ref() else {
+                if global.binding.is_none() {
                     return Ok(None);
                 };
 
-                let uniform_hint = directives.get_uniform_hint(binding);
+                let uniform_hint =
+                    directives.get_uniform_hint(naga_utils::global_names(module, global));
                 let binding = infer_uniform_binding_type(module, global, &uniform_hint);
                 Ok(Self::new_uniform(binding, global, module, visibility))
             }
             AddressSpace::Storage { access } => {
-                let Some(binding) = global.binding.as_ref() else {
+                if global.binding.is_none() {
                     return Ok(None);
                 };
 
-                let uniform_hint = directives.get_uniform_hint(binding);
+                let uniform_hint =
+                    directives.get_uniform_hint(naga_utils::global_names(module, global));
                 let binding = infer_storage_binding_type(access, uniform_hint, module, &global.ty)?;
                 Ok(Self::new_uniform(binding, global, module, visibility))
             }
             AddressSpace::Handle => {
-                let Some(binding) = global.binding.as_ref() else {
+                if global.binding.is_none() {
                     return Ok(None);
                 };
 
-                let binding = infer_handle_binding_type(directives, binding, module, &global.ty)?;
+                let names: Vec<_> = naga_utils::global_names(module, global).collect();
+                let binding = infer_handle_binding_type(directives, &names, module, &global.ty)?;
                 Ok(Self::new_uniform(binding, global, module, visibility))
             }
             AddressSpace::PushConstant => {
@@ -235,7 +509,12 @@ impl GlobalVar {
             Some(Self::Uniform(BindingInfo {
                 entry,
                 binding,
-                name: global.name.clone(),
+                // anonymous glsl blocks are known by their block name
+                name: naga_utils::global_names(module, global)
+                    .next()
+                    .map(str::to_owned),
+                label: None,
+                sampler: None,
             }))
         } else {
             None
@@ -243,6 +522,63 @@ impl GlobalVar {
     }
 }
 
+/// Widen the span of `stages`, or add it.
+fn add_span(spans: &mut Vec<(ShaderStages, Range<u32>)>, stages: ShaderStages, span: Range<u32>) {
+    match spans.iter_mut().find(|(s, _)| *s == stages) {
+        Some((_, existing)) => {
+            *existing = existing.start.min(span.start)..existing.end.max(span.end)
+        }
+        None => spans.push((stages, span)),
+    }
+}
+
+/// Give each stage its own section of the push constants. wgpu allows a stage
+/// in only one range, so stages reading overlapping bytes share a range. The
+/// ranges are widened to cover the whole block, each starting where the
+/// bytes its stages read begin.
+fn split_push_constants(size: u32, spans: &[(ShaderStages, Range<u32>)]) -> Vec<PushConstantRange> {
+    let mut groups = spans.to_vec();
+
+    // merge until no two groups overlap or share a stage
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..groups.len() {
+            for j in i + 1..groups.len() {
+                let (a, b) = (&groups[i], &groups[j]);
+                if a.0.intersects(b.0) || (a.1.start < b.1.end && b.1.start < a.1.end) {
+                    let (stages, span) = groups.remove(j);
+                    let group = &mut groups[i];
+                    group.0 |= stages;
+                    group.1 = group.1.start.min(span.start)..group.1.end.max(span.end);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    groups.sort_by_key(|(_, span)| span.start);
+
+    let align = wgpu::PUSH_CONSTANT_ALIGNMENT;
+    let mut ranges: Vec<PushConstantRange> = vec![];
+    for (stages, span) in groups {
+        let start = match ranges.last_mut() {
+            Some(previous) => {
+                previous.range.end = span.start / align * align;
+                previous.range.end
+            }
+            None => 0,
+        };
+        ranges.push(PushConstantRange {
+            stages,
+            range: start..size.next_multiple_of(align),
+        });
+    }
+
+    ranges
+}
+
 fn update_entry_map(
     info: BindingInfo,
     bindings: &mut Vec<Vec<wgpu::BindGroupLayoutEntry>>,
@@ -252,6 +588,8 @@ fn update_entry_map(
         entry,
         binding,
         name,
+        label,
+        sampler,
     } = info;
 
     let needed_len = (binding.group + 1) as usize;
@@ -269,6 +607,8 @@ fn update_entry_map(
             set_idx: binding.group as _,
             entry_idx,
             name,
+            label,
+            sampler,
         },
     );
 }
@@ -342,7 +682,7 @@ fn infer_storage_binding_type(
 
 fn infer_handle_binding_type(
     directives: &Directives,
-    binding: &naga::ResourceBinding,
+    names: &[&str],
     module: &naga::Module,
     ty: &naga::Handle<naga::Type>,
 ) -> Result<BindingType, BindGroupError> {
@@ -353,7 +693,7 @@ fn infer_handle_binding_type(
 
     match type_actual.inner {
         TypeInner::Sampler { comparison } => {
-            let sampler_hint = directives.get_sampler_hint(binding);
+            let sampler_hint = directives.get_sampler_hint(names.iter().copied());
             if comparison {
                 Ok(BindingType::Sampler(wgpu::SamplerBindingType::Comparison))
             } else {
@@ -367,30 +707,74 @@ fn infer_handle_binding_type(
                 }
             }
         }
-        TypeInner::Image { dim, class, .. } => match class {
-            naga::ImageClass::Sampled { kind, multi } => Ok(BindingType::Texture {
-                view_dimension: naga_utils::image_dim(&dim),
-                sample_type: sample_kind(&kind),
-                multisampled: multi,
-            }),
-            naga::ImageClass::Depth { multi } => Ok(BindingType::Texture {
-                view_dimension: naga_utils::image_dim(&dim),
-                sample_type: wgpu::TextureSampleType::Depth,
-                multisampled: multi,
-            }),
-            naga::ImageClass::Storage { format, access } => Ok(BindingType::StorageTexture {
-                access: naga_utils::storage_access(&access),
-                format: naga_utils::texture_fmt(&format),
-                view_dimension: naga_utils::image_dim(&dim),
-            }),
-        },
+        TypeInner::Image { dim, class, .. } => {
+            let texture_hint = directives.get_texture_hint(names.iter().copied());
+            match class {
+                naga::ImageClass::Sampled { kind, multi } => {
+                    let multisampled = hinted_multisampling(multi, &texture_hint)?;
+                    // wgpu rejects filterable multisampled textures
+                    let filterable = texture_hint.filterable && !multisampled;
+                    let inferred = sample_kind(&kind, filterable);
+                    Ok(BindingType::Texture {
+                        view_dimension: naga_utils::image_dim(&dim),
+                        sample_type: hinted_sample_type(inferred, &texture_hint, filterable)?,
+                        multisampled,
+                    })
+                }
+                naga::ImageClass::Depth { multi } => {
+                    let multisampled = hinted_multisampling(multi, &texture_hint)?;
+                    let inferred = wgpu::TextureSampleType::Depth;
+                    Ok(BindingType::Texture {
+                        view_dimension: naga_utils::image_dim(&dim),
+                        sample_type: hinted_sample_type(inferred, &texture_hint, false)?,
+                        multisampled,
+                    })
+                }
+                naga::ImageClass::Storage { format, access } => Ok(BindingType::StorageTexture {
+                    access: naga_utils::storage_access(&access),
+                    format: naga_utils::texture_fmt(&format),
+                    view_dimension: naga_utils::image_dim(&dim),
+                }),
+            }
+        }
         TypeInner::BindingArray { base, .. } => {
-            infer_handle_binding_type(directives, binding, module, &base)
+            infer_handle_binding_type(directives, names, module, &base)
         }
         _ => Err(BindGroupError::UnexpectedType),
     }
 }
 
+/// The shader decides whether a texture is multisampled, a `sample_count`
+/// hint may only restate it.
+fn hinted_multisampling(multi: bool, hint: &TextureHint) -> Result<bool, BindGroupError> {
+    match (multi, hint.sample_count) {
+        (false, count) if count > 1 => Err(BindGroupError::SampleCountMismatch(count)),
+        _ => Ok(multi),
+    }
+}
+
+/// Apply a `sample_type` override, float and depth textures may be
+/// rebound as one another but integer textures must match the shader.
+fn hinted_sample_type(
+    inferred: wgpu::TextureSampleType,
+    hint: &TextureHint,
+    filterable: bool,
+) -> Result<wgpu::TextureSampleType, BindGroupError> {
+    use wgpu::TextureSampleType as Ty;
+
+    let Some(hinted) = hint.sample_type else {
+        return Ok(inferred);
+    };
+
+    match (hinted, inferred) {
+        (SampleTypeHint::Float, Ty::Float { .. } | Ty::Depth) => Ok(Ty::Float { filterable }),
+        (SampleTypeHint::Depth, Ty::Float { .. } | Ty::Depth) => Ok(Ty::Depth),
+        (SampleTypeHint::Uint, Ty::Uint) => Ok(Ty::Uint),
+        (SampleTypeHint::Sint, Ty::Sint) => Ok(Ty::Sint),
+        (hinted, inferred) => Err(BindGroupError::SampleTypeMismatch(hinted, inferred)),
+    }
+}
+
 fn push_constant_ranges(
     stages: ShaderStages,
     module: &naga::Module,
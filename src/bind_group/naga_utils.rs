@@ -14,6 +14,202 @@ pub fn type_size(module: &Module, ty: Handle<Type>) -> Option<std::num::NonZeroU
     std::num::NonZeroU64::new(size as u64)
 }
 
+/// Every name a global may be referred to by in directives:
+/// the variable name, followed by the name of its type (for
+/// GLSL interface blocks this is the block name).
+pub fn global_names<'a>(
+    module: &'a Module,
+    global: &'a GlobalVariable,
+) -> impl Iterator<Item = &'a str> {
+    let type_name = module
+        .types
+        .get_handle(global.ty)
+        .ok()
+        .and_then(|ty| ty.name.as_deref());
+
+    global.name.as_deref().into_iter().chain(type_name)
+}
+
+/// Every global referenced by an entry point, directly or
+/// through the functions it calls.
+pub fn used_globals(
+    module: &Module,
+    entry_point: &EntryPoint,
+) -> FastHashSet<Handle<GlobalVariable>> {
+    reachable_functions(module, entry_point)
+        .into_iter()
+        .flat_map(|function| function.expressions.iter())
+        .filter_map(|(_, expr)| match expr {
+            Expression::GlobalVariable(global) => Some(*global),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The bytes of a global an entry point reads, narrowed to the members
+/// it accesses for structs. `None` if the entry point doesn't use it.
+pub fn used_span(
+    module: &Module,
+    entry_point: &EntryPoint,
+    global: Handle<GlobalVariable>,
+) -> Option<std::ops::Range<u32>> {
+    let inner = &module.types[module.global_variables[global].ty].inner;
+    let whole = 0..inner.size(module.to_ctx());
+    let members = match inner {
+        TypeInner::Struct { members, .. } => members.as_slice(),
+        _ => &[],
+    };
+
+    let mut span: Option<std::ops::Range<u32>> = None;
+    let mut extend = |range: std::ops::Range<u32>| {
+        span = Some(match span.take() {
+            Some(span) => span.start.min(range.start)..span.end.max(range.end),
+            None => range,
+        });
+    };
+    let member_span = |index: u32| match members.get(index as usize) {
+        Some(member) => {
+            let size = module.types[member.ty].inner.size(module.to_ctx());
+            member.offset..member.offset + size
+        }
+        None => whole.clone(),
+    };
+
+    for function in reachable_functions(module, entry_point) {
+        let refs: FastHashSet<_> = function
+            .expressions
+            .iter()
+            .filter(|(_, expr)| matches!(expr, Expression::GlobalVariable(g) if *g == global))
+            .map(|(handle, _)| handle)
+            .collect();
+        if refs.is_empty() {
+            continue;
+        }
+
+        let loads: FastHashSet<_> = function
+            .expressions
+            .iter()
+            .filter(
+                |(_, expr)| matches!(expr, Expression::Load { pointer } if refs.contains(pointer)),
+            )
+            .map(|(handle, _)| handle)
+            .collect();
+
+        // the glsl frontend emits a member access for every member of a
+        // block up front, only the ones something goes through count
+        let mut split_loads = FastHashSet::default();
+        for (handle, expr) in function.expressions.iter() {
+            match *expr {
+                Expression::AccessIndex { base, index }
+                    if refs.contains(&base) && is_pointer_used(function, handle) =>
+                {
+                    extend(member_span(index))
+                }
+                // a member of the loaded value
+                Expression::AccessIndex { base, index } if loads.contains(&base) => {
+                    split_loads.insert(base);
+                    extend(member_span(index))
+                }
+                Expression::Access { base, .. } if refs.contains(&base) => extend(whole.clone()),
+                Expression::ArrayLength(pointer) if refs.contains(&pointer) => {
+                    extend(whole.clone())
+                }
+                _ => {}
+            }
+        }
+
+        // the whole global is read at once
+        if loads.difference(&split_loads).next().is_some() {
+            extend(whole.clone());
+        }
+
+        let mut passed = false;
+        for_each_call(&function.body, &mut |_, arguments| {
+            passed |= arguments.iter().any(|arg| refs.contains(arg));
+        });
+        if passed {
+            extend(whole.clone());
+        }
+    }
+
+    span
+}
+
+/// The entry point's function and every function it calls.
+fn reachable_functions<'a>(module: &'a Module, entry_point: &'a EntryPoint) -> Vec<&'a Function> {
+    let mut reachable = vec![];
+    let mut visited = FastHashSet::default();
+    let mut pending = vec![&entry_point.function];
+
+    while let Some(function) = pending.pop() {
+        reachable.push(function);
+
+        for_each_call(&function.body, &mut |callee, _| {
+            if visited.insert(callee) {
+                pending.push(&module.functions[callee]);
+            }
+        });
+    }
+
+    reachable
+}
+
+/// Whether anything reads through a pointer expression.
+fn is_pointer_used(function: &Function, pointer: Handle<Expression>) -> bool {
+    let through_expression = function.expressions.iter().any(|(_, expr)| match *expr {
+        Expression::Load { pointer: p }
+        | Expression::Access { base: p, .. }
+        | Expression::AccessIndex { base: p, .. }
+        | Expression::ArrayLength(p) => p == pointer,
+        _ => false,
+    });
+
+    let mut passed = false;
+    for_each_call(&function.body, &mut |_, arguments| {
+        passed |= arguments.contains(&pointer);
+    });
+
+    through_expression || passed
+}
+
+/// Visit every call in a block with the callee and its arguments.
+fn for_each_call(block: &Block, visit: &mut impl FnMut(Handle<Function>, &[Handle<Expression>])) {
+    for statement in block.iter() {
+        match statement {
+            Statement::Call {
+                function,
+                arguments,
+                ..
+            } => visit(*function, arguments),
+            Statement::Block(body) => for_each_call(body, visit),
+            Statement::If { accept, reject, .. } => {
+                for_each_call(accept, visit);
+                for_each_call(reject, visit);
+            }
+            Statement::Switch { cases, .. } => {
+                for case in cases {
+                    for_each_call(&case.body, visit);
+                }
+            }
+            Statement::Loop {
+                body, continuing, ..
+            } => {
+                for_each_call(body, visit);
+                for_each_call(continuing, visit);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn stage_flags(stage: ShaderStage) -> wgpu::ShaderStages {
+    match stage {
+        ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+        ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+        ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+    }
+}
+
 /// Get array count of a type
 pub fn type_array_ct(module: &Module, ty: &Handle<Type>) -> Option<std::num::NonZeroU32> {
     let type_actual = module.types.get_handle(*ty).ok()?;
@@ -73,14 +269,18 @@ pub fn storage_access(access: &naga::StorageAccess) -> wgpu::StorageTextureAcces
     }
 }
 
-pub fn sample_kind(kind: &naga::ScalarKind) -> wgpu::TextureSampleType {
+/// naga doesn't know the format of sampled textures, so whether a float
+/// texture is filterable (R32Float is not, without a feature) comes from hints.
+pub fn sample_kind(kind: &naga::ScalarKind, filterable: bool) -> wgpu::TextureSampleType {
     match kind {
         naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
         naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
-        // TODO:error out here, f32 textures shouldn't be filterable
-        naga::ScalarKind::Float => wgpu::TextureSampleType::Float { filterable: true },
+        // frontends concretize abstract types before they reach images
+        naga::ScalarKind::Float | ScalarKind::AbstractFloat => {
+            wgpu::TextureSampleType::Float { filterable }
+        }
         naga::ScalarKind::Bool => wgpu::TextureSampleType::Uint,
-        ScalarKind::AbstractFloat | ScalarKind::AbstractInt => unreachable!(),
+        ScalarKind::AbstractInt => wgpu::TextureSampleType::Sint,
     }
 }
 
@@ -1,8 +1,11 @@
 use std::num::NonZeroU32;
 
 use std::cell::RefCell;
+use std::fmt;
 use wgpu::{BindGroupLayoutEntry, BindingResource, PushConstantRange};
 
+use super::resource_check::{DescribedView, ViewInfo};
+
 pub enum PassSlot<'a> {
     DynamicOffset {
         loc: (u32, u32),
@@ -13,6 +16,333 @@ pub enum PassSlot<'a> {
         range: std::ops::Range<u32>,
         buffer: RefCell<Option<&'a [u8]>>,
     },
+    ColorAttachment {
+        location: u32,
+        /// the format of the pipeline's color target at `location`
+        format: wgpu::TextureFormat,
+        attachment: RefCell<Option<wgpu::RenderPassColorAttachment<'a>>>,
+    },
+    DepthStencilAttachment {
+        format: wgpu::TextureFormat,
+        attachment: RefCell<Option<wgpu::RenderPassDepthStencilAttachment<'a>>>,
+    },
+    VertexBuffer {
+        slot: u32,
+        buffer: RefCell<Option<wgpu::BufferSlice<'a>>>,
+    },
+    /// optional, only needed for indexed draws
+    IndexBuffer {
+        buffer: RefCell<Option<(wgpu::BufferSlice<'a>, wgpu::IndexFormat)>>,
+    },
+}
+
+/// A pass slot the pass function left empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingPassSlot {
+    DynamicOffset {
+        loc: (u32, u32),
+        /// the shader variable bound there
+        name: Option<String>,
+    },
+    PushConstants(wgpu::ShaderStages),
+    /// the push constants given don't cover the whole range
+    PushConstantSize {
+        stages: wgpu::ShaderStages,
+        expected: u32,
+        found: usize,
+    },
+    ColorAttachment {
+        location: u32,
+        /// the fragment output written there
+        name: Option<String>,
+        format: wgpu::TextureFormat,
+    },
+    DepthStencilAttachment,
+    VertexBuffer {
+        slot: u32,
+        /// the vertex inputs read from the buffer, with their attributes
+        inputs: Vec<(Option<String>, wgpu::VertexAttribute)>,
+    },
+}
+
+impl fmt::Display for MissingPassSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DynamicOffset { loc, name } => {
+                write!(
+                    f,
+                    "a dynamic offset for {}",
+                    describe_loc(*loc, name.as_deref())
+                )
+            }
+            Self::PushConstants(stages) => write!(f, "push constants for {stages:?}"),
+            Self::PushConstantSize {
+                stages,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{expected} bytes of push constants for {stages:?}, {found} were given"
+            ),
+            Self::ColorAttachment {
+                location,
+                name,
+                format,
+            } => {
+                write!(f, "a {format:?} color attachment for location {location}")?;
+                match name {
+                    Some(name) => write!(f, " (`{name}`)"),
+                    None => Ok(()),
+                }
+            }
+            Self::DepthStencilAttachment => write!(f, "the depth stencil attachment"),
+            Self::VertexBuffer { slot, inputs } => {
+                let inputs: Vec<_> = inputs
+                    .iter()
+                    .map(|(name, attribute)| {
+                        let (format, location) = (attribute.format, attribute.shader_location);
+                        match name {
+                            Some(name) => format!("`{name}` as {format:?} at location {location}"),
+                            None => format!("{format:?} at location {location}"),
+                        }
+                    })
+                    .collect();
+                write!(f, "vertex buffer {slot} with {}", inputs.join(", "))
+            }
+        }
+    }
+}
+
+impl MissingPassSlot {
+    /// The bytes the pass function gave the push constant `range`, which
+    /// must cover all of it.
+    pub(crate) fn push_constants<'a>(
+        range: &PushConstantRange,
+        slot: PassSlot<'a>,
+    ) -> Result<(u32, &'a [u8]), Self> {
+        let expected = range.range.len() as u32;
+        match slot.push_const_slice() {
+            None => Err(Self::PushConstants(range.stages)),
+            Some((_, data)) if data.len() != expected as usize => Err(Self::PushConstantSize {
+                stages: range.stages,
+                expected,
+                found: data.len(),
+            }),
+            Some(pc) => Ok(pc),
+        }
+    }
+}
+
+/// What is wrong with a binding after the bind function ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingProblem {
+    /// nothing was bound
+    Missing,
+    /// the field of a [`crate::Bindings`] type holds the wrong kind of resource
+    WrongKind {
+        field: &'static str,
+        found: &'static str,
+    },
+    /// a binding array was given the wrong number of resources
+    WrongCount {
+        found: usize,
+    },
+    /// kinnara can't bind resources of this type
+    Unsupported,
+    /// the buffer wasn't created with the usage the binding needs
+    BufferUsage {
+        missing: wgpu::BufferUsages,
+    },
+    /// the viewed texture wasn't created with the usage the binding needs
+    TextureUsage {
+        missing: wgpu::TextureUsages,
+    },
+    /// the offset isn't a multiple of the device's offset alignment
+    UnalignedOffset {
+        offset: u64,
+        alignment: u32,
+    },
+    /// storage bindings must be a multiple of 4 bytes
+    UnalignedSize {
+        size: u64,
+    },
+    /// the bound range runs past the end of the buffer
+    OutOfBounds {
+        offset: u64,
+        size: u64,
+        buffer_size: u64,
+    },
+    /// smaller than the binding's `min_binding_size`
+    TooSmall {
+        size: u64,
+        min: u64,
+    },
+    /// the view's dimension, format or sample count don't match
+    ViewDimension {
+        found: wgpu::TextureViewDimension,
+    },
+    Format {
+        found: wgpu::TextureFormat,
+    },
+    SampleCount {
+        found: u32,
+    },
+}
+
+/// A binding which couldn't be bound, with what the shader expects there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingIssue {
+    pub loc: (u32, u32),
+    /// the shader variable bound there
+    pub name: Option<String>,
+    pub ty: wgpu::BindingType,
+    /// the element count of binding arrays
+    pub count: Option<u32>,
+    pub problem: BindingProblem,
+}
+
+impl BindingIssue {
+    pub(crate) fn new(
+        set: u32,
+        entry: &BindGroupLayoutEntry,
+        name: Option<&str>,
+        problem: BindingProblem,
+    ) -> Self {
+        Self {
+            loc: (set, entry.binding),
+            name: name.map(str::to_owned),
+            ty: entry.ty,
+            count: entry.count.map(NonZeroU32::get),
+            problem,
+        }
+    }
+
+    /// The expected resource, like "an array of 4 filtering samplers".
+    fn expected(&self) -> String {
+        let ty = describe_binding_type(&self.ty);
+        match self.count {
+            Some(count) => format!("an array of {count} {ty}s"),
+            None if ty.starts_with(['a', 'e', 'i', 'o']) => format!("an {ty}"),
+            None => format!("a {ty}"),
+        }
+    }
+}
+
+impl fmt::Display for BindingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let loc = describe_loc(self.loc, self.name.as_deref());
+        let expected = self.expected();
+        match &self.problem {
+            BindingProblem::Missing => write!(f, "{loc} expects {expected}, nothing was bound"),
+            BindingProblem::WrongKind { field, found } => {
+                write!(f, "{loc} expects {expected}, field `{field}` holds {found}")
+            }
+            BindingProblem::WrongCount { found } => {
+                write!(f, "{loc} expects {expected}, {found} were bound")
+            }
+            BindingProblem::Unsupported => {
+                write!(f, "{loc} expects {expected}, which kinnara can't bind")
+            }
+            BindingProblem::BufferUsage { missing } => {
+                write!(f, "{loc} expects {expected}, the buffer lacks {missing:?} usage")
+            }
+            BindingProblem::TextureUsage { missing } => {
+                write!(f, "{loc} expects {expected}, the texture lacks {missing:?} usage")
+            }
+            BindingProblem::UnalignedOffset { offset, alignment } => write!(
+                f,
+                "{loc} expects {expected}, offset {offset} is not a multiple of {alignment}"
+            ),
+            BindingProblem::UnalignedSize { size } => write!(
+                f,
+                "{loc} expects {expected}, {size} bytes were bound, not a multiple of 4"
+            ),
+            BindingProblem::OutOfBounds {
+                offset,
+                size,
+                buffer_size,
+            } => write!(
+                f,
+                "{loc} expects {expected}, {offset}..{} is outside the buffer of {buffer_size} bytes",
+                offset.saturating_add(*size)
+            ),
+            BindingProblem::TooSmall { size, min } => write!(
+                f,
+                "{loc} expects {expected} of at least {min} bytes, {size} were bound"
+            ),
+            BindingProblem::ViewDimension { found } => {
+                write!(f, "{loc} expects {expected}, the view is {found:?}")
+            }
+            BindingProblem::Format { found } => {
+                write!(f, "{loc} expects {expected}, the view is {found:?}")
+            }
+            BindingProblem::SampleCount { found } => write!(
+                f,
+                "{loc} expects {expected}, the view has {found} samples"
+            ),
+        }
+    }
+}
+
+/// "set 0 binding 1 (`lights`)"
+fn describe_loc((set, binding): (u32, u32), name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("set {set} binding {binding} (`{name}`)"),
+        None => format!("set {set} binding {binding}"),
+    }
+}
+
+fn describe_binding_type(ty: &wgpu::BindingType) -> String {
+    use wgpu::{BindingType, BufferBindingType, SamplerBindingType, StorageTextureAccess};
+
+    match ty {
+        BindingType::Buffer { ty, .. } => match ty {
+            BufferBindingType::Uniform => "uniform buffer".to_owned(),
+            BufferBindingType::Storage { read_only: true } => "read-only storage buffer".to_owned(),
+            BufferBindingType::Storage { read_only: false } => "storage buffer".to_owned(),
+        },
+        BindingType::Sampler(ty) => match ty {
+            SamplerBindingType::Filtering => "filtering sampler".to_owned(),
+            SamplerBindingType::NonFiltering => "non-filtering sampler".to_owned(),
+            SamplerBindingType::Comparison => "comparison sampler".to_owned(),
+        },
+        BindingType::Texture {
+            sample_type,
+            view_dimension,
+            multisampled,
+        } => {
+            let sample_type = match sample_type {
+                wgpu::TextureSampleType::Float { .. } => "float",
+                wgpu::TextureSampleType::Depth => "depth",
+                wgpu::TextureSampleType::Sint => "sint",
+                wgpu::TextureSampleType::Uint => "uint",
+            };
+            let multisampled = if *multisampled { " multisampled" } else { "" };
+            format!("{view_dimension:?}{multisampled} {sample_type} texture")
+        }
+        BindingType::StorageTexture {
+            access,
+            format,
+            view_dimension,
+        } => {
+            let access = match access {
+                StorageTextureAccess::ReadOnly => "read-only",
+                StorageTextureAccess::WriteOnly => "write-only",
+                StorageTextureAccess::ReadWrite => "read-write",
+            };
+            format!("{access} {view_dimension:?} {format:?} storage texture")
+        }
+        BindingType::AccelerationStructure => "acceleration structure".to_owned(),
+    }
+}
+
+/// Render a list of issues one per line.
+pub(crate) fn render_all<T: fmt::Display>(issues: &[T]) -> String {
+    issues
+        .iter()
+        .map(|issue| format!("  {issue}"))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 impl<'a> From<&PushConstantRange> for PassSlot<'a> {
@@ -38,6 +368,19 @@ impl<'a> PassSlot<'a> {
         }
     }
 
+    /// Fill a push constant range from the bytes of the whole push constant
+    /// block, taking only the bytes of the range. Stages may be given
+    /// separate ranges, so this can be called for each of them with the
+    /// same block. A block too short for the range leaves it short, which
+    /// is reported when the pass is created.
+    pub fn fill_push_constants(&self, block: &'a [u8]) {
+        if let PassSlot::PushConstantRange { range, buffer, .. } = self {
+            let end = (range.end as usize).min(block.len());
+            let start = (range.start as usize).min(end);
+            buffer.replace(Some(&block[start..end]));
+        }
+    }
+
     pub fn offset_for(set: u32, binding: u32) -> Self {
         Self::DynamicOffset {
             loc: (set, binding),
@@ -51,61 +394,133 @@ impl<'a> PassSlot<'a> {
             _ => None,
         }
     }
+
+    pub fn color_attachment_for(location: u32, format: wgpu::TextureFormat) -> Self {
+        Self::ColorAttachment {
+            location,
+            format,
+            attachment: None.into(),
+        }
+    }
+
+    pub fn color_attachment(self) -> Option<wgpu::RenderPassColorAttachment<'a>> {
+        match self {
+            PassSlot::ColorAttachment { attachment, .. } => attachment.take(),
+            _ => None,
+        }
+    }
+
+    pub fn depth_stencil_attachment_for(format: wgpu::TextureFormat) -> Self {
+        Self::DepthStencilAttachment {
+            format,
+            attachment: None.into(),
+        }
+    }
+
+    pub fn depth_stencil_attachment(self) -> Option<wgpu::RenderPassDepthStencilAttachment<'a>> {
+        match self {
+            PassSlot::DepthStencilAttachment { attachment, .. } => attachment.take(),
+            _ => None,
+        }
+    }
+
+    pub fn vertex_buffer_for(slot: u32) -> Self {
+        Self::VertexBuffer {
+            slot,
+            buffer: None.into(),
+        }
+    }
+
+    pub fn vertex_buffer(self) -> Option<wgpu::BufferSlice<'a>> {
+        match self {
+            PassSlot::VertexBuffer { buffer, .. } => buffer.take(),
+            _ => None,
+        }
+    }
+
+    pub fn index_buffer_slot() -> Self {
+        Self::IndexBuffer {
+            buffer: None.into(),
+        }
+    }
+
+    pub fn index_buffer(self) -> Option<(wgpu::BufferSlice<'a>, wgpu::IndexFormat)> {
+        match self {
+            PassSlot::IndexBuffer { buffer } => buffer.take(),
+            _ => None,
+        }
+    }
 }
 
+/// A binding to be filled by the user, `name` is the shader variable
+/// bound there, or the block name for anonymous glsl blocks.
 #[derive(Debug)]
 pub enum BindSlot<'a> {
     StorageBuffer {
         loc: (u32, u32),
+        name: Option<String>,
         slot: RefCell<Option<wgpu::BufferBinding<'a>>>,
     },
     UniformBuffer {
         loc: (u32, u32),
+        name: Option<String>,
         slot: RefCell<Option<wgpu::BufferBinding<'a>>>,
     },
     StorageBufferArray {
         loc: (u32, u32),
+        name: Option<String>,
         slots: RefCell<Option<&'a [wgpu::BufferBinding<'a>]>>,
         entry_count: u32,
     },
     UniformBufferArray {
         loc: (u32, u32),
+        name: Option<String>,
         slots: RefCell<Option<&'a [wgpu::BufferBinding<'a>]>>,
         entry_count: u32,
     },
     Texture {
         loc: (u32, u32),
+        name: Option<String>,
         slot: RefCell<Option<&'a wgpu::TextureView>>,
+        /// set by [`BindSlot::fill_view`], the view is only checked
+        /// against the binding when it is known
+        view: RefCell<Option<ViewInfo>>,
     },
     TextureArray {
         loc: (u32, u32),
+        name: Option<String>,
         slots: RefCell<Option<&'a [&'a wgpu::TextureView]>>,
         entry_count: u32,
     },
     Sampler {
         loc: (u32, u32),
+        name: Option<String>,
         slot: RefCell<Option<&'a wgpu::Sampler>>,
     },
     SamplerArray {
         loc: (u32, u32),
+        name: Option<String>,
         slots: RefCell<Option<&'a [&'a wgpu::Sampler]>>,
         entry_count: u32,
     },
 }
 
 macro_rules! create_bind_slot {
-    ($fn_name:ident, $single:ident, $array:ident) => {
+    ($fn_name:ident, $single:ident, $array:ident $(, $extra:ident)*) => {
         #[inline(always)]
-        fn $fn_name(set: u32, binding: u32, ct: &Option<NonZeroU32>) -> Self {
+        fn $fn_name(set: u32, binding: u32, name: Option<String>, ct: &Option<NonZeroU32>) -> Self {
             match ct {
                 Some(ct) => Self::$array {
                     loc: (set, binding),
+                    name,
                     slots: None.into(),
                     entry_count: ct.get(),
                 },
                 None => Self::$single {
                     loc: (set, binding),
+                    name,
                     slot: None.into(),
+                    $($extra: None.into(),)*
                 },
             }
         }
@@ -113,29 +528,42 @@ macro_rules! create_bind_slot {
 }
 
 impl<'a> BindSlot<'a> {
-    pub fn from_entry(set: u32, entry: &BindGroupLayoutEntry) -> Self {
+    /// `None` for bindings kinnara has no slot for, acceleration structures.
+    pub fn from_entry(set: u32, entry: &BindGroupLayoutEntry, name: Option<&str>) -> Option<Self> {
         let BindGroupLayoutEntry {
             binding, ty, count, ..
         } = entry;
+        let name = name.map(str::to_owned);
 
-        match ty {
+        let slot = match ty {
             wgpu::BindingType::Buffer { ty, .. } => match ty {
-                wgpu::BufferBindingType::Uniform => Self::uniform_buf(set, *binding, count),
-                wgpu::BufferBindingType::Storage { .. } => Self::storage_buf(set, *binding, count),
+                wgpu::BufferBindingType::Uniform => Self::uniform_buf(set, *binding, name, count),
+                wgpu::BufferBindingType::Storage { .. } => {
+                    Self::storage_buf(set, *binding, name, count)
+                }
             },
-            wgpu::BindingType::Sampler(_) => Self::sampler(set, *binding, count),
-            wgpu::BindingType::Texture { .. } => Self::texture(set, *binding, count),
-            wgpu::BindingType::StorageTexture { .. } => Self::texture(set, *binding, count),
-            wgpu::BindingType::AccelerationStructure => {
-                todo!("I'm not sure if these are widely supported.")
-            }
-        }
+            wgpu::BindingType::Sampler(_) => Self::sampler(set, *binding, name, count),
+            wgpu::BindingType::Texture { .. } => Self::texture(set, *binding, name, count),
+            wgpu::BindingType::StorageTexture { .. } => Self::texture(set, *binding, name, count),
+            // not widely supported yet
+            wgpu::BindingType::AccelerationStructure => return None,
+        };
+        Some(slot)
     }
 
     create_bind_slot!(uniform_buf, UniformBuffer, UniformBufferArray);
     create_bind_slot!(storage_buf, StorageBuffer, StorageBufferArray);
     create_bind_slot!(sampler, Sampler, SamplerArray);
-    create_bind_slot!(texture, Texture, TextureArray);
+    create_bind_slot!(texture, Texture, TextureArray, view);
+
+    /// Fill a texture slot with a view whose [`ViewInfo`] is known, so
+    /// its format, dimension and sample count are checked before binding.
+    pub fn fill_view(&self, described: &'a DescribedView) {
+        if let Self::Texture { slot, view, .. } = self {
+            slot.replace(Some(&described.view));
+            view.replace(Some(described.info));
+        }
+    }
 
     pub fn binding(&self) -> u32 {
         match self {
@@ -150,6 +578,45 @@ impl<'a> BindSlot<'a> {
         }
     }
 
+    pub fn loc(&self) -> (u32, u32) {
+        match self {
+            Self::StorageBuffer { loc, .. }
+            | Self::UniformBuffer { loc, .. }
+            | Self::Texture { loc, .. }
+            | Self::Sampler { loc, .. }
+            | Self::StorageBufferArray { loc, .. }
+            | Self::UniformBufferArray { loc, .. }
+            | Self::TextureArray { loc, .. }
+            | Self::SamplerArray { loc, .. } => *loc,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::StorageBuffer { name, .. }
+            | Self::UniformBuffer { name, .. }
+            | Self::Texture { name, .. }
+            | Self::Sampler { name, .. }
+            | Self::StorageBufferArray { name, .. }
+            | Self::UniformBufferArray { name, .. }
+            | Self::TextureArray { name, .. }
+            | Self::SamplerArray { name, .. } => name.as_deref(),
+        }
+    }
+
+    /// How many resources a binding array slot was filled with,
+    /// `None` for empty and single slots.
+    pub fn filled_len(&self) -> Option<usize> {
+        match self {
+            Self::StorageBufferArray { slots, .. } | Self::UniformBufferArray { slots, .. } => {
+                slots.borrow().map(<[_]>::len)
+            }
+            Self::TextureArray { slots, .. } => slots.borrow().map(<[_]>::len),
+            Self::SamplerArray { slots, .. } => slots.borrow().map(<[_]>::len),
+            _ => None,
+        }
+    }
+
     pub fn is_some(&self) -> bool {
         match self {
             Self::StorageBuffer { slot, .. } | Self::UniformBuffer { slot, .. } => {
@@ -0,0 +1,228 @@
+//! Checking the resources given to a bind slot against its layout entry,
+//! so a wrong resource is reported with the binding it was meant for
+//! instead of failing inside wgpu.
+
+use std::num::NonZeroU64;
+
+use wgpu::{
+    BindGroupLayoutEntry, BindingType, BufferBindingType, BufferUsages, TextureSampleType,
+    TextureUsages, TextureViewDimension,
+};
+
+use super::requirements::{BindSlot, BindingProblem};
+
+/// What a texture view was created as, which wgpu views can't be asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewInfo {
+    pub format: wgpu::TextureFormat,
+    pub dimension: TextureViewDimension,
+    pub sample_count: u32,
+    /// the usage of the viewed texture
+    pub usage: TextureUsages,
+}
+
+impl ViewInfo {
+    /// The info of a view of `texture` created from `desc`, with its
+    /// defaults resolved like wgpu does.
+    pub fn new(texture: &wgpu::Texture, desc: &wgpu::TextureViewDescriptor) -> Self {
+        let format = desc
+            .format
+            .or_else(|| texture.format().aspect_specific_format(desc.aspect))
+            .unwrap_or(texture.format());
+
+        let dimension = desc.dimension.unwrap_or(match texture.dimension() {
+            wgpu::TextureDimension::D1 => TextureViewDimension::D1,
+            wgpu::TextureDimension::D2 if texture.depth_or_array_layers() == 1 => {
+                TextureViewDimension::D2
+            }
+            wgpu::TextureDimension::D2 => TextureViewDimension::D2Array,
+            wgpu::TextureDimension::D3 => TextureViewDimension::D3,
+        });
+
+        Self {
+            format,
+            dimension,
+            sample_count: texture.sample_count(),
+            usage: texture.usage(),
+        }
+    }
+}
+
+/// A texture view kept with its [`ViewInfo`], bind it with
+/// [`BindSlot::fill_view`] to have it checked.
+#[derive(Debug)]
+pub struct DescribedView {
+    pub view: wgpu::TextureView,
+    pub info: ViewInfo,
+}
+
+impl DescribedView {
+    pub fn new(texture: &wgpu::Texture, desc: &wgpu::TextureViewDescriptor) -> Self {
+        Self {
+            view: texture.create_view(desc),
+            info: ViewInfo::new(texture, desc),
+        }
+    }
+}
+
+/// Check what the bind function put in `slot` against `entry`. Empty
+/// slots pass, they are reported as missing later.
+pub(crate) fn check_slot(
+    slot: &BindSlot,
+    entry: &BindGroupLayoutEntry,
+    device: &wgpu::Device,
+) -> Result<(), BindingProblem> {
+    let limits = device.limits();
+
+    match (slot, &entry.ty) {
+        (
+            BindSlot::StorageBuffer { slot, .. } | BindSlot::UniformBuffer { slot, .. },
+            BindingType::Buffer {
+                ty,
+                min_binding_size,
+                ..
+            },
+        ) => {
+            if let Some(binding) = &*slot.borrow() {
+                check_buffer(binding, ty, *min_binding_size, &limits)?;
+            }
+        }
+        (
+            BindSlot::StorageBufferArray { slots, .. } | BindSlot::UniformBufferArray { slots, .. },
+            BindingType::Buffer {
+                ty,
+                min_binding_size,
+                ..
+            },
+        ) => {
+            for binding in slots.borrow().into_iter().flatten() {
+                check_buffer(binding, ty, *min_binding_size, &limits)?;
+            }
+        }
+        (BindSlot::Texture { view, .. }, ty) => {
+            if let Some(info) = &*view.borrow() {
+                check_view(info, ty, device.features())?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn check_buffer(
+    binding: &wgpu::BufferBinding,
+    ty: &BufferBindingType,
+    min_binding_size: Option<NonZeroU64>,
+    limits: &wgpu::Limits,
+) -> Result<(), BindingProblem> {
+    let (usage, alignment) = match ty {
+        BufferBindingType::Uniform => (
+            BufferUsages::UNIFORM,
+            limits.min_uniform_buffer_offset_alignment,
+        ),
+        BufferBindingType::Storage { .. } => (
+            BufferUsages::STORAGE,
+            limits.min_storage_buffer_offset_alignment,
+        ),
+    };
+
+    if !binding.buffer.usage().contains(usage) {
+        return Err(BindingProblem::BufferUsage { missing: usage });
+    }
+
+    let offset = binding.offset;
+    if !offset.is_multiple_of(u64::from(alignment)) {
+        return Err(BindingProblem::UnalignedOffset { offset, alignment });
+    }
+
+    let buffer_size = binding.buffer.size();
+    let size = match binding.size {
+        Some(size) => size.get(),
+        None => buffer_size.saturating_sub(offset),
+    };
+    if offset.checked_add(size).is_none_or(|end| end > buffer_size) {
+        return Err(BindingProblem::OutOfBounds {
+            offset,
+            size,
+            buffer_size,
+        });
+    }
+
+    // zero sized bindings are never valid
+    let min = min_binding_size.map_or(1, NonZeroU64::get);
+    if size < min {
+        return Err(BindingProblem::TooSmall { size, min });
+    }
+
+    if matches!(ty, BufferBindingType::Storage { .. }) && !size.is_multiple_of(4) {
+        return Err(BindingProblem::UnalignedSize { size });
+    }
+
+    Ok(())
+}
+
+fn check_view(
+    info: &ViewInfo,
+    ty: &BindingType,
+    features: wgpu::Features,
+) -> Result<(), BindingProblem> {
+    let (usage, dimension) = match ty {
+        BindingType::Texture { view_dimension, .. } => {
+            (TextureUsages::TEXTURE_BINDING, *view_dimension)
+        }
+        BindingType::StorageTexture { view_dimension, .. } => {
+            (TextureUsages::STORAGE_BINDING, *view_dimension)
+        }
+        _ => return Ok(()),
+    };
+
+    if !info.usage.contains(usage) {
+        return Err(BindingProblem::TextureUsage { missing: usage });
+    }
+    if info.dimension != dimension {
+        return Err(BindingProblem::ViewDimension {
+            found: info.dimension,
+        });
+    }
+
+    let (format_fits, multisampled) = match ty {
+        BindingType::Texture {
+            sample_type,
+            multisampled,
+            ..
+        } => {
+            let found = info.format.sample_type(None, Some(features));
+            (sample_type_fits(*sample_type, found), *multisampled)
+        }
+        BindingType::StorageTexture { format, .. } => (info.format == *format, false),
+        _ => return Ok(()),
+    };
+
+    if !format_fits {
+        return Err(BindingProblem::Format { found: info.format });
+    }
+    if multisampled != (info.sample_count > 1) {
+        return Err(BindingProblem::SampleCount {
+            found: info.sample_count,
+        });
+    }
+
+    Ok(())
+}
+
+/// Whether a view of a format sampling as `found` can be bound where
+/// `expected` is. Unfilterable float bindings take any float or depth
+/// format, the others need an exact match.
+fn sample_type_fits(expected: TextureSampleType, found: Option<TextureSampleType>) -> bool {
+    use TextureSampleType::*;
+
+    matches!(
+        (expected, found),
+        (Float { filterable: true }, Some(Float { filterable: true }))
+            | (Float { filterable: false }, Some(Float { .. } | Depth))
+            | (Depth, Some(Depth))
+            | (Sint, Some(Sint))
+            | (Uint, Some(Uint))
+    )
+}
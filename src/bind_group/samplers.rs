@@ -0,0 +1,33 @@
+use wgpu::naga::{FastHashMap, ResourceBinding};
+
+/// The samplers kinnara creates from sampler hints, bound automatically
+/// in place of the user supplying them.
+#[derive(Debug, Default)]
+pub struct Samplers {
+    samplers: FastHashMap<ResourceBinding, wgpu::Sampler>,
+}
+
+impl Samplers {
+    pub(crate) fn insert(&mut self, set: u32, binding: u32, sampler: wgpu::Sampler) {
+        let binding = ResourceBinding {
+            group: set,
+            binding,
+        };
+        self.samplers.insert(binding, sampler);
+    }
+
+    pub fn get(&self, set: u32, binding: u32) -> Option<&wgpu::Sampler> {
+        self.samplers.get(&ResourceBinding {
+            group: set,
+            binding,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.samplers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samplers.is_empty()
+    }
+}
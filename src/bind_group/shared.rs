@@ -0,0 +1,188 @@
+//! Labelling and bind group creation shared by the compute and render
+//! reflectors, both keep their merged layout in a [`BindGroups`].
+
+use wgpu::{BindGroupEntry, BindGroupLayoutDescriptor, ErrorFilter};
+
+use super::requirements::{BindSlot, BindingIssue, BindingProblem};
+use super::{check_slot, BindGroups, Samplers};
+use crate::{DeviceUtils, Error};
+
+/// The labels derived from a shader's `#pragma label`.
+#[derive(Debug, Clone)]
+pub(crate) struct Labels {
+    base: Option<String>,
+    // "{base}/set{n}" for each bind group, so layout descriptors can borrow them
+    sets: Vec<String>,
+}
+
+impl Labels {
+    pub fn new(base: Option<String>, bind_groups: &BindGroups) -> Self {
+        let sets = match &base {
+            Some(base) => (0..=bind_groups.bind_group_count())
+                .map(|set| format!("{base}/set{set}"))
+                .collect(),
+            None => vec![],
+        };
+        Self { base, sets }
+    }
+
+    pub fn base(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+
+    pub fn derived(&self, suffix: &str) -> Option<String> {
+        self.base.as_ref().map(|base| format!("{base}/{suffix}"))
+    }
+
+    pub fn set(&self, set: u32) -> Option<&str> {
+        self.sets.get(set as usize).map(String::as_str)
+    }
+}
+
+/// A reflector's bind groups with its labels.
+#[derive(Clone, Copy)]
+pub(crate) struct Reflected<'r> {
+    pub bind_groups: &'r BindGroups,
+    pub labels: &'r Labels,
+}
+
+impl<'r> Reflected<'r> {
+    pub fn binding_label(&self, set: u32, binding: u32) -> Option<String> {
+        let name = self.bind_groups.binding_label(set, binding)?;
+        match self.labels.base() {
+            Some(label) => Some(format!("{label}/{name}")),
+            None => Some(name.to_owned()),
+        }
+    }
+
+    pub fn binding_name(&self, set: u32, binding: u32) -> Option<&'r str> {
+        self.bind_groups.binding_name(set, binding)
+    }
+
+    pub fn find_binding(&self, name: &str) -> Option<(u32, u32)> {
+        self.bind_groups.find_binding(name)
+    }
+
+    pub fn create_samplers(&self, device: &wgpu::Device) -> Samplers {
+        let mut samplers = Samplers::default();
+
+        for (binding, _) in self.bind_groups.sampler_hints() {
+            let (set, binding) = (binding.group, binding.binding);
+            let label = self.binding_label(set, binding);
+            if let Some(desc) = self.bind_groups.sampler_descriptor(set, binding) {
+                let desc = wgpu::SamplerDescriptor {
+                    label: label.as_deref(),
+                    ..desc
+                };
+                samplers.insert(set, binding, device.create_sampler(&desc));
+            }
+        }
+
+        samplers
+    }
+
+    pub fn create_bind_group_with<'a, F>(
+        &self,
+        device: &wgpu::Device,
+        set: u32,
+        samplers: &Samplers,
+        func: F,
+    ) -> Result<wgpu::BindGroup, Error>
+    where
+        F: FnMut(&BindSlot<'a>),
+    {
+        build_bind_group(
+            device,
+            set,
+            self.labels.set(set),
+            self.bind_groups.get_bind_group_layout_entries(set),
+            self.bind_groups,
+            samplers,
+            func,
+        )
+    }
+}
+
+/// Create a bind group and its layout from `entries`, the layout is
+/// labelled like the group.
+pub(crate) fn build_bind_group<'a, F>(
+    device: &wgpu::Device,
+    set: u32,
+    label: Option<&str>,
+    entries: &[wgpu::BindGroupLayoutEntry],
+    bind_groups: &BindGroups,
+    samplers: &Samplers,
+    mut func: F,
+) -> Result<wgpu::BindGroup, Error>
+where
+    F: FnMut(&BindSlot<'a>),
+{
+    let mut good = vec![];
+    let mut issues = vec![];
+
+    for entry in entries {
+        // hinted samplers are never handed out, a sampler from `func` might
+        // not match the filtering or comparison of the layout entry
+        if let Some(sampler) = samplers.get(set, entry.binding) {
+            good.push(BindGroupEntry {
+                binding: entry.binding,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            });
+            continue;
+        }
+
+        let name = bind_groups.binding_name(set, entry.binding);
+        let issue = |problem| BindingIssue::new(set, entry, name, problem);
+        let Some(req) = BindSlot::from_entry(set, entry, name) else {
+            issues.push(issue(BindingProblem::Unsupported));
+            continue;
+        };
+
+        func(&req);
+        if let Some(found) = req.filled_len() {
+            if !array_len_fits(device.features(), entry.count, found) {
+                issues.push(issue(BindingProblem::WrongCount { found }));
+                continue;
+            }
+        }
+        if let Err(problem) = check_slot(&req, entry, device) {
+            issues.push(issue(problem));
+            continue;
+        }
+
+        match wgpu::BindingResource::try_from(req) {
+            Ok(resource) => good.push(BindGroupEntry {
+                binding: entry.binding,
+                resource,
+            }),
+            Err(_) => issues.push(issue(BindingProblem::Missing)),
+        }
+    }
+
+    if !issues.is_empty() {
+        return Err(Error::InvalidBindings(issues));
+    }
+
+    let group = device.wgpu_try(ErrorFilter::Validation, |dev| {
+        let layout = &dev.create_bind_group_layout(&BindGroupLayoutDescriptor { label, entries });
+        let desc = wgpu::BindGroupDescriptor {
+            label,
+            layout,
+            entries: good.as_slice(),
+        };
+        dev.create_bind_group(&desc)
+    })?;
+    Ok(group)
+}
+
+/// Whether `found` resources may fill a binding array of `count`, fewer
+/// than `count` needs PARTIALLY_BOUND_BINDING_ARRAY.
+fn array_len_fits(
+    features: wgpu::Features,
+    count: Option<std::num::NonZeroU32>,
+    found: usize,
+) -> bool {
+    let count = count.map_or(1, |count| count.get() as usize);
+    let partial = features.contains(wgpu::Features::PARTIALLY_BOUND_BINDING_ARRAY);
+    found != 0 && (found == count || (found < count && partial))
+}
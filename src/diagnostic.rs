@@ -0,0 +1,176 @@
+use std::{fmt, ops::Range};
+
+/// A position in the user's original source, after undoing
+/// any rewriting done by the preprocessor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    /// byte range within the file
+    pub span: Range<usize>,
+    /// the full text of the line the span starts on
+    pub line_text: String,
+}
+
+impl SourceLocation {
+    pub(crate) fn new(file: &str, text: &str, span: Range<usize>) -> Self {
+        let start = span.start.min(text.len());
+        let line_start = text[..start].rfind('\n').map_or(0, |pos| pos + 1);
+        let line_end = text[start..]
+            .find('\n')
+            .map_or(text.len(), |pos| start + pos);
+
+        Self {
+            file: file.to_owned(),
+            line: text[..start].matches('\n').count() + 1,
+            column: text[line_start..start].chars().count() + 1,
+            span: start..span.end.clamp(start, text.len()),
+            line_text: text[line_start..line_end].trim_end_matches('\r').to_owned(),
+        }
+    }
+}
+
+/// An error message pointing into shader source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub location: Option<SourceLocation>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, location: Option<SourceLocation>) -> Self {
+        Self {
+            message: message.into(),
+            location,
+        }
+    }
+
+    /// Render the diagnostic with an excerpt of the offending line:
+    ///
+    /// ```text
+    /// error: invalid filter `Cubic`, expected `Nearest` or `Linear`
+    ///  --> blur.glsl:2:1
+    ///   |
+    /// 2 | #pragma sampler samp (filter=Cubic)
+    ///   | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let mut out = format!("error: {}", self.message);
+
+        let Some(loc) = &self.location else {
+            return out;
+        };
+
+        let gutter = loc.line.to_string().len();
+        let pad = " ".repeat(gutter);
+
+        // the caret underline can't run past the end of the first line
+        let line_offset = loc
+            .line_text
+            .chars()
+            .take(loc.column - 1)
+            .map(char::len_utf8)
+            .sum::<usize>();
+        let remaining = &loc.line_text[line_offset..];
+        let span_len = loc.span.end - loc.span.start;
+        let carets = remaining
+            .char_indices()
+            .take_while(|(idx, _)| *idx < span_len)
+            .count()
+            .max(1);
+
+        out += &format!("\n{pad}--> {}:{}:{}", loc.file, loc.line, loc.column);
+        out += &format!("\n{pad} |");
+        out += &format!("\n{} | {}", loc.line, loc.line_text);
+        // keep tabs so the carets line up with the excerpt
+        let indent: String = loc.line_text[..line_offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        out += &format!("\n{pad} | {indent}{}", "^".repeat(carets));
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+pub(crate) fn render_all(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(Diagnostic::render)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(feature = "wgsl")]
+pub(crate) fn from_wgsl(
+    error: &wgpu::naga::front::wgsl::ParseError,
+    source_map: &crate::preprocessing::SourceMap,
+) -> Vec<Diagnostic> {
+    let primary = error.labels().next();
+
+    let message = match primary {
+        Some((_, label)) if !label.is_empty() && label != error.message() => {
+            format!("{} ({label})", error.message())
+        }
+        _ => error.message().to_owned(),
+    };
+
+    let location = primary
+        .and_then(|(span, _)| span.to_range())
+        .and_then(|span| source_map.locate(span));
+
+    vec![Diagnostic::new(message, location)]
+}
+
+#[cfg(feature = "glsl")]
+pub(crate) fn from_glsl(
+    errors: &wgpu::naga::front::glsl::ParseErrors,
+    source_map: &crate::preprocessing::SourceMap,
+) -> Vec<Diagnostic> {
+    errors
+        .errors
+        .iter()
+        .map(|error| {
+            let location = error
+                .meta
+                .to_range()
+                .and_then(|span| source_map.locate(span));
+            Diagnostic::new(error.kind.to_string(), location)
+        })
+        .collect()
+}
+
+pub(crate) fn from_validation(
+    error: &wgpu::naga::WithSpan<wgpu::naga::valid::ValidationError>,
+    source_map: &crate::preprocessing::SourceMap,
+) -> Vec<Diagnostic> {
+    // the outer errors only name the function or global, the cause is
+    // further down the chain
+    let mut message = error.as_inner().to_string();
+    let mut cause = std::error::Error::source(error.as_inner());
+    while let Some(error) = cause {
+        message += &format!(": {error}");
+        cause = error.source();
+    }
+
+    // the first span is the innermost one
+    let primary = error.spans().next();
+    if let Some((_, label)) = primary.filter(|(_, label)| !label.is_empty()) {
+        message += &format!(" ({label})");
+    }
+
+    let location = primary
+        .and_then(|(span, _)| span.to_range())
+        .and_then(|span| source_map.locate(span));
+
+    vec![Diagnostic::new(message, location)]
+}
@@ -1,4 +1,649 @@
-// This should contain a type like serde_jsons object which can represent a
-// generic, host typed struct. given a vec<u8> of the right size it will
-// allow you optioned off view like access to the buffer gauranteeing
-// good GPU layout
+//! A dynamically typed, host side view of a reflected struct, in the spirit of
+//! serde_json's `Value`. A [`HostBuffer`] owns correctly sized bytes for a
+//! naga type and gives checked access to them by field path. Offsets and
+//! strides are taken from the reflected module, so the layout matches the
+//! shader's declaration: std140 for glsl uniform blocks, std430 for glsl
+//! buffers and push constants, and wgsl's own rules for wgsl.
+
+use std::borrow::Cow;
+
+use thiserror::Error;
+use wgpu::naga::{self, ArraySize, ScalarKind, TypeInner};
+
+#[derive(Debug, Error)]
+pub enum HostTypeError {
+    #[error("Type can't be stored in host shareable memory: {0}")]
+    Unsupported(&'static str),
+    #[error("Malformed field path `{0}`")]
+    MalformedPath(String),
+    #[error("No field `{0}`")]
+    NoSuchField(String),
+    #[error("Index {index} of `{path}` is out of bounds for length {len}")]
+    OutOfBounds {
+        path: String,
+        index: usize,
+        len: usize,
+    },
+    #[error("`{path}` is {expected}, found {found}")]
+    TypeMismatch {
+        path: String,
+        expected: String,
+        found: String,
+    },
+    #[error("Type has no runtime sized array")]
+    NoRuntimeArray,
+    #[error("No uniform or storage buffer at set {0} binding {1}")]
+    NoSuchBuffer(u32, u32),
+    #[error("Module has no push constants")]
+    NoPushConstants,
+    #[error("The vertex and fragment stages declare different push constant blocks")]
+    PushConstantMismatch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarType {
+    /// stored as a 32 bit integer, zero is false
+    Bool,
+    F32,
+    F64,
+    U32,
+    I32,
+}
+
+impl ScalarType {
+    pub(crate) fn from_naga(scalar: naga::Scalar) -> Result<Self, HostTypeError> {
+        match (scalar.kind, scalar.width) {
+            (ScalarKind::Bool, _) => Ok(Self::Bool),
+            (ScalarKind::Float, 4) => Ok(Self::F32),
+            (ScalarKind::Float, 8) => Ok(Self::F64),
+            (ScalarKind::Uint, 4) => Ok(Self::U32),
+            (ScalarKind::Sint, 4) => Ok(Self::I32),
+            (ScalarKind::Float, _) => Err(HostTypeError::Unsupported("16 bit float")),
+            (ScalarKind::Uint | ScalarKind::Sint, _) => {
+                Err(HostTypeError::Unsupported("64 bit integer"))
+            }
+            (ScalarKind::AbstractInt | ScalarKind::AbstractFloat, _) => {
+                Err(HostTypeError::Unsupported("abstract number"))
+            }
+        }
+    }
+
+    fn size(self) -> u32 {
+        match self {
+            Self::F64 => 8,
+            _ => 4,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::U32 => "u32",
+            Self::I32 => "i32",
+        }
+    }
+}
+
+/// The layout of a host shareable type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostType {
+    Scalar(ScalarType),
+    Vector {
+        size: u32,
+        scalar: ScalarType,
+    },
+    /// stored column major, columns are addressed as vectors
+    Matrix {
+        columns: u32,
+        rows: u32,
+        scalar: ScalarType,
+        column_stride: u32,
+    },
+    /// `len` is `None` for runtime sized arrays, whose length
+    /// is given by the size of the buffer.
+    Array {
+        base: Box<HostType>,
+        len: Option<u32>,
+        stride: u32,
+    },
+    Struct {
+        members: Vec<HostMember>,
+        size: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostMember {
+    pub name: String,
+    pub offset: u32,
+    pub ty: HostType,
+}
+
+impl HostType {
+    pub fn from_naga(
+        module: &naga::Module,
+        ty: naga::Handle<naga::Type>,
+    ) -> Result<Self, HostTypeError> {
+        let out = match module.types[ty].inner {
+            TypeInner::Scalar(scalar) | TypeInner::Atomic(scalar) => {
+                Self::Scalar(ScalarType::from_naga(scalar)?)
+            }
+            TypeInner::Vector { size, scalar } => Self::Vector {
+                size: size as u32,
+                scalar: ScalarType::from_naga(scalar)?,
+            },
+            TypeInner::Matrix {
+                columns,
+                rows,
+                scalar,
+            } => {
+                let scalar = ScalarType::from_naga(scalar)?;
+                // columns are aligned like a vector of `rows` components,
+                // three component vectors align like four.
+                let aligned_rows = if rows == naga::VectorSize::Bi { 2 } else { 4 };
+                Self::Matrix {
+                    columns: columns as u32,
+                    rows: rows as u32,
+                    scalar,
+                    column_stride: aligned_rows * scalar.size(),
+                }
+            }
+            TypeInner::Array { base, size, stride } => Self::Array {
+                base: Box::new(Self::from_naga(module, base)?),
+                len: match size {
+                    ArraySize::Constant(len) => Some(len.get()),
+                    ArraySize::Dynamic => None,
+                },
+                stride,
+            },
+            TypeInner::Struct { ref members, span } => Self::Struct {
+                members: members
+                    .iter()
+                    .map(|member| {
+                        Ok(HostMember {
+                            name: member.name.clone().unwrap_or_default(),
+                            offset: member.offset,
+                            ty: Self::from_naga(module, member.ty)?,
+                        })
+                    })
+                    .collect::<Result<_, HostTypeError>>()?,
+                size: span,
+            },
+            TypeInner::Pointer { .. } | TypeInner::ValuePointer { .. } => {
+                return Err(HostTypeError::Unsupported("pointer"))
+            }
+            TypeInner::Image { .. }
+            | TypeInner::Sampler { .. }
+            | TypeInner::AccelerationStructure
+            | TypeInner::RayQuery
+            | TypeInner::BindingArray { .. } => {
+                return Err(HostTypeError::Unsupported("opaque handle"))
+            }
+        };
+
+        Ok(out)
+    }
+
+    /// Size in bytes, runtime sized arrays count as empty.
+    pub fn size(&self) -> u32 {
+        match self {
+            Self::Scalar(scalar) => scalar.size(),
+            Self::Vector { size, scalar } => size * scalar.size(),
+            Self::Matrix {
+                columns,
+                column_stride,
+                ..
+            } => columns * column_stride,
+            Self::Array { len, stride, .. } => len.unwrap_or(0) * stride,
+            // naga sizes runtime arrays as a single element
+            Self::Struct { size, .. } => match self.runtime_array() {
+                Some((offset, _)) => offset,
+                None => *size,
+            },
+        }
+    }
+
+    /// The runtime sized array at the end of the type, with its offset.
+    fn runtime_array(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::Array {
+                len: None, stride, ..
+            } => Some((0, *stride)),
+            Self::Struct { members, .. } => {
+                let last = members.last()?;
+                let (offset, stride) = last.ty.runtime_array()?;
+                Some((last.offset + offset, stride))
+            }
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Scalar(scalar) => scalar.name().to_owned(),
+            Self::Vector { size, scalar } => format!("vec{size}<{}>", scalar.name()),
+            Self::Matrix {
+                columns,
+                rows,
+                scalar,
+                ..
+            } => format!("mat{columns}x{rows}<{}>", scalar.name()),
+            Self::Array {
+                base,
+                len: Some(len),
+                ..
+            } => format!("array<{}, {len}>", base.describe()),
+            Self::Array { base, .. } => format!("array<{}>", base.describe()),
+            Self::Struct { .. } => "a struct".to_owned(),
+        }
+    }
+}
+
+/// A dynamically typed value read from or written to a [`HostBuffer`].
+/// Vectors, arrays and matrices (as a list of columns) are lists.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostValue {
+    Bool(bool),
+    F32(f32),
+    F64(f64),
+    U32(u32),
+    I32(i32),
+    List(Vec<HostValue>),
+    Struct(Vec<(String, HostValue)>),
+}
+
+impl HostValue {
+    fn describe(&self) -> String {
+        match self {
+            Self::Bool(_) => "bool".to_owned(),
+            Self::F32(_) => "f32".to_owned(),
+            Self::F64(_) => "f64".to_owned(),
+            Self::U32(_) => "u32".to_owned(),
+            Self::I32(_) => "i32".to_owned(),
+            Self::List(list) => format!("a list of {}", list.len()),
+            Self::Struct(_) => "a struct".to_owned(),
+        }
+    }
+
+    /// Numeric values convert to any scalar type which can represent them,
+    /// so untyped literals like `0.5` or `4` can be written to any field.
+    fn to_scalar_bytes(&self, scalar: ScalarType) -> Option<Vec<u8>> {
+        let float = match *self {
+            Self::F32(v) => Some(v as f64),
+            Self::F64(v) => Some(v),
+            _ => None,
+        };
+        let int = match *self {
+            Self::U32(v) => Some(v as i64),
+            Self::I32(v) => Some(v as i64),
+            _ => None,
+        };
+
+        let bytes = match (scalar, self) {
+            (ScalarType::Bool, Self::Bool(v)) => (*v as u32).to_le_bytes().to_vec(),
+            (ScalarType::F32, _) => (float.or(int.map(|i| i as f64))? as f32)
+                .to_le_bytes()
+                .to_vec(),
+            (ScalarType::F64, _) => float.or(int.map(|i| i as f64))?.to_le_bytes().to_vec(),
+            (ScalarType::U32, _) => u32::try_from(int?).ok()?.to_le_bytes().to_vec(),
+            (ScalarType::I32, _) => i32::try_from(int?).ok()?.to_le_bytes().to_vec(),
+            _ => return None,
+        };
+        Some(bytes)
+    }
+}
+
+macro_rules! host_scalar {
+    ($ty:ty, $variant:ident) => {
+        impl From<$ty> for HostValue {
+            fn from(value: $ty) -> Self {
+                Self::$variant(value)
+            }
+        }
+
+        impl TryFrom<HostValue> for $ty {
+            type Error = HostValue;
+
+            fn try_from(value: HostValue) -> Result<Self, HostValue> {
+                match value {
+                    HostValue::$variant(v) => Ok(v),
+                    other => Err(other),
+                }
+            }
+        }
+    };
+}
+
+host_scalar!(bool, Bool);
+host_scalar!(f32, F32);
+host_scalar!(f64, F64);
+host_scalar!(u32, U32);
+host_scalar!(i32, I32);
+
+impl<T: Into<HostValue>, const N: usize> From<[T; N]> for HostValue {
+    fn from(value: [T; N]) -> Self {
+        Self::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<HostValue>> From<Vec<T>> for HostValue {
+    fn from(value: Vec<T>) -> Self {
+        Self::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: TryFrom<HostValue, Error = HostValue>> TryFrom<HostValue> for Vec<T> {
+    type Error = HostValue;
+
+    fn try_from(value: HostValue) -> Result<Self, HostValue> {
+        match value {
+            HostValue::List(list) => list.into_iter().map(T::try_from).collect(),
+            other => Err(other),
+        }
+    }
+}
+
+impl<T: TryFrom<HostValue, Error = HostValue>, const N: usize> TryFrom<HostValue> for [T; N] {
+    type Error = HostValue;
+
+    fn try_from(value: HostValue) -> Result<Self, HostValue> {
+        match value {
+            HostValue::List(list) if list.len() == N => {
+                let items = list
+                    .into_iter()
+                    .map(T::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(items
+                    .try_into()
+                    .unwrap_or_else(|_| unreachable!("length checked above")))
+            }
+            other => Err(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step<'p> {
+    Field(&'p str),
+    Index(usize),
+}
+
+/// `a.b[2].c`, an empty path is the whole value.
+fn parse_path(path: &str) -> Result<Vec<Step<'_>>, HostTypeError> {
+    let malformed = || HostTypeError::MalformedPath(path.to_owned());
+    let mut steps = vec![];
+
+    if path.is_empty() {
+        return Ok(steps);
+    }
+
+    for (idx, segment) in path.split('.').enumerate() {
+        let (name, mut indices) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+
+        if !name.is_empty() {
+            steps.push(Step::Field(name));
+        } else if idx > 0 || indices.is_empty() {
+            return Err(malformed());
+        }
+
+        while !indices.is_empty() {
+            let close = indices.find(']').ok_or_else(malformed)?;
+            let index = indices[1..close].trim().parse().map_err(|_| malformed())?;
+            steps.push(Step::Index(index));
+            indices = &indices[close + 1..];
+            if !indices.is_empty() && !indices.starts_with('[') {
+                return Err(malformed());
+            }
+        }
+    }
+
+    Ok(steps)
+}
+
+/// Bytes laid out for a reflected type, accessed by field path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostBuffer {
+    ty: HostType,
+    /// names the root may be addressed by, the global and type name
+    names: Vec<String>,
+    bytes: Vec<u8>,
+}
+
+impl HostBuffer {
+    /// A zeroed buffer for `ty`, runtime sized arrays start out empty.
+    pub fn new(module: &naga::Module, ty: naga::Handle<naga::Type>) -> Result<Self, HostTypeError> {
+        let host_ty = HostType::from_naga(module, ty)?;
+        let names = module.types[ty].name.iter().cloned().collect();
+        Ok(Self::from_type(host_ty, names))
+    }
+
+    /// A zeroed buffer for the contents of a uniform, storage or push constant
+    /// global. Paths may start with the name of the global, so the paths of
+    /// [`crate::Parameter`]s can be used directly.
+    pub fn for_global(
+        module: &naga::Module,
+        global: &naga::GlobalVariable,
+    ) -> Result<Self, HostTypeError> {
+        let mut buffer = Self::new(module, global.ty)?;
+        if let Some(name) = &global.name {
+            buffer.names.insert(0, name.clone());
+        }
+        Ok(buffer)
+    }
+
+    fn from_type(ty: HostType, names: Vec<String>) -> Self {
+        let bytes = vec![0; ty.size() as usize];
+        Self { ty, names, bytes }
+    }
+
+    pub fn ty(&self) -> &HostType {
+        &self.ty
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Resize the runtime sized array at the end of the type to `len`
+    /// elements, new elements are zeroed.
+    pub fn set_runtime_len(&mut self, len: u32) -> Result<(), HostTypeError> {
+        let (offset, stride) = self
+            .ty
+            .runtime_array()
+            .ok_or(HostTypeError::NoRuntimeArray)?;
+        self.bytes.resize((offset + len * stride) as usize, 0);
+        Ok(())
+    }
+
+    pub fn get(&self, path: &str) -> Result<HostValue, HostTypeError> {
+        let (offset, ty) = self.locate(path)?;
+        Ok(self.read(&ty, offset))
+    }
+
+    /// Read a value and convert it, `f32`, `[f32; 4]`, `Vec<u32>` and so on.
+    pub fn get_as<T: TryFrom<HostValue>>(&self, path: &str) -> Result<T, HostTypeError> {
+        let value = self.get(path)?;
+        let found = value.describe();
+        T::try_from(value).map_err(|_| HostTypeError::TypeMismatch {
+            path: path.to_owned(),
+            expected: std::any::type_name::<T>().to_owned(),
+            found,
+        })
+    }
+
+    pub fn set(&mut self, path: &str, value: impl Into<HostValue>) -> Result<(), HostTypeError> {
+        let (offset, ty) = self.locate(path)?;
+        let ty = ty.into_owned();
+        self.write(path, &ty, offset, &value.into())
+    }
+
+    /// Find the offset and type a path refers to.
+    fn locate(&self, path: &str) -> Result<(u32, Cow<'_, HostType>), HostTypeError> {
+        let mut steps = parse_path(path)?.into_iter().peekable();
+
+        // a leading global or type name is optional
+        if let (Some(Step::Field(root)), HostType::Struct { members, .. }) =
+            (steps.peek(), &self.ty)
+        {
+            let is_member = members.iter().any(|m| m.name == *root);
+            if !is_member && self.names.iter().any(|name| name == root) {
+                steps.next();
+            }
+        }
+
+        let mut offset = 0;
+        let mut ty = Cow::Borrowed(&self.ty);
+
+        for step in steps {
+            let mismatch = |expected: &str| HostTypeError::TypeMismatch {
+                path: path.to_owned(),
+                expected: expected.to_owned(),
+                found: ty.describe(),
+            };
+
+            let (child_offset, child) = match (step, ty.as_ref()) {
+                (Step::Field(name), HostType::Struct { members, .. }) => {
+                    let member = members
+                        .iter()
+                        .find(|m| m.name == name)
+                        .ok_or_else(|| HostTypeError::NoSuchField(path.to_owned()))?;
+                    (member.offset, member.ty.clone())
+                }
+                (Step::Field(_), _) => return Err(mismatch("a struct")),
+                (Step::Index(index), parent) => {
+                    let (len, stride, element) = self
+                        .elements(parent, offset)
+                        .ok_or_else(|| mismatch("a vector, matrix or array"))?;
+                    if index >= len {
+                        return Err(HostTypeError::OutOfBounds {
+                            path: path.to_owned(),
+                            index,
+                            len,
+                        });
+                    }
+                    (index as u32 * stride, element)
+                }
+            };
+
+            offset += child_offset;
+            ty = Cow::Owned(child);
+        }
+
+        Ok((offset, ty))
+    }
+
+    /// Length, stride and element type of a vector, matrix or array at `offset`.
+    fn elements(&self, ty: &HostType, offset: u32) -> Option<(usize, u32, HostType)> {
+        let (len, stride, element) = match ty {
+            HostType::Vector { size, scalar } => (*size, scalar.size(), HostType::Scalar(*scalar)),
+            HostType::Matrix {
+                columns,
+                rows,
+                scalar,
+                column_stride,
+            } => (
+                *columns,
+                *column_stride,
+                HostType::Vector {
+                    size: *rows,
+                    scalar: *scalar,
+                },
+            ),
+            HostType::Array { base, len, stride } => {
+                let len = len
+                    .unwrap_or_else(|| (self.bytes.len() as u32).saturating_sub(offset) / stride);
+                (len, *stride, base.as_ref().clone())
+            }
+            HostType::Scalar(_) | HostType::Struct { .. } => return None,
+        };
+        Some((len as usize, stride, element))
+    }
+
+    fn word<const N: usize>(&self, at: usize) -> [u8; N] {
+        let mut word = [0; N];
+        word.copy_from_slice(&self.bytes[at..at + N]);
+        word
+    }
+
+    fn read(&self, ty: &HostType, offset: u32) -> HostValue {
+        match ty {
+            HostType::Scalar(scalar) => {
+                let at = offset as usize;
+                match scalar {
+                    ScalarType::Bool => HostValue::Bool(u32::from_le_bytes(self.word(at)) != 0),
+                    ScalarType::F32 => HostValue::F32(f32::from_le_bytes(self.word(at))),
+                    ScalarType::F64 => HostValue::F64(f64::from_le_bytes(self.word(at))),
+                    ScalarType::U32 => HostValue::U32(u32::from_le_bytes(self.word(at))),
+                    ScalarType::I32 => HostValue::I32(i32::from_le_bytes(self.word(at))),
+                }
+            }
+            HostType::Struct { members, .. } => HostValue::Struct(
+                members
+                    .iter()
+                    .map(|m| (m.name.clone(), self.read(&m.ty, offset + m.offset)))
+                    .collect(),
+            ),
+            list => {
+                let Some((len, stride, element)) = self.elements(list, offset) else {
+                    return HostValue::List(vec![]);
+                };
+                HostValue::List(
+                    (0..len as u32)
+                        .map(|idx| self.read(&element, offset + idx * stride))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn write(
+        &mut self,
+        path: &str,
+        ty: &HostType,
+        offset: u32,
+        value: &HostValue,
+    ) -> Result<(), HostTypeError> {
+        let mismatch = || HostTypeError::TypeMismatch {
+            path: path.to_owned(),
+            expected: ty.describe(),
+            found: value.describe(),
+        };
+
+        match (ty, value) {
+            (HostType::Scalar(scalar), _) => {
+                let bytes = value.to_scalar_bytes(*scalar).ok_or_else(mismatch)?;
+                let at = offset as usize;
+                self.bytes[at..at + bytes.len()].copy_from_slice(&bytes);
+            }
+            (HostType::Struct { members, .. }, HostValue::Struct(fields)) => {
+                for (name, field) in fields {
+                    let member = members
+                        .iter()
+                        .find(|m| m.name == *name)
+                        .ok_or_else(|| HostTypeError::NoSuchField(format!("{path}.{name}")))?;
+                    let field_path = format!("{path}.{name}");
+                    self.write(&field_path, &member.ty, offset + member.offset, field)?;
+                }
+            }
+            (list, HostValue::List(values)) => {
+                let (len, stride, element) = self.elements(list, offset).ok_or_else(mismatch)?;
+                if len != values.len() {
+                    return Err(mismatch());
+                }
+                for (idx, value) in values.iter().enumerate() {
+                    let element_path = format!("{path}[{idx}]");
+                    self.write(&element_path, &element, offset + idx as u32 * stride, value)?;
+                }
+            }
+            _ => return Err(mismatch()),
+        }
+
+        Ok(())
+    }
+}
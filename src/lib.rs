@@ -1,17 +1,54 @@
 mod bind_group;
+mod diagnostic;
+mod host_type;
+mod parameters;
 mod preprocessing;
+mod render;
+#[cfg(feature = "encase")]
+mod shader_type;
+mod signature;
+mod texture_copy;
 mod traits;
 mod wgpu_utils;
 
-use bind_group::BindGroups;
+use bind_group::{build_bind_group, requirements, BindGroups, Labels, Reflected};
+use preprocessing::SourceMap;
 
-pub use bind_group::requirements::{BindSlot, PassSlot};
-pub use wgpu_utils::DeviceUtils;
+pub use bind_group::requirements::{
+    BindSlot, BindingIssue, BindingProblem, MissingPassSlot, PassSlot,
+};
+pub use bind_group::{DescribedView, Samplers, ViewInfo};
+pub use diagnostic::{Diagnostic, SourceLocation};
+pub use host_type::{HostBuffer, HostMember, HostType, HostTypeError, HostValue, ScalarType};
+pub use parameters::{Parameter, ParameterLocation};
+pub use preprocessing::{
+    FileSystemResolver, IncludeResolver, MemoryResolver, PreprocessOptions, PreprocessingError,
+    ResolvedInclude, VarType,
+};
+pub use render::{
+    BoundRenderPipeline, ColorTarget, RenderError, RenderReflector, RenderState,
+    UnboundRenderPipeline, VertexBuffer,
+};
+#[cfg(feature = "encase")]
+pub use shader_type::{MemberOffsets, ShaderTypeError, TypedBinding};
+pub use signature::{
+    FieldLayout, LayoutLoc, ResourceKind, Signature, SignatureBinding, SignatureMismatch,
+    StructLayout,
+};
+pub use texture_copy::{TextureCopyError, TextureRegion};
+pub use traits::{
+    BindingField, BindingSignature, BindingTarget, Bindings, BindingsError, FieldResource,
+    PushConstant, PushConstantError, PushConstantField,
+};
+pub use wgpu_utils::{DeviceUtils, Readback, ReadbackError, StagingPool};
+
+pub use bytemuck;
+#[cfg(feature = "derive")]
+pub use kinnara_derive::{Bindings, PushConstant};
 
 use thiserror::Error;
 use wgpu::{
-    naga::front::{self, glsl::ParseErrors as GlslParseError, wgsl::ParseError as WgslParseError},
-    BindGroupEntry, BindGroupLayoutDescriptor, ComputePipeline, ComputePipelineDescriptor,
+    naga::front, BindGroupLayoutDescriptor, ComputePipeline, ComputePipelineDescriptor,
     ErrorFilter, ShaderModuleDescriptor, ShaderSource,
 };
 
@@ -25,21 +62,52 @@ pub enum Error {
     Wgpu(String),
     #[error("Unsupported shader source type. Pass either kinnara enriched wgsl or glsl.")]
     UnsupportedSourceType,
-    #[error("Shader compilation error: {0}")]
-    WgslCompilationError(#[from] WgslParseError),
-    #[error("Shader compilation error: {0}")]
-    GlslCompilationError(#[from] GlslParseError),
+    #[error("Shader compilation error:\n{}", diagnostic::render_all(.0))]
+    CompilationError(Vec<Diagnostic>),
     #[error("Bind Group Error: {0}")]
     BindGroupError(#[from] bind_group::BindGroupError),
     #[error("Preprocessing Error : {0}")]
     PreprocessingError(#[from] preprocessing::PreprocessingError),
-    //TODO: make this error prettier
-    #[error(
-        "Incomplete Pass : missing dynaic offset information for {0:?} , and push constants for {1:?}"
-    )]
-    PassConstruction(Vec<(u32, u32)>, Vec<wgpu::ShaderStages>),
-    #[error("Missing Bindings: bind groups missing {0:?}")]
-    MissingBindings(Vec<(u32, u32)>),
+    #[error("Parameter Error : {0}")]
+    ParameterError(#[from] parameters::ParameterError),
+    #[error("Host Type Error : {0}")]
+    HostTypeError(#[from] host_type::HostTypeError),
+    #[error("Render Error : {0}")]
+    RenderError(#[from] render::RenderError),
+    #[error("Push Constant Error : {0}")]
+    PushConstantError(#[from] traits::PushConstantError),
+    #[error("Bindings Error : {0}")]
+    BindingsError(#[from] traits::BindingsError),
+    #[cfg(feature = "encase")]
+    #[error("Shader Type Error : {0}")]
+    ShaderTypeError(#[from] shader_type::ShaderTypeError),
+    #[error("Readback Error : {0}")]
+    ReadbackError(#[from] wgpu_utils::ReadbackError),
+    #[error("Texture Copy Error : {0}")]
+    TextureCopyError(#[from] texture_copy::TextureCopyError),
+    #[error("Shader doesn't match the binding signature:\n{}", signature::render_all(.0))]
+    SignatureMismatch(Vec<SignatureMismatch>),
+    #[error("Incomplete pass, missing:\n{}", requirements::render_all(.0))]
+    PassConstruction(Vec<MissingPassSlot>),
+    #[error("Invalid bindings:\n{}", requirements::render_all(.0))]
+    InvalidBindings(Vec<BindingIssue>),
+    #[error("Pipeline has no bind group set {0}")]
+    NoSuchSet(u32),
+}
+
+impl Error {
+    /// Diagnostics pointing into the user's shader source,
+    /// empty for errors which don't originate from the source.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Error::CompilationError(diagnostics) => diagnostics,
+            Error::PreprocessingError(
+                PreprocessingError::ParsingError(diagnostic)
+                | PreprocessingError::IncludeError(diagnostic),
+            ) => std::slice::from_ref(diagnostic),
+            _ => &[],
+        }
+    }
 }
 
 impl From<wgpu::Error> for Error {
@@ -52,13 +120,10 @@ impl From<wgpu::Error> for Error {
     }
 }
 
-// Question: should these generics be moved into a trait which
-// can be derived that auto implements the the access and setup functions?
-// TODO: trait bounds on the push constant type
-// TODO: trait bounds on binding type
 pub struct UnboundComputePipeline {
     pipeline: wgpu::ComputePipeline,
     reflection_ctx: ComputeReflector,
+    samplers: Samplers,
     entry_point: String,
 }
 
@@ -66,6 +131,7 @@ pub struct BoundComputePipeline {
     pipeline: wgpu::ComputePipeline,
     bind_groups: Vec<wgpu::BindGroup>,
     reflection_ctx: ComputeReflector,
+    samplers: Samplers,
     entry_point: String,
 }
 
@@ -75,12 +141,14 @@ impl BoundComputePipeline {
             pipeline,
             entry_point,
             reflection_ctx,
+            samplers,
             ..
         } = self;
 
         UnboundComputePipeline {
             pipeline,
             reflection_ctx,
+            samplers,
             entry_point,
         }
     }
@@ -94,9 +162,16 @@ impl BoundComputePipeline {
     where
         F: FnMut(&bind_group::requirements::BindSlot<'a>),
     {
-        let group = self
-            .reflection_ctx
-            .create_bind_group(device, set, &mut bind_func)?;
+        if set as usize >= self.bind_groups.len() {
+            return Err(Error::NoSuchSet(set));
+        }
+        let group = self.reflection_ctx.create_bind_group_for(
+            device,
+            &self.entry_point,
+            set,
+            &self.samplers,
+            &mut bind_func,
+        )?;
 
         self.bind_groups[set as usize] = group;
 
@@ -112,28 +187,48 @@ impl BoundComputePipeline {
         F: FnMut(&bind_group::requirements::BindSlot<'a>),
     {
         let mut bind_groups = vec![];
-        let mut missing = vec![];
-
-        let bg_ct = self.reflection_ctx.bind_group_count() as u32;
-        for set in 0..=bg_ct {
-            match self
-                .reflection_ctx
-                .create_bind_group(device, set, &mut bind_func)
-            {
+        let mut issues = vec![];
+
+        let set_ct = self.reflection_ctx.set_count_for(&self.entry_point)? as u32;
+        for set in 0..set_ct {
+            match self.reflection_ctx.create_bind_group_for(
+                device,
+                &self.entry_point,
+                set,
+                &self.samplers,
+                &mut bind_func,
+            ) {
                 Ok(group) => bind_groups.push(group),
-                Err(Error::MissingBindings(ent)) => missing.extend(ent),
-                _ => unreachable!(),
+                Err(Error::InvalidBindings(ent)) => issues.extend(ent),
+                Err(e) => return Err(e),
             }
         }
 
-        if !missing.is_empty() {
-            return Err(Error::MissingBindings(missing));
+        if !issues.is_empty() {
+            return Err(Error::InvalidBindings(issues));
         }
 
         self.bind_groups = bind_groups;
         Ok(())
     }
 
+    /// Like [`BoundComputePipeline::rebind_all`], `bind_func` is given the
+    /// shader variable name of each slot.
+    pub fn rebind_all_by_name<'a, F>(
+        &mut self,
+        device: &wgpu::Device,
+        mut bind_func: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&str, &bind_group::requirements::BindSlot<'a>),
+    {
+        self.rebind_all(device, |slot| {
+            if let Some(name) = slot.name() {
+                bind_func(name, slot)
+            }
+        })
+    }
+
     pub fn derail(self) -> (wgpu::ComputePipeline, Vec<wgpu::BindGroup>) {
         let Self {
             pipeline,
@@ -153,46 +248,58 @@ impl BoundComputePipeline {
     where
         F: FnMut(&bind_group::requirements::PassSlot<'b>),
     {
+        let label = self.reflection_ctx.derived_label("pass");
         let mut pass = enc.begin_compute_pass(&wgpu::ComputePassDescriptor {
-            label: None,
+            label: label.as_deref(),
             timestamp_writes: None,
         });
 
-        let mut pc_range_errors = vec![];
+        let mut missing = vec![];
         let mut pc_ranges = vec![];
 
-        if let Some(reflected_ranges) = self.reflection_ctx.push_constant_range() {
+        if let Some(reflected_ranges) = self
+            .reflection_ctx
+            .push_constant_range_for(&self.entry_point)?
+        {
             for range in reflected_ranges {
                 let pc_out = PassSlot::from(range);
                 pass_func(&pc_out);
-                match pc_out.push_const_slice() {
-                    Some(pc) => pc_ranges.push(pc),
-                    None => pc_range_errors.push(range.stages),
+                match MissingPassSlot::push_constants(range, pc_out) {
+                    Ok(pc) => pc_ranges.push(pc),
+                    Err(slot) => missing.push(slot),
                 }
             }
         }
 
         let mut bg_and_offsets = vec![];
-        let mut errors = vec![];
 
         for (set, group) in self.bind_groups.iter().enumerate() {
             let mut offsets = vec![];
 
-            for ent in self.reflection_ctx.iter_bind_group_entries(set as u32) {
+            let entries = self
+                .reflection_ctx
+                .bind_group_layout_entries_for(&self.entry_point, set as u32)?;
+            for ent in entries {
                 if ent.ty.has_dynamic_offset() {
                     let dyn_offset = PassSlot::offset_for(set as u32, ent.binding);
                     pass_func(&dyn_offset);
                     match dyn_offset.offset() {
                         Some(offset) => offsets.push(offset),
-                        None => errors.push((set as u32, ent.binding)),
+                        None => missing.push(MissingPassSlot::DynamicOffset {
+                            loc: (set as u32, ent.binding),
+                            name: self
+                                .reflection_ctx
+                                .binding_name(set as u32, ent.binding)
+                                .map(str::to_owned),
+                        }),
                     }
                 }
             }
             bg_and_offsets.push((set, group, offsets));
         }
 
-        if !errors.is_empty() || !pc_range_errors.is_empty() {
-            return Err(Error::PassConstruction(errors, pc_range_errors));
+        if !missing.is_empty() {
+            return Err(Error::PassConstruction(missing));
         }
 
         pass.set_pipeline(&self.pipeline);
@@ -207,6 +314,28 @@ impl BoundComputePipeline {
 
         Ok(pass)
     }
+
+    /// [`Self::create_pass`] with the push constants taken from a rust
+    /// type, which is checked against the shader's push constant block
+    /// first. `pass_func` is only asked for the remaining slots.
+    pub fn create_pass_with<'a, 'b, P, F>(
+        &self,
+        enc: &'a mut wgpu::CommandEncoder,
+        constants: &'b P,
+        mut pass_func: F,
+    ) -> Result<wgpu::ComputePass<'a>, Error>
+    where
+        P: PushConstant + ?Sized,
+        F: FnMut(&bind_group::requirements::PassSlot<'b>),
+    {
+        let reflected = self.reflection_ctx.push_constant_buffer().ok();
+        traits::check_push_constant(reflected.as_ref().map(HostBuffer::ty), constants)?;
+
+        self.create_pass(enc, |slot| match slot {
+            PassSlot::PushConstantRange { .. } => slot.fill_push_constants(constants.bytes()),
+            _ => pass_func(slot),
+        })
+    }
 }
 
 impl UnboundComputePipeline {
@@ -217,10 +346,12 @@ impl UnboundComputePipeline {
         mut context: ComputeReflector,
     ) -> Result<Self, Error> {
         let pipeline = context.create_compute_pipeline(entry_point, device, options)?;
+        let samplers = context.create_samplers(device);
 
         Ok(Self {
             pipeline,
             reflection_ctx: context,
+            samplers,
             entry_point: entry_point.to_owned(),
         })
     }
@@ -229,6 +360,14 @@ impl UnboundComputePipeline {
         self.reflection_ctx.work_group_size(&self.entry_point)
     }
 
+    /// The samplers created for the pipeline's sampler bindings.
+    pub fn samplers(&self) -> &Samplers {
+        &self.samplers
+    }
+
+    /// Create every bind group of the pipeline. Sampler bindings with a
+    /// sampler in [`Self::samplers`] are bound to it and never passed to
+    /// `bind_func`, so they always match their layout entry.
     pub fn bind<'a, F>(
         self,
         device: &wgpu::Device,
@@ -240,33 +379,82 @@ impl UnboundComputePipeline {
         let Self {
             pipeline,
             reflection_ctx,
+            samplers,
             entry_point,
         } = self;
 
-        let bg_ct = reflection_ctx.bind_group_count() as u32;
+        let set_ct = reflection_ctx.set_count_for(&entry_point)? as u32;
 
         let mut bind_groups = vec![];
-        let mut missing = vec![];
-
-        for set in 0..=bg_ct {
-            match reflection_ctx.create_bind_group(device, set, &mut bind_func) {
+        let mut issues = vec![];
+
+        for set in 0..set_ct {
+            match reflection_ctx.create_bind_group_for(
+                device,
+                &entry_point,
+                set,
+                &samplers,
+                &mut bind_func,
+            ) {
                 Ok(group) => bind_groups.push(group),
-                Err(Error::MissingBindings(ent)) => missing.extend(ent),
-                _ => unreachable!(),
+                Err(Error::InvalidBindings(ent)) => issues.extend(ent),
+                Err(e) => return Err(e),
             }
         }
 
-        if !missing.is_empty() {
-            return Err(Error::MissingBindings(missing));
+        if !issues.is_empty() {
+            return Err(Error::InvalidBindings(issues));
         }
 
         Ok(BoundComputePipeline {
             pipeline,
             bind_groups,
             reflection_ctx,
+            samplers,
             entry_point,
         })
     }
+
+    /// Like [`UnboundComputePipeline::bind`], `bind_func` is given the shader
+    /// variable name of each slot so host code doesn't depend on binding
+    /// numbers. Slots without a name are never passed to `bind_func`.
+    pub fn bind_by_name<'a, F>(
+        self,
+        device: &wgpu::Device,
+        mut bind_func: F,
+    ) -> Result<BoundComputePipeline, Error>
+    where
+        F: FnMut(&str, &bind_group::requirements::BindSlot<'a>),
+    {
+        self.bind(device, |slot| {
+            if let Some(name) = slot.name() {
+                bind_func(name, slot)
+            }
+        })
+    }
+
+    /// Like [`UnboundComputePipeline::bind`], with every binding taken from
+    /// the fields of `bindings` instead of a closure.
+    pub fn bind_with<'a, B>(
+        self,
+        device: &wgpu::Device,
+        bindings: &B,
+    ) -> Result<BoundComputePipeline, Error>
+    where
+        B: Bindings<'a> + ?Sized,
+    {
+        let fields = bindings.bindings();
+        traits::check_targets(&fields, &self.reflection_ctx.bind_groups)?;
+
+        let mut wrong_kinds = vec![];
+        let bound = self.bind(device, |slot| {
+            if let Err(problem) = traits::fill_slot(&fields, slot) {
+                wrong_kinds.push((slot.loc(), problem));
+            }
+        });
+
+        traits::with_wrong_kinds(bound, wrong_kinds)
+    }
 }
 
 /// A structure holding user enriched reflection info and book keeping
@@ -274,42 +462,190 @@ impl UnboundComputePipeline {
 #[derive(Debug, Clone)]
 pub struct ComputeReflector {
     bind_groups: BindGroups,
+    parameters: Vec<Parameter>,
+    labels: Labels,
+    /// sets whose full layout is used by every entry point
+    shared_sets: Vec<u32>,
+    /// downlevel flags of the adapter, see [`ComputeReflector::set_downlevel_flags`]
+    downlevel: wgpu::DownlevelFlags,
     naga_mod: wgpu::naga::Module,
+    /// locates validation errors in the original source
+    source_map: SourceMap,
 }
 
-// TODO: Add Pixel reflection context
 impl ComputeReflector {
     pub fn new_compute(source: wgpu::ShaderSource) -> Result<Self, Error> {
-        let (directives, modified_source) = preprocessing::process(&source)?;
+        Self::new_compute_with(source, &PreprocessOptions::default())
+    }
 
-        let naga_mod = match modified_source {
-            #[cfg(feature = "wgsl")]
-            wgpu::ShaderSource::Wgsl(src) => {
-                let mut parser = front::wgsl::Frontend::new();
-                parser.parse(&src)?
-            }
-            #[cfg(feature = "glsl")]
-            wgpu::ShaderSource::Glsl {
-                shader,
-                stage,
-                defines,
-            } => {
-                let mut options = front::glsl::Options::from(stage);
-                options.defines = defines;
-                let mut parser = front::glsl::Frontend::default();
-                parser.parse(&options, &shader)?
-            }
-            _ => return Err(Error::UnsupportedSourceType),
-        };
+    /// Like [`ComputeReflector::new_compute`], `options` control how the source
+    /// is preprocessed and how it is named in diagnostics.
+    pub fn new_compute_with(
+        source: wgpu::ShaderSource,
+        options: &PreprocessOptions,
+    ) -> Result<Self, Error> {
+        let (directives, naga_mod, source_map) = parse_module(source, options)?;
 
         let bind_groups = BindGroups::new(&naga_mod, &directives)?;
+        let parameters = parameters::reflect_parameters(&naga_mod, &directives)?;
+
+        let labels = Labels::new(directives.label().map(str::to_owned), &bind_groups);
 
         Ok(Self {
             bind_groups,
+            parameters,
+            labels,
+            shared_sets: vec![],
+            downlevel: wgpu::DownlevelFlags::empty(),
             naga_mod,
+            source_map,
         })
     }
 
+    /// The base label given with `#pragma label "..."`.
+    pub fn label(&self) -> Option<&str> {
+        self.labels.base()
+    }
+
+    /// `"{label}/{suffix}"`, the label used for the objects kinnara creates.
+    pub fn derived_label(&self, suffix: &str) -> Option<String> {
+        self.labels.derived(suffix)
+    }
+
+    /// A label for resources bound at `(set, binding)`, derived from the
+    /// base label and the binding's `label` option or variable name.
+    /// Useful for labelling the buffers and textures you bind there.
+    pub fn binding_label(&self, set: u32, binding: u32) -> Option<String> {
+        self.reflected().binding_label(set, binding)
+    }
+
+    /// The shader variable bound at `(set, binding)`, for anonymous
+    /// glsl blocks this is the block name.
+    pub fn binding_name(&self, set: u32, binding: u32) -> Option<&str> {
+        self.reflected().binding_name(set, binding)
+    }
+
+    /// The `(set, binding)` of the shader variable called `name`.
+    pub fn find_binding(&self, name: &str) -> Option<(u32, u32)> {
+        self.reflected().find_binding(name)
+    }
+
+    fn set_label(&self, set: u32) -> Option<&str> {
+        self.labels.set(set)
+    }
+
+    fn reflected(&self) -> Reflected<'_> {
+        Reflected {
+            bind_groups: &self.bind_groups,
+            labels: &self.labels,
+        }
+    }
+
+    /// Every uniform or push constant member annotated with a typed
+    /// variable pragma, in declaration order.
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    /// A zeroed [`HostBuffer`] laid out like the uniform or storage buffer
+    /// bound at `(set, binding)`.
+    pub fn host_buffer(&self, set: u32, binding: u32) -> Result<HostBuffer, HostTypeError> {
+        let global = self
+            .buffer_global(set, binding)
+            .ok_or(HostTypeError::NoSuchBuffer(set, binding))?;
+        HostBuffer::for_global(&self.naga_mod, global)
+    }
+
+    /// A zeroed [`HostBuffer`] laid out like the shader's push constants.
+    pub fn push_constant_buffer(&self) -> Result<HostBuffer, HostTypeError> {
+        let global = self
+            .push_constant_global()
+            .ok_or(HostTypeError::NoPushConstants)?;
+        HostBuffer::for_global(&self.naga_mod, global)
+    }
+
+    /// Check the encase type `T` is laid out like the uniform or storage
+    /// buffer bound at `(set, binding)`, the returned handle writes `T`s
+    /// for it.
+    #[cfg(feature = "encase")]
+    pub fn typed_binding<T>(&self, set: u32, binding: u32) -> Result<TypedBinding<T>, Error>
+    where
+        T: encase::ShaderType,
+        T::ExtraMetadata: MemberOffsets,
+    {
+        let global = self
+            .buffer_global(set, binding)
+            .ok_or(HostTypeError::NoSuchBuffer(set, binding))?;
+        let loc = LayoutLoc::Binding(set, binding);
+        Ok(shader_type::check(&self.naga_mod, global.ty, loc)?)
+    }
+
+    /// Like [`ComputeReflector::typed_binding`], for the push constants.
+    #[cfg(feature = "encase")]
+    pub fn typed_push_constants<T>(&self) -> Result<TypedBinding<T>, Error>
+    where
+        T: encase::ShaderType,
+        T::ExtraMetadata: MemberOffsets,
+    {
+        let global = self
+            .push_constant_global()
+            .ok_or(HostTypeError::NoPushConstants)?;
+        let loc = LayoutLoc::PushConstants;
+        Ok(shader_type::check(&self.naga_mod, global.ty, loc)?)
+    }
+
+    fn buffer_global(&self, set: u32, binding: u32) -> Option<&wgpu::naga::GlobalVariable> {
+        let target = wgpu::naga::ResourceBinding {
+            group: set,
+            binding,
+        };
+
+        self.naga_mod
+            .global_variables
+            .iter()
+            .map(|(_, global)| global)
+            .find(|global| {
+                matches!(
+                    global.space,
+                    wgpu::naga::AddressSpace::Uniform | wgpu::naga::AddressSpace::Storage { .. }
+                ) && global.binding.as_ref() == Some(&target)
+            })
+    }
+
+    fn push_constant_global(&self) -> Option<&wgpu::naga::GlobalVariable> {
+        self.naga_mod
+            .global_variables
+            .iter()
+            .map(|(_, global)| global)
+            .find(|global| global.space == wgpu::naga::AddressSpace::PushConstant)
+    }
+
+    /// Compare the shader's bindings and push constants with the interface
+    /// `S` expects, every difference is reported at once. Doesn't need a
+    /// device, so it can run in plain unit tests.
+    pub fn check_signature<S: BindingSignature>(&self) -> Result<(), Error> {
+        let signature = S::signature();
+        let entries = (0..=self.bind_group_count() as u32).flat_map(|set| {
+            self.iter_bind_group_entries(set)
+                .map(move |entry| (set, entry))
+        });
+        let layout = |set, binding| {
+            self.host_buffer(set, binding)
+                .ok()
+                .map(|buffer| buffer.ty().clone())
+        };
+        let push_constants = self
+            .push_constant_buffer()
+            .ok()
+            .map(|buffer| buffer.ty().clone());
+
+        let mismatches = signature::diff(&signature, entries, layout, push_constants);
+        if !mismatches.is_empty() {
+            return Err(Error::SignatureMismatch(mismatches));
+        }
+        Ok(())
+    }
+
     pub fn work_group_size(&self, entry_point: &str) -> Option<[u32; 3]> {
         self.bind_groups.work_group_size(entry_point)
     }
@@ -322,50 +658,112 @@ impl ComputeReflector {
         self.bind_groups.push_constant_range.as_deref()
     }
 
-    /// TODO: document the shit out of this, it's fairly opaque
-    /// essentially it expects you to write a map function which takes a set
-    /// of required bindings, then fullfills them, it returns none if you fail.
-    /// TODO: this should return a result describing EXACTLY what went wrong
+    /// Create a bind group for `set` with the full layout of the set.
+    ///
+    /// `func` is called once for every binding of the set, samplers
+    /// included, with a [`BindSlot`] describing the binding: its location,
+    /// shader variable name and layout entry. It fills the slot with the
+    /// resource to bind, or with one per element for binding arrays.
+    ///
+    /// Slots left empty are reported as [`BindingProblem::Missing`], and
+    /// resources that don't fit their layout entry (usage, size, offset
+    /// alignment, view dimension or format, array length) with the matching
+    /// [`BindingProblem`]. Every problem of the set comes back at once in
+    /// [`Error::InvalidBindings`], before anything is handed to wgpu. What
+    /// kinnara can't check, such as plain texture views, is left to wgpu
+    /// and reported as [`Error::Validation`].
     pub fn create_bind_group<'a, F>(
         &self,
         device: &wgpu::Device,
         set: u32,
-        mut func: F,
+        func: F,
     ) -> Result<wgpu::BindGroup, Error>
     where
         F: FnMut(&bind_group::requirements::BindSlot<'a>),
     {
-        let layout = self.create_bind_group_layout(device, set);
+        self.create_bind_group_with(device, set, &Samplers::default(), func)
+    }
 
-        let (good, bad): (Vec<_>, _) = self
-            .bind_groups
-            .get_bind_group_layout_entries(set)
-            .iter()
-            .map(|entry| {
-                let req = BindSlot::from_entry(set, entry);
-                func(&req);
-                let resource = wgpu::BindingResource::try_from(req)?;
-                Ok(BindGroupEntry {
-                    binding: entry.binding,
-                    resource,
-                })
-            })
-            .partition(|e| e.is_ok());
+    /// Like [`ComputeReflector::create_bind_group`], sampler bindings with
+    /// a sampler in `samplers` are bound to it and never passed to `func`.
+    pub fn create_bind_group_with<'a, F>(
+        &self,
+        device: &wgpu::Device,
+        set: u32,
+        samplers: &Samplers,
+        func: F,
+    ) -> Result<wgpu::BindGroup, Error>
+    where
+        F: FnMut(&bind_group::requirements::BindSlot<'a>),
+    {
+        self.reflected()
+            .create_bind_group_with(device, set, samplers, func)
+    }
 
-        if !bad.is_empty() {
-            let res: Vec<_> = bad.into_iter().filter_map(Result::err).collect();
-            return Err(Error::MissingBindings(res));
-        }
+    /// Like [`ComputeReflector::create_bind_group`], with every binding of
+    /// `set` taken from the fields of `bindings`. Fields for other sets are
+    /// ignored.
+    pub fn create_bind_group_from<'a, B>(
+        &self,
+        device: &wgpu::Device,
+        set: u32,
+        bindings: &B,
+    ) -> Result<wgpu::BindGroup, Error>
+    where
+        B: Bindings<'a> + ?Sized,
+    {
+        let fields = bindings.bindings();
+        traits::check_targets(&fields, &self.bind_groups)?;
+
+        let mut wrong_kinds = vec![];
+        let group = self.create_bind_group(device, set, |slot| {
+            if let Err(problem) = traits::fill_slot(&fields, slot) {
+                wrong_kinds.push((slot.loc(), problem));
+            }
+        });
 
-        let good: Vec<_> = good.into_iter().collect::<Result<_, _>>().unwrap();
+        traits::with_wrong_kinds(group, wrong_kinds)
+    }
 
-        let desc = wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &layout,
-            entries: good.as_slice(),
-        };
+    /// Like [`ComputeReflector::create_bind_group_with`], for the layout of `set`
+    /// used by `entry_point`'s pipeline.
+    pub fn create_bind_group_for<'a, F>(
+        &self,
+        device: &wgpu::Device,
+        entry_point: &str,
+        set: u32,
+        samplers: &Samplers,
+        func: F,
+    ) -> Result<wgpu::BindGroup, Error>
+    where
+        F: FnMut(&bind_group::requirements::BindSlot<'a>),
+    {
+        let entries = self.bind_group_layout_entries_for(entry_point, set)?;
+        let label = self.entry_point_set_label(entry_point, set);
+        build_bind_group(
+            device,
+            set,
+            label.as_deref(),
+            &entries,
+            &self.bind_groups,
+            samplers,
+            func,
+        )
+    }
+
+    /// The descriptor of the sampler created for a sampler binding,
+    /// built from its sampler hint. `None` for anything but single samplers.
+    pub fn sampler_descriptor(
+        &self,
+        set: u32,
+        binding: u32,
+    ) -> Option<wgpu::SamplerDescriptor<'static>> {
+        self.bind_groups.sampler_descriptor(set, binding)
+    }
 
-        Ok(device.create_bind_group(&desc))
+    /// Create a sampler for every single sampler binding from its sampler hint.
+    pub fn create_samplers(&self, device: &wgpu::Device) -> Samplers {
+        self.reflected().create_samplers(device)
     }
 
     pub fn create_compute_pipeline(
@@ -374,28 +772,30 @@ impl ComputeReflector {
         device: &wgpu::Device,
         options: wgpu::PipelineCompilationOptions,
     ) -> Result<ComputePipeline, Error> {
+        let module_label = self.derived_label("module");
+        let pipeline_label = self.derived_label("pipeline");
         let module_desc = ShaderModuleDescriptor {
-            label: None,
+            label: module_label.as_deref(),
             source: ShaderSource::Naga(std::borrow::Cow::Owned(self.naga_mod.clone())),
         };
 
-        let layout = self.create_pipeline_layout(device);
-
-        device
-            .wgpu_try(ErrorFilter::Validation, |dev| {
-                let module = dev.create_shader_module(module_desc);
-                let pipeline_desc = ComputePipelineDescriptor {
-                    label: None,
-                    layout: Some(&layout),
-                    module: &module,
-                    entry_point,
-                    compilation_options: options,
-                    cache: None,
-                };
-
-                dev.create_compute_pipeline(&pipeline_desc)
-            })
-            .map_err(Error::from)
+        validate_module(device, self.downlevel, &self.naga_mod, &self.source_map)?;
+
+        let pipeline = device.wgpu_try(ErrorFilter::Validation, |dev| {
+            let layout = self.create_pipeline_layout_for(dev, entry_point)?;
+            let module = dev.create_shader_module(module_desc);
+            let pipeline_desc = ComputePipelineDescriptor {
+                label: pipeline_label.as_deref(),
+                layout: Some(&layout),
+                module: &module,
+                entry_point,
+                compilation_options: options,
+                cache: None,
+            };
+
+            Ok::<_, Error>(dev.create_compute_pipeline(&pipeline_desc))
+        })??;
+        Ok(pipeline)
     }
 
     pub fn create_pipeline_layout(&self, device: &wgpu::Device) -> wgpu::PipelineLayout {
@@ -408,8 +808,9 @@ impl ComputeReflector {
 
         let bind_group_layouts: Vec<_> = bind_group_layouts.iter().collect();
 
+        let label = self.derived_label("pipeline_layout");
         let desc = wgpu::PipelineLayoutDescriptor {
-            label: None,
+            label: label.as_deref(),
             bind_group_layouts: bind_group_layouts.as_slice(),
             push_constant_ranges: push_constant_range,
         };
@@ -417,6 +818,102 @@ impl ComputeReflector {
         device.create_pipeline_layout(&desc)
     }
 
+    /// Like [`ComputeReflector::create_pipeline_layout`], with only the bindings
+    /// and push constants `entry_point` uses, besides those of shared sets.
+    pub fn create_pipeline_layout_for(
+        &self,
+        device: &wgpu::Device,
+        entry_point: &str,
+    ) -> Result<wgpu::PipelineLayout, Error> {
+        let push_constant_range = self.push_constant_range_for(entry_point)?.unwrap_or(&[]);
+
+        let bind_group_layouts = (0..self.set_count_for(entry_point)?)
+            .map(|set| self.create_bind_group_layout_for(device, entry_point, set as u32))
+            .collect::<Result<Vec<_>, _>>()?;
+        let bind_group_layouts: Vec<_> = bind_group_layouts.iter().collect();
+
+        let label = self.derived_label(&format!("{entry_point}/pipeline_layout"));
+        let desc = wgpu::PipelineLayoutDescriptor {
+            label: label.as_deref(),
+            bind_group_layouts: bind_group_layouts.as_slice(),
+            push_constant_ranges: push_constant_range,
+        };
+
+        Ok(device.create_pipeline_layout(&desc))
+    }
+
+    /// Use the full layout of `set` in every entry point's pipeline layout,
+    /// so bind groups made with [`ComputeReflector::create_bind_group`]
+    /// can be shared between the pipelines of different entry points.
+    pub fn share_set(&mut self, set: u32) {
+        if !self.shared_sets.contains(&set) {
+            self.shared_sets.push(set);
+        }
+    }
+
+    /// The downlevel flags of the adapter pipelines are created on, from
+    /// `adapter.get_downlevel_capabilities()`. The module is validated
+    /// against them before it reaches wgpu, without them cube map arrays
+    /// and per sample shading are rejected.
+    pub fn set_downlevel_flags(&mut self, flags: wgpu::DownlevelFlags) {
+        self.downlevel = flags;
+    }
+
+    /// Number of bind group layouts in `entry_point`'s pipeline layout.
+    pub fn set_count_for(&self, entry_point: &str) -> Result<usize, Error> {
+        Ok(self
+            .bind_groups
+            .entry_point_set_count(entry_point, &self.shared_sets)?)
+    }
+
+    /// The entries of `set` used by `entry_point`, every entry for shared sets.
+    pub fn bind_group_layout_entries_for(
+        &self,
+        entry_point: &str,
+        set: u32,
+    ) -> Result<Vec<wgpu::BindGroupLayoutEntry>, Error> {
+        let shared = self.shared_sets.contains(&set);
+        Ok(self
+            .bind_groups
+            .entry_point_layout_entries(entry_point, set, shared)?)
+    }
+
+    pub fn create_bind_group_layout_for(
+        &self,
+        device: &wgpu::Device,
+        entry_point: &str,
+        set: u32,
+    ) -> Result<wgpu::BindGroupLayout, Error> {
+        let entries = self.bind_group_layout_entries_for(entry_point, set)?;
+        let label = self.entry_point_set_label(entry_point, set);
+        Ok(
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: label.as_deref(),
+                entries: &entries,
+            }),
+        )
+    }
+
+    /// The push constant ranges of the module if `entry_point` uses them.
+    pub fn push_constant_range_for(
+        &self,
+        entry_point: &str,
+    ) -> Result<Option<&[wgpu::PushConstantRange]>, Error> {
+        let used = self
+            .bind_groups
+            .entry_point_uses_push_constants(entry_point)?;
+        Ok(self.push_constant_range().filter(|_| used))
+    }
+
+    /// Shared sets keep their module wide label.
+    fn entry_point_set_label(&self, entry_point: &str, set: u32) -> Option<String> {
+        if self.shared_sets.contains(&set) {
+            self.set_label(set).map(str::to_owned)
+        } else {
+            self.derived_label(&format!("{entry_point}/set{set}"))
+        }
+    }
+
     pub fn bind_group_count(&self) -> usize {
         self.bind_groups.bind_group_count()
     }
@@ -433,7 +930,7 @@ impl ComputeReflector {
     pub fn get_bind_group_layout_descriptor(&self, set: u32) -> BindGroupLayoutDescriptor<'_> {
         let entries = self.bind_groups.get_bind_group_layout_entries(set);
         BindGroupLayoutDescriptor {
-            label: None,
+            label: self.set_label(set),
             entries,
         }
     }
@@ -457,3 +954,59 @@ impl ComputeReflector {
         self.bind_groups.get_bind_group_layout_entry(set, binding)
     }
 }
+
+pub(crate) type ParsedModule = (preprocessing::Directives, wgpu::naga::Module, SourceMap);
+
+/// Preprocess `source` and parse it into a naga module.
+fn parse_module(
+    source: wgpu::ShaderSource,
+    options: &PreprocessOptions,
+) -> Result<ParsedModule, Error> {
+    let (directives, modified_source, source_map) = preprocessing::process(&source, options)?;
+
+    let naga_mod = match modified_source {
+        #[cfg(feature = "wgsl")]
+        wgpu::ShaderSource::Wgsl(src) => {
+            let mut parser = front::wgsl::Frontend::new();
+            parser
+                .parse(&src)
+                .map_err(|e| Error::CompilationError(diagnostic::from_wgsl(&e, &source_map)))?
+        }
+        #[cfg(feature = "glsl")]
+        wgpu::ShaderSource::Glsl {
+            shader,
+            stage,
+            defines,
+        } => {
+            let mut options = front::glsl::Options::from(stage);
+            options.defines = defines;
+            let mut parser = front::glsl::Frontend::default();
+            parser
+                .parse(&options, &shader)
+                .map_err(|e| Error::CompilationError(diagnostic::from_glsl(&e, &source_map)))?
+        }
+        _ => return Err(Error::UnsupportedSourceType),
+    };
+
+    Ok((directives, naga_mod, source_map))
+}
+
+/// Validate `module` with the capabilities of `device`, created from an
+/// adapter with the `downlevel` flags. wgpu validates the module again
+/// when it is created, but can only report failures by panicking since
+/// naga modules come without their source.
+pub(crate) fn validate_module(
+    device: &wgpu::Device,
+    downlevel: wgpu::DownlevelFlags,
+    module: &wgpu::naga::Module,
+    source_map: &SourceMap,
+) -> Result<(), Error> {
+    let mut validator = wgpu::naga::valid::Validator::new(
+        wgpu::naga::valid::ValidationFlags::all(),
+        wgpu_utils::capabilities(device.features(), downlevel),
+    );
+    validator
+        .validate(module)
+        .map(drop)
+        .map_err(|e| Error::CompilationError(diagnostic::from_validation(&e, source_map)))
+}
@@ -0,0 +1,207 @@
+use crate::preprocessing::{Directives, GlobalVarHint, VarType};
+use thiserror::Error;
+use wgpu::naga::{self, AddressSpace, TypeInner};
+
+/// Where the bytes of a [`Parameter`] live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterLocation {
+    Uniform { set: u32, binding: u32 },
+    PushConstant,
+}
+
+/// A user facing shader parameter, declared with a typed variable pragma
+/// such as `#pragma float super.name : range(0..1) = 0.5`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// dotted path of the member as written in the pragma
+    pub path: String,
+    pub ty: VarType,
+    pub location: ParameterLocation,
+    /// byte offset of the member from the start of its owning buffer
+    pub offset: u32,
+    /// size of the member in bytes
+    pub size: u32,
+}
+
+#[derive(Debug, Error)]
+pub enum ParameterError {
+    #[error("No uniform or push constant member found for parameter `{0}`")]
+    Unresolved(String),
+    #[error("Parameter `{path}` is declared as `{declared}` but the shader member is `{found}`")]
+    TypeMismatch {
+        path: String,
+        declared: String,
+        found: String,
+    },
+}
+
+/// Resolve every variable hint against the uniform and push constant
+/// globals of the module.
+pub fn reflect_parameters(
+    module: &naga::Module,
+    directives: &Directives,
+) -> Result<Vec<Parameter>, ParameterError> {
+    directives
+        .var_hints()
+        .iter()
+        .map(|hint| {
+            module
+                .global_variables
+                .iter()
+                .find_map(|(_, global)| resolve(module, global, hint))
+                .ok_or_else(|| ParameterError::Unresolved(hint.path.join(".")))?
+        })
+        .collect()
+}
+
+/// Returns `None` if the hint doesn't refer to a member of this global,
+/// and an error if it does but the declared type doesn't fit the member.
+fn resolve(
+    module: &naga::Module,
+    global: &naga::GlobalVariable,
+    hint: &GlobalVarHint,
+) -> Option<Result<Parameter, ParameterError>> {
+    let location = match (global.space, &global.binding) {
+        (AddressSpace::Uniform, Some(binding)) => ParameterLocation::Uniform {
+            set: binding.group,
+            binding: binding.binding,
+        },
+        (AddressSpace::PushConstant, _) => ParameterLocation::PushConstant,
+        _ => return None,
+    };
+
+    let type_name = module.types[global.ty].name.as_deref();
+    let (root, rest) = hint.path.split_first()?;
+
+    // named globals and blocks are addressed through their name, the members
+    // of anonymous glsl blocks are globals in their own right.
+    let members =
+        if global.name.as_deref() == Some(root.as_str()) || type_name == Some(root.as_str()) {
+            rest
+        } else if global.name.is_none() {
+            hint.path.as_slice()
+        } else {
+            return None;
+        };
+
+    let (offset, ty) = member_offset(module, global.ty, members)?;
+    let inner = &module.types[ty].inner;
+
+    if !type_matches(&hint.ty, inner) {
+        return Some(Err(ParameterError::TypeMismatch {
+            path: hint.path.join("."),
+            declared: hint.ty.type_name(),
+            found: describe(inner),
+        }));
+    }
+
+    Some(Ok(Parameter {
+        path: hint.path.join("."),
+        ty: hint.ty.clone(),
+        location,
+        offset,
+        size: host_size(module, ty),
+    }))
+}
+
+fn type_matches(declared: &VarType, inner: &TypeInner) -> bool {
+    let float = naga::Scalar::F32;
+    let uint = naga::Scalar::U32;
+    let sint = naga::Scalar::I32;
+
+    match (declared, inner) {
+        (VarType::Float { .. }, TypeInner::Scalar(s)) => *s == float,
+        (VarType::Uint { .. }, TypeInner::Scalar(s)) => *s == uint,
+        (VarType::Sint { .. }, TypeInner::Scalar(s)) => *s == sint,
+        // wgsl has no host shareable booleans, so allow flagging a u32 as one
+        (VarType::Bool { .. }, TypeInner::Scalar(s)) => {
+            s.kind == naga::ScalarKind::Bool || *s == uint
+        }
+        (VarType::Color { .. }, TypeInner::Vector { size, scalar }) => {
+            *scalar == float && matches!(size, naga::VectorSize::Tri | naga::VectorSize::Quad)
+        }
+        (VarType::FloatVec { size: n, .. }, TypeInner::Vector { size, scalar }) => {
+            *scalar == float && *size as u8 == *n
+        }
+        (VarType::UintVec { size: n, .. }, TypeInner::Vector { size, scalar }) => {
+            *scalar == uint && *size as u8 == *n
+        }
+        (VarType::SintVec { size: n, .. }, TypeInner::Vector { size, scalar }) => {
+            *scalar == sint && *size as u8 == *n
+        }
+        (
+            VarType::Matrix { columns, rows, .. },
+            TypeInner::Matrix {
+                columns: c,
+                rows: r,
+                scalar,
+            },
+        ) => *scalar == float && *c as u8 == *columns && *r as u8 == *rows,
+        _ => false,
+    }
+}
+
+/// A short glsl flavoured description of a naga type for error messages.
+fn describe(inner: &TypeInner) -> String {
+    fn prefix(scalar: &naga::Scalar) -> &'static str {
+        match scalar.kind {
+            naga::ScalarKind::Sint => "i",
+            naga::ScalarKind::Uint => "u",
+            naga::ScalarKind::Bool => "b",
+            _ if scalar.width == 8 => "d",
+            _ => "",
+        }
+    }
+
+    match inner {
+        TypeInner::Scalar(s) => match s.kind {
+            naga::ScalarKind::Sint => "int".into(),
+            naga::ScalarKind::Uint => "uint".into(),
+            naga::ScalarKind::Bool => "bool".into(),
+            _ if s.width == 8 => "double".into(),
+            _ => "float".into(),
+        },
+        TypeInner::Vector { size, scalar } => format!("{}vec{}", prefix(scalar), *size as u8),
+        TypeInner::Matrix {
+            columns,
+            rows,
+            scalar,
+        } => format!("{}mat{}x{}", prefix(scalar), *columns as u8, *rows as u8),
+        TypeInner::Struct { .. } => "struct".into(),
+        TypeInner::Array { .. } => "array".into(),
+        _ => "opaque type".into(),
+    }
+}
+
+/// naga gives booleans a width of one byte, but glsl stores
+/// them as 32 bit values in host shareable memory.
+fn host_size(module: &naga::Module, ty: naga::Handle<naga::Type>) -> u32 {
+    match module.types[ty].inner {
+        TypeInner::Scalar(naga::Scalar {
+            kind: naga::ScalarKind::Bool,
+            ..
+        }) => 4,
+        ref inner => inner.size(module.to_ctx()),
+    }
+}
+
+/// Walk struct members by name, accumulating their offsets.
+fn member_offset(
+    module: &naga::Module,
+    ty: naga::Handle<naga::Type>,
+    path: &[String],
+) -> Option<(u32, naga::Handle<naga::Type>)> {
+    let Some((name, rest)) = path.split_first() else {
+        return Some((0, ty));
+    };
+
+    let TypeInner::Struct { members, .. } = &module.types[ty].inner else {
+        return None;
+    };
+
+    let member = members
+        .iter()
+        .find(|m| m.name.as_deref() == Some(name.as_str()))?;
+
+    member_offset(module, member.ty, rest).map(|(offset, ty)| (member.offset + offset, ty))
+}
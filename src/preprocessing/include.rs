@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+
+use wgpu::naga::FastHashMap;
+
+/// The contents of an included file.
+#[derive(Debug, Clone)]
+pub struct ResolvedInclude {
+    /// Identifies the file, used for include guards, cycle detection
+    /// and diagnostics. Two includes resolving to the same name are
+    /// the same file.
+    pub name: String,
+    pub source: String,
+}
+
+/// Loads the files named by `#include` and `#import` directives.
+pub trait IncludeResolver {
+    /// Resolve `path` as written in the directive, `includer` is the
+    /// name of the file containing the directive.
+    fn resolve(&self, path: &str, includer: &str) -> Result<ResolvedInclude, String>;
+}
+
+/// Resolves includes relative to the including file first,
+/// then against each of the search paths in order.
+#[derive(Debug, Clone, Default)]
+pub struct FileSystemResolver {
+    search_paths: Vec<PathBuf>,
+}
+
+impl FileSystemResolver {
+    pub fn new<P: Into<PathBuf>>(search_paths: impl IntoIterator<Item = P>) -> Self {
+        Self {
+            search_paths: search_paths.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl IncludeResolver for FileSystemResolver {
+    fn resolve(&self, path: &str, includer: &str) -> Result<ResolvedInclude, String> {
+        let includer = Path::new(includer);
+        let relative = includer
+            .is_file()
+            .then(|| includer.parent())
+            .flatten()
+            .map(|dir| dir.join(path));
+
+        let found = relative
+            .into_iter()
+            .chain(self.search_paths.iter().map(|dir| dir.join(path)))
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(|| format!("could not find include `{path}`"))?;
+
+        let read_err = |e: std::io::Error| format!("could not read `{}`: {e}", found.display());
+        let canonical = found.canonicalize().map_err(read_err)?;
+        let source = std::fs::read_to_string(&canonical).map_err(read_err)?;
+
+        Ok(ResolvedInclude {
+            name: canonical.display().to_string(),
+            source,
+        })
+    }
+}
+
+/// Resolves includes from a fixed set of named sources,
+/// names are matched exactly.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: FastHashMap<String, String>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.insert(name, source);
+        self
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, source: impl Into<String>) {
+        self.files.insert(name.into(), source.into());
+    }
+}
+
+impl IncludeResolver for MemoryResolver {
+    fn resolve(&self, path: &str, _includer: &str) -> Result<ResolvedInclude, String> {
+        self.files
+            .get(path)
+            .map(|source| ResolvedInclude {
+                name: path.to_owned(),
+                source: source.clone(),
+            })
+            .ok_or_else(|| format!("could not find include `{path}`"))
+    }
+}
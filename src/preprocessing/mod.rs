@@ -1,90 +1,602 @@
+mod include;
 mod sampler_hint;
+mod source_map;
+mod texture_hint;
 mod uniform_hint;
 mod var_hint;
+mod vertex_hint;
 
 use derive_more::From;
-use std::num::NonZeroU32;
+use std::borrow::Cow;
 use struct_patch::Patch;
 use thiserror::Error;
-use wgpu::naga::{FastHashMap, ResourceBinding};
+use wgpu::naga::{FastHashMap, FastHashSet};
 
+use crate::diagnostic::Diagnostic;
+
+pub use include::{FileSystemResolver, IncludeResolver, MemoryResolver, ResolvedInclude};
 pub(crate) use sampler_hint::*;
+pub(crate) use source_map::SourceMap;
+pub(crate) use texture_hint::*;
 pub(crate) use uniform_hint::*;
+pub(crate) use var_hint::GlobalVarHint;
+pub use var_hint::VarType;
+pub(crate) use vertex_hint::*;
 
 #[derive(Debug, Error)]
 pub enum PreprocessingError {
-    #[error("Error Parsing : {0}")]
-    ParsingError(String),
+    #[error("Error Parsing :\n{0}")]
+    ParsingError(Diagnostic),
+    #[error("Error resolving include :\n{0}")]
+    IncludeError(Diagnostic),
     #[error("Unsupported source type, only wgsl and glsl supported.")]
     UnsupportedSource,
 }
 
-#[derive(Debug)]
-pub struct TextureHint {}
-
-#[derive(Debug)]
-pub struct ImageHint {}
-
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Directives {
+    label: Option<String>,
+
     uniform_hint_base: UniformHintPatch,
-    uniform_hints: FastHashMap<ResourceBinding, UniformHint>,
+    uniform_hints: FastHashMap<String, UniformHintPatch>,
 
     sampler_hint_base: SamplerHintPatch,
-    sampler_hint: FastHashMap<ResourceBinding, SamplerHint>,
+    sampler_hints: FastHashMap<String, SamplerHintPatch>,
+
+    texture_hint_base: TextureHintPatch,
+    texture_hints: FastHashMap<String, TextureHintPatch>,
+
+    vertex_hint_base: VertexHintPatch,
+    vertex_hints: FastHashMap<String, VertexHintPatch>,
+
+    var_hints: Vec<GlobalVarHint>,
+    binding_labels: FastHashMap<String, String>,
+}
+
+/// Hints are looked up by every name a global answers to,
+/// the first name with a hint wins.
+fn patched<'n, T, P>(
+    base: &P,
+    hints: &FastHashMap<String, P>,
+    names: impl IntoIterator<Item = &'n str>,
+) -> T
+where
+    T: Default + Patch<P>,
+    P: Clone,
+{
+    let mut hint = T::default();
+    hint.apply(base.clone());
+    if let Some(patch) = names.into_iter().find_map(|name| hints.get(name)) {
+        hint.apply(patch.clone());
+    }
+    hint
 }
 
 impl Directives {
     /// Returns uniform hint, patched with defaults if
     /// unreferenced in the directives
-    pub fn get_uniform_hint(&self, binding: &ResourceBinding) -> UniformHint {
-        let mut hint = self.uniform_hints.get(binding).cloned().unwrap_or_default();
-        hint.apply(self.uniform_hint_base.clone());
-        hint
+    pub fn get_uniform_hint<'n>(&self, names: impl IntoIterator<Item = &'n str>) -> UniformHint {
+        patched(&self.uniform_hint_base, &self.uniform_hints, names)
+    }
+
+    pub fn get_sampler_hint<'n>(&self, names: impl IntoIterator<Item = &'n str>) -> SamplerHint {
+        patched(&self.sampler_hint_base, &self.sampler_hints, names)
+    }
+
+    pub fn get_texture_hint<'n>(&self, names: impl IntoIterator<Item = &'n str>) -> TextureHint {
+        patched(&self.texture_hint_base, &self.texture_hints, names)
+    }
+
+    pub fn get_vertex_hint<'n>(&self, names: impl IntoIterator<Item = &'n str>) -> VertexHint {
+        patched(&self.vertex_hint_base, &self.vertex_hints, names)
+    }
+
+    /// The names of the vertex inputs given a vertex pragma.
+    pub fn vertex_hint_names(&self) -> impl Iterator<Item = &str> {
+        self.vertex_hints.keys().map(String::as_str)
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The label given to a binding with a `label="..."` option.
+    pub fn get_binding_label<'n>(&self, names: impl IntoIterator<Item = &'n str>) -> Option<&str> {
+        names
+            .into_iter()
+            .find_map(|name| self.binding_labels.get(name))
+            .map(String::as_str)
+    }
+
+    pub fn var_hints(&self) -> &[GlobalVarHint] {
+        &self.var_hints
+    }
+
+    fn insert(&mut self, directive: Directive) -> Result<(), String> {
+        fn insert_named<P>(
+            map: &mut FastHashMap<String, P>,
+            kind: &str,
+            name: String,
+            patch: P,
+        ) -> Result<(), String> {
+            if map.contains_key(&name) {
+                return Err(format!("duplicate {kind} pragma for `{name}`"));
+            }
+            map.insert(name, patch);
+            Ok(())
+        }
+
+        match directive {
+            Directive::Texture(name, patch) => {
+                insert_named(&mut self.texture_hints, "texture", name, patch)?
+            }
+            Directive::Buffer(name, patch) => {
+                insert_named(&mut self.uniform_hints, "uniform", name, patch)?
+            }
+            Directive::Sampler(name, patch) => {
+                insert_named(&mut self.sampler_hints, "sampler", name, patch)?
+            }
+            Directive::Vertex(name, patch) => {
+                insert_named(&mut self.vertex_hints, "vertex", name, patch)?
+            }
+            Directive::DefaultTexture(patch) => self.texture_hint_base = patch,
+            Directive::DefaultVertex(patch) => self.vertex_hint_base = patch,
+            Directive::DefaultBuffer(patch) => self.uniform_hint_base = patch,
+            Directive::DefaultSampler(patch) => self.sampler_hint_base = patch,
+            Directive::Var(hint) => {
+                if self.var_hints.iter().any(|h| h.path == hint.path) {
+                    return Err(format!(
+                        "duplicate variable pragma for `{}`",
+                        hint.path.join(".")
+                    ));
+                }
+                self.var_hints.push(hint)
+            }
+            Directive::Label(label) => {
+                if self.label.is_some() {
+                    return Err("duplicate label pragma".to_owned());
+                }
+                self.label = Some(label)
+            }
+            Directive::BindingLabel(name, label) => {
+                insert_named(&mut self.binding_labels, "label", name, label)?
+            }
+        }
+
+        Ok(())
     }
+}
+
+/// Options controlling how user source is preprocessed.
+#[derive(Clone, Default)]
+pub struct PreprocessOptions<'a> {
+    /// The name the source is reported under in diagnostics,
+    /// `<source>` if none is given.
+    pub file_name: Option<&'a str>,
+    /// Loads the files named by `#include` and `#import` directives,
+    /// including a file without a resolver is an error.
+    pub include_resolver: Option<&'a dyn IncludeResolver>,
+}
 
-    pub fn get_sampler_hint(&self, binding: &ResourceBinding) -> SamplerHint {
-        let mut hint = self.sampler_hint.get(binding).cloned().unwrap_or_default();
-        hint.apply(self.sampler_hint_base.clone());
-        hint
+impl std::fmt::Debug for PreprocessOptions<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreprocessOptions")
+            .field("file_name", &self.file_name)
+            .field("include_resolver", &self.include_resolver.is_some())
+            .finish()
     }
 }
 
+/// Extracts kinnara `#pragma` and `// @kinnara` directives from the source,
+/// replacing each directive line with an empty one so line numbers reported
+/// by naga still match the user's source. Pragmas kinnara doesn't know about
+/// are left in place. `#include` and `#import` lines are replaced with
+/// the preprocessed contents of the named file. The returned [`SourceMap`]
+/// maps the output back to the user's sources.
 pub fn process<'a>(
     source: &'a wgpu::ShaderSource,
-) -> Result<(Directives, wgpu::ShaderSource<'a>), PreprocessingError> {
-    let deleteme_out = source.to_owned().clone();
-    let directives = Default::default();
-
+    options: &PreprocessOptions,
+) -> Result<(Directives, wgpu::ShaderSource<'a>, SourceMap), PreprocessingError> {
     let src = match source {
+        #[cfg(feature = "glsl")]
         wgpu::ShaderSource::Glsl { shader, .. } => shader,
+        #[cfg(feature = "wgsl")]
         wgpu::ShaderSource::Wgsl(src) => src,
         _ => return Err(PreprocessingError::UnsupportedSource),
     };
 
-    Ok((directives, deleteme_out))
+    let mut preprocessor = Preprocessor {
+        resolver: options.include_resolver,
+        directives: Directives::default(),
+        out: String::with_capacity(src.len()),
+        source_map: SourceMap::default(),
+        stack: vec![],
+        once: FastHashSet::default(),
+    };
+    preprocessor.process_file(options.file_name.unwrap_or("<source>"), src)?;
+
+    let Preprocessor {
+        directives,
+        out,
+        source_map,
+        ..
+    } = preprocessor;
+
+    let out = match source {
+        #[cfg(feature = "glsl")]
+        wgpu::ShaderSource::Glsl { stage, defines, .. } => wgpu::ShaderSource::Glsl {
+            shader: Cow::Owned(out),
+            stage: *stage,
+            defines: defines.clone(),
+        },
+        #[cfg(feature = "wgsl")]
+        wgpu::ShaderSource::Wgsl(_) => wgpu::ShaderSource::Wgsl(Cow::Owned(out)),
+        _ => return Err(PreprocessingError::UnsupportedSource),
+    };
+
+    Ok((directives, out, source_map))
+}
+
+struct Preprocessor<'r> {
+    resolver: Option<&'r dyn IncludeResolver>,
+    directives: Directives,
+    out: String,
+    source_map: SourceMap,
+    /// files currently being expanded, innermost last
+    stack: Vec<String>,
+    /// files marked with `#pragma once`
+    once: FastHashSet<String>,
+}
+
+impl Preprocessor<'_> {
+    fn process_file(&mut self, name: &str, src: &str) -> Result<(), PreprocessingError> {
+        let file = self.source_map.add_file(name, src);
+        self.stack.push(name.to_owned());
+
+        let mut line_start = 0;
+        for line in src.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\r', '\n']);
+            let line_range = line_start..line_start + line.len();
+            let ending = line_start + content.len()..line_range.end;
+            let error = |map: &SourceMap, err: PragmaError| {
+                let location = map.file_location(file, err.span(line_start, content));
+                Diagnostic::new(err.message, location)
+            };
+
+            match parse_line(line)
+                .map_err(|e| PreprocessingError::ParsingError(error(&self.source_map, e)))?
+            {
+                Line::Source => self.copy(file, src, line_range),
+                Line::Pragma(parsed) => {
+                    for directive in parsed {
+                        self.directives.insert(directive).map_err(|e| {
+                            PreprocessingError::ParsingError(error(&self.source_map, e.into()))
+                        })?;
+                    }
+                    self.copy(file, src, ending);
+                }
+                Line::Once => {
+                    self.once.insert(name.to_owned());
+                    self.copy(file, src, ending);
+                }
+                Line::Include(path) => {
+                    // errors inside the included file already point into it
+                    match self.include(path, name) {
+                        Err(IncludeFailure::Resolve(message)) => {
+                            let diagnostic = error(&self.source_map, message.into());
+                            return Err(PreprocessingError::IncludeError(diagnostic));
+                        }
+                        Err(IncludeFailure::Nested(e)) => return Err(e),
+                        Ok(()) => {}
+                    }
+                    self.copy(file, src, ending);
+                }
+            }
+
+            line_start += line.len();
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn include(&mut self, path: &str, includer: &str) -> Result<(), IncludeFailure> {
+        let resolver = self
+            .resolver
+            .ok_or_else(|| format!("cannot include `{path}`, no include resolver was provided"))?;
+        let resolved = resolver.resolve(path, includer)?;
+
+        if self.once.contains(&resolved.name) {
+            return Ok(());
+        }
+
+        if self.stack.contains(&resolved.name) {
+            let cycle = self
+                .stack
+                .iter()
+                .skip_while(|name| **name != resolved.name)
+                .chain([&resolved.name])
+                .map(|name| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(format!("include cycle {cycle}").into());
+        }
+
+        self.process_file(&resolved.name, &resolved.source)
+            .map_err(IncludeFailure::Nested)
+    }
+
+    /// Copy `range` of `file` to the output verbatim.
+    fn copy(&mut self, file: usize, src: &str, range: std::ops::Range<usize>) {
+        let out_start = self.out.len();
+        self.out.push_str(&src[range.clone()]);
+        self.source_map.push_segment(out_start, file, range);
+    }
+}
+
+#[derive(From)]
+enum IncludeFailure {
+    Resolve(String),
+    Nested(PreprocessingError),
+}
+
+/// An error in a single pragma line.
+struct PragmaError {
+    message: String,
+    /// byte offset in the line where parsing stopped, if known
+    at: Option<usize>,
+}
+
+impl From<String> for PragmaError {
+    fn from(message: String) -> Self {
+        Self { message, at: None }
+    }
+}
+
+impl PragmaError {
+    /// The span in the file the error covers, from where parsing stopped
+    /// or the whole pragma, to the end of the line.
+    fn span(&self, line_start: usize, content: &str) -> std::ops::Range<usize> {
+        let start = self
+            .at
+            .unwrap_or_else(|| content.len() - content.trim_start().len());
+        line_start + start..line_start + content.trim_end().len()
+    }
 }
 
 #[derive(Debug, From)]
 enum Directive {
-    Texture(TextureHint),
-    Buffer(UniformHint),
-    Var(var_hint::GlobalVarHint),
-    Image(ImageHint),
-    Sampler(SamplerHint),
+    Texture(String, TextureHintPatch),
+    Buffer(String, UniformHintPatch),
+    Sampler(String, SamplerHintPatch),
+    Vertex(String, VertexHintPatch),
+    DefaultTexture(TextureHintPatch),
+    DefaultBuffer(UniformHintPatch),
+    DefaultSampler(SamplerHintPatch),
+    DefaultVertex(VertexHintPatch),
+    Var(GlobalVarHint),
+    Label(String),
+    BindingLabel(String, String),
+}
+
+/// Implemented by hint patches which can be built from
+/// a `(key=value, ...)` option list.
+trait HintOptions: Default {
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String>;
+
+    fn from_options(options: Vec<(String, &str)>) -> Result<Self, String> {
+        let mut patch = Self::default();
+        for (key, value) in options {
+            patch.set_option(&key, value)?;
+        }
+        Ok(patch)
+    }
+}
+
+fn parse_bool_option(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!(
+            "`{key}` expects `true` or `false`, found `{value}`"
+        )),
+    }
 }
 
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    character::complete::{alpha1, alphanumeric1, char, digit1},
-    combinator::{map, map_res, opt, recognize},
-    multi::{many0, separated_list1},
-    sequence::{delimited, pair},
+    bytes::complete::{is_not, tag},
+    character::complete::{alpha1, alphanumeric1, char, multispace0, space0, space1},
+    combinator::{all_consuming, map, not, opt, recognize, rest},
+    multi::{many0, many1, separated_list0, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
     IResult,
 };
 
-fn parse_pragma() {}
+/// How a single source line is handled by the preprocessor.
+enum Line<'a> {
+    /// copied to the output unchanged
+    Source,
+    Pragma(Vec<Directive>),
+    /// `#pragma once`
+    Once,
+    /// `#include "path"`, `#include <path>` or the `#import` equivalents
+    Include(&'a str),
+}
+
+/// Parses a single source line, anything which isn't an include or
+/// a kinnara directive is [`Line::Source`].
+fn parse_line(line: &str) -> Result<Line<'_>, PragmaError> {
+    if let Ok((body, _)) = include_keyword(line) {
+        let path = complete(
+            line,
+            body,
+            alt((
+                delimited(char('"'), is_not("\"\n"), char('"')),
+                delimited(char('<'), is_not(">\n"), char('>')),
+            )),
+        )?;
+        return Ok(Line::Include(path));
+    }
+
+    let Ok((body, (syntax, keyword))) = directive_keyword(line) else {
+        return Ok(Line::Source);
+    };
+
+    let directive = match keyword.as_str() {
+        "uniform" => return named_hint::<UniformHintPatch>(line, body).map(Line::Pragma),
+        "sampler" => return named_hint::<SamplerHintPatch>(line, body).map(Line::Pragma),
+        "texture" => return named_hint::<TextureHintPatch>(line, body).map(Line::Pragma),
+        "vertex" => {
+            let (name, opts) = complete(line, body, parse_named_options)?;
+            Directive::from((name, VertexHintPatch::from_options(opts)?))
+        }
+        "once" => {
+            complete(line, body, space0)?;
+            return Ok(Line::Once);
+        }
+        "default" => {
+            let (kind, opts) = complete(line, body, parse_named_options)?;
+            match kind.as_str() {
+                "uniform" => Directive::from(UniformHintPatch::from_options(opts)?),
+                "sampler" => Directive::from(SamplerHintPatch::from_options(opts)?),
+                "texture" => Directive::from(TextureHintPatch::from_options(opts)?),
+                "vertex" => Directive::from(VertexHintPatch::from_options(opts)?),
+                _ => return Err(format!("no defaults can be set for `{kind}`").into()),
+            }
+        }
+        "label" => Directive::Label(complete(line, body, parse_string)?),
+        kw if var_hint::is_var_type(kw) => {
+            let (path, range, default) = complete(line, body, var_hint::parse_var_hint)?;
+            Directive::from(GlobalVarHint::new(kw, path, range, default)?)
+        }
+        // other tools use `#pragma` too, but `@kinnara` comments are ours
+        _ => match syntax {
+            DirectiveSyntax::Pragma => return Ok(Line::Source),
+            DirectiveSyntax::Comment => {
+                return Err(format!("unknown kinnara directive `{keyword}`").into())
+            }
+        },
+    };
+
+    Ok(Line::Pragma(vec![directive]))
+}
+
+/// A hint pragma for a single binding, `label` is accepted by
+/// every kind of binding and split off from the hint options.
+fn named_hint<P>(line: &str, body: &str) -> Result<Vec<Directive>, PragmaError>
+where
+    P: HintOptions,
+    Directive: From<(String, P)>,
+{
+    let (name, mut opts) = complete(line, body, parse_named_options)?;
+
+    let label = opts
+        .iter()
+        .position(|(key, _)| key == "label")
+        .map(|idx| opts.remove(idx).1.to_owned());
+
+    let mut directives = vec![Directive::from((name.clone(), P::from_options(opts)?))];
+    if let Some(label) = label {
+        directives.push(Directive::BindingLabel(name, label));
+    }
+
+    Ok(directives)
+}
+
+/// Runs `parser` over the whole pragma body, allowing trailing whitespace
+/// and line comments.
+fn complete<'a, O>(
+    line: &str,
+    body: &'a str,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> Result<O, PragmaError> {
+    let trailing = pair(multispace0, opt(preceded(tag("//"), rest)));
+    all_consuming(terminated(parser, trailing))(body)
+        .map(|(_, out)| out)
+        .map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => PragmaError {
+                message: format!(
+                    "malformed pragma, unexpected input `{}`",
+                    e.input.trim_end()
+                ),
+                at: Some(line.len() - e.input.len()),
+            },
+            nom::Err::Incomplete(_) => "incomplete pragma".to_owned().into(),
+        })
+}
+
+fn include_keyword(input: &str) -> IResult<&str, &str> {
+    preceded(
+        tuple((space0, char('#'), space0)),
+        terminated(
+            alt((tag("include"), tag("import"))),
+            pair(not(alt((alphanumeric1, tag("_")))), space0),
+        ),
+    )(input)
+}
+
+/// Directives are written as `#pragma` lines in glsl, wgsl has no preprocessor
+/// so the same directives can be given as `// @kinnara` comments instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectiveSyntax {
+    Pragma,
+    Comment,
+}
+
+fn directive_keyword(input: &str) -> IResult<&str, (DirectiveSyntax, String)> {
+    pair(
+        alt((
+            map(
+                tuple((space0, char('#'), space0, tag("pragma"), space1)),
+                |_| DirectiveSyntax::Pragma,
+            ),
+            map(
+                tuple((space0, tag("//"), space0, tag("@kinnara"), space1)),
+                |_| DirectiveSyntax::Comment,
+            ),
+        )),
+        terminated(parse_identifier, space0),
+    )(input)
+}
+
+type NamedOptions<'a> = (String, Vec<(String, &'a str)>);
+
+/// `name (key=value, ...)`, the option list is optional.
+fn parse_named_options(input: &str) -> IResult<&str, NamedOptions<'_>> {
+    pair(
+        parse_identifier,
+        map(
+            opt(preceded(space0, parse_options)),
+            Option::unwrap_or_default,
+        ),
+    )(input)
+}
+
+fn parse_options(input: &str) -> IResult<&str, Vec<(String, &str)>> {
+    delimited(
+        pair(char('('), multispace0),
+        separated_list0(
+            delimited(multispace0, char(','), multispace0),
+            separated_pair(
+                parse_identifier,
+                delimited(space0, char('='), space0),
+                alt((
+                    delimited(char('"'), is_not("\"\n"), char('"')),
+                    recognize(many1(alt((alphanumeric1, tag("_"), tag("."))))),
+                )),
+            ),
+        ),
+        pair(multispace0, char(')')),
+    )(input)
+}
+
+fn parse_string(input: &str) -> IResult<&str, String> {
+    map(
+        delimited(char('"'), opt(is_not("\"\n")), char('"')),
+        |s: Option<&str>| s.unwrap_or_default().to_string(),
+    )(input)
+}
 
 fn parse_identifier(input: &str) -> IResult<&str, String> {
     map(
@@ -96,14 +608,6 @@ fn parse_identifier(input: &str) -> IResult<&str, String> {
     )(input)
 }
 
-fn parse_count(input: &str) -> IResult<&str, Option<NonZeroU32>> {
-    opt(delimited(
-        char('['),
-        map_res(digit1, |s: &str| s.parse::<NonZeroU32>()),
-        char(']'),
-    ))(input)
-}
-
 fn parse_global_access_name(input: &str) -> IResult<&str, Vec<String>> {
     separated_list1(char('.'), parse_identifier)(input)
 }
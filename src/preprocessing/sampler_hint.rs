@@ -1,5 +1,7 @@
 use struct_patch::Patch;
 
+use super::HintOptions;
+
 #[derive(Debug, Clone, Copy, Patch)]
 #[patch(attribute(derive(Debug, Default, Clone)))]
 pub struct SamplerHint {
@@ -18,3 +20,84 @@ impl Default for SamplerHint {
         }
     }
 }
+
+impl SamplerHint {
+    /// The descriptor of a sampler matching this hint. `comparison` is whether the
+    /// shader declares a comparison sampler, the hint's comparison function is
+    /// only used for those, `LessEqual` if none is given.
+    pub fn descriptor<'a>(
+        &self,
+        label: Option<&'a str>,
+        comparison: bool,
+    ) -> wgpu::SamplerDescriptor<'a> {
+        let compare =
+            comparison.then(|| self.comparison.unwrap_or(wgpu::CompareFunction::LessEqual));
+
+        let border_color = (self.wrap == wgpu::AddressMode::ClampToBorder)
+            .then_some(wgpu::SamplerBorderColor::TransparentBlack);
+
+        wgpu::SamplerDescriptor {
+            label,
+            address_mode_u: self.wrap,
+            address_mode_v: self.wrap,
+            address_mode_w: self.wrap,
+            mag_filter: self.filter,
+            min_filter: self.filter,
+            mipmap_filter: self.filter,
+            compare,
+            border_color,
+            ..Default::default()
+        }
+    }
+}
+
+impl HintOptions for SamplerHintPatch {
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "filter" => self.filter = Some(filter_mode(value)?),
+            "wrap" => self.wrap = Some(address_mode(value)?),
+            "comparison" => self.comparison = Some(compare_function(value)?),
+            _ => return Err(format!("unknown sampler option `{key}`")),
+        }
+        Ok(())
+    }
+}
+
+fn filter_mode(value: &str) -> Result<wgpu::FilterMode, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "nearest" => Ok(wgpu::FilterMode::Nearest),
+        "linear" => Ok(wgpu::FilterMode::Linear),
+        _ => Err(format!(
+            "invalid filter `{value}`, expected `Nearest` or `Linear`"
+        )),
+    }
+}
+
+fn address_mode(value: &str) -> Result<wgpu::AddressMode, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "clamptoedge" => Ok(wgpu::AddressMode::ClampToEdge),
+        "repeat" => Ok(wgpu::AddressMode::Repeat),
+        "mirrorrepeat" => Ok(wgpu::AddressMode::MirrorRepeat),
+        "clamptoborder" => Ok(wgpu::AddressMode::ClampToBorder),
+        _ => Err(format!(
+            "invalid wrap `{value}`, expected one of `ClampToEdge`, `Repeat`, `MirrorRepeat` or `ClampToBorder`"
+        )),
+    }
+}
+
+/// `None` disables comparison, which lets a default comparison be overridden.
+fn compare_function(value: &str) -> Result<Option<wgpu::CompareFunction>, String> {
+    let func = match value.to_ascii_lowercase().as_str() {
+        "none" => return Ok(None),
+        "never" => wgpu::CompareFunction::Never,
+        "less" => wgpu::CompareFunction::Less,
+        "equal" => wgpu::CompareFunction::Equal,
+        "lessequal" => wgpu::CompareFunction::LessEqual,
+        "greater" => wgpu::CompareFunction::Greater,
+        "notequal" => wgpu::CompareFunction::NotEqual,
+        "greaterequal" => wgpu::CompareFunction::GreaterEqual,
+        "always" => wgpu::CompareFunction::Always,
+        _ => return Err(format!("invalid comparison function `{value}`")),
+    };
+    Ok(Some(func))
+}
@@ -0,0 +1,83 @@
+use std::ops::Range;
+
+use crate::diagnostic::SourceLocation;
+
+#[derive(Debug, Clone)]
+struct SourceFile {
+    name: String,
+    text: String,
+}
+
+/// A run of bytes copied verbatim from a source file into the output.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    out_start: usize,
+    file: usize,
+    start: usize,
+    len: usize,
+}
+
+/// Maps byte offsets in the preprocessed text handed to naga
+/// back to the file and offset they were copied from.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    segments: Vec<Segment>,
+}
+
+impl SourceMap {
+    pub fn add_file(&mut self, name: &str, text: &str) -> usize {
+        self.files.push(SourceFile {
+            name: name.to_owned(),
+            text: text.to_owned(),
+        });
+        self.files.len() - 1
+    }
+
+    /// Record that `range` of `file` was appended to the output at `out_start`.
+    pub fn push_segment(&mut self, out_start: usize, file: usize, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        if let Some(last) = self.segments.last_mut() {
+            let contiguous = last.file == file
+                && last.start + last.len == range.start
+                && last.out_start + last.len == out_start;
+
+            if contiguous {
+                last.len += range.len();
+                return;
+            }
+        }
+
+        self.segments.push(Segment {
+            out_start,
+            file,
+            start: range.start,
+            len: range.len(),
+        });
+    }
+
+    /// Locate a span given in original file coordinates.
+    pub fn file_location(&self, file: usize, span: Range<usize>) -> Option<SourceLocation> {
+        let file = self.files.get(file)?;
+        Some(SourceLocation::new(&file.name, &file.text, span))
+    }
+
+    /// Locate a span of the preprocessed output in the original sources.
+    pub fn locate(&self, span: Range<usize>) -> Option<SourceLocation> {
+        let idx = self
+            .segments
+            .partition_point(|seg| seg.out_start <= span.start)
+            .checked_sub(1)?;
+        let seg = self.segments[idx];
+
+        // spans may point one past the end of the output (eof errors)
+        let rel = (span.start - seg.out_start).min(seg.len);
+        let len = span.len().min(seg.len - rel);
+        let start = seg.start + rel;
+
+        self.file_location(seg.file, start..start + len)
+    }
+}
@@ -0,0 +1,64 @@
+use struct_patch::Patch;
+
+use super::{parse_bool_option, HintOptions};
+
+/// Overrides the sample type naga infers from the texture declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleTypeHint {
+    Float,
+    Depth,
+    Uint,
+    Sint,
+}
+
+#[derive(Debug, Clone, Copy, Patch)]
+#[patch(attribute(derive(Debug, Default, Clone)))]
+pub struct TextureHint {
+    pub sample_count: u32,
+    /// float textures are assumed filterable unless noted otherwise,
+    /// multisampled textures are never filterable.
+    pub filterable: bool,
+    pub sample_type: Option<SampleTypeHint>,
+}
+
+impl Default for TextureHint {
+    fn default() -> Self {
+        Self {
+            sample_count: 1,
+            filterable: true,
+            sample_type: None,
+        }
+    }
+}
+
+impl HintOptions for TextureHintPatch {
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "sample_count" => {
+                let count = value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|ct| *ct > 0)
+                    .ok_or_else(|| format!("invalid sample count `{value}`"))?;
+                self.sample_count = Some(count);
+            }
+            "filterable" => self.filterable = Some(parse_bool_option(key, value)?),
+            "sample_type" => {
+                let ty = match value.to_ascii_lowercase().as_str() {
+                    "float" => SampleTypeHint::Float,
+                    "depth" => SampleTypeHint::Depth,
+                    "uint" => SampleTypeHint::Uint,
+                    "sint" | "int" => SampleTypeHint::Sint,
+                    _ => {
+                        return Err(format!(
+                            "invalid sample type `{value}`, expected one of `depth`, `float`, `uint` or `sint`"
+                        ))
+                    }
+                };
+                self.sample_type = Some(Some(ty));
+            }
+            _ => return Err(format!("unknown texture option `{key}`")),
+        }
+        Ok(())
+    }
+}
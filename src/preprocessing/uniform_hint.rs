@@ -1,5 +1,7 @@
 use struct_patch::Patch;
 
+use super::{parse_bool_option, HintOptions};
+
 #[derive(Debug, Clone, Copy, Patch)]
 #[patch(attribute(derive(Debug, Default, Clone)))]
 #[derive(Default)]
@@ -8,3 +10,15 @@ pub struct UniformHint {
     pub calculate_min_binding_size: bool,
 }
 
+impl HintOptions for UniformHintPatch {
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "dynamic_offset" => self.dynamic_offset = Some(parse_bool_option(key, value)?),
+            "calculate_min_size" | "calculate_size" | "calculate_min_binding_size" => {
+                self.calculate_min_binding_size = Some(parse_bool_option(key, value)?)
+            }
+            _ => return Err(format!("unknown uniform option `{key}`")),
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,43 @@
 use std::ops::Range;
 
-#[derive(Debug)]
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, digit1, multispace0, one_of, space0},
+    combinator::{map, opt, recognize, value},
+    multi::separated_list1,
+    sequence::{delimited, pair, preceded, separated_pair, tuple},
+    IResult,
+};
+
+use super::parse_global_access_name;
+
+/// Keywords which introduce a scalar typed variable pragma.
+const SCALAR_KEYWORDS: &[&str] = &["float", "uint", "int", "sint", "bool", "color"];
+
+/// Whether `keyword` introduces a typed variable pragma.
+pub fn is_var_type(keyword: &str) -> bool {
+    SCALAR_KEYWORDS.contains(&keyword)
+        || vector_keyword(keyword).is_some()
+        || matrix_keyword(keyword).is_some()
+}
+
+/// `vecN`, `ivecN` and `uvecN`, returning the prefix and component count.
+fn vector_keyword(keyword: &str) -> Option<(&str, u8)> {
+    let (prefix, size) = keyword.split_once("vec")?;
+    let size = size.parse().ok().filter(|n| (2..=4).contains(n))?;
+    matches!(prefix, "" | "i" | "u").then_some((prefix, size))
+}
+
+/// `matC` and `matCxR`, returning columns and rows.
+fn matrix_keyword(keyword: &str) -> Option<(u8, u8)> {
+    let dims = keyword.strip_prefix("mat")?;
+    let (columns, rows) = dims.split_once('x').unwrap_or((dims, dims));
+    let valid = |n: &str| n.parse().ok().filter(|n| (2..=4).contains(n));
+    Some((valid(columns)?, valid(rows)?))
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum VarType {
     Float {
         range: Option<Range<f32>>,
@@ -20,9 +57,370 @@ pub enum VarType {
     Color {
         default: Option<[f32; 4]>,
     },
+    FloatVec {
+        size: u8,
+        range: Option<Range<Vec<f32>>>,
+        default: Option<Vec<f32>>,
+    },
+    UintVec {
+        size: u8,
+        range: Option<Range<Vec<u32>>>,
+        default: Option<Vec<u32>>,
+    },
+    SintVec {
+        size: u8,
+        range: Option<Range<Vec<i32>>>,
+        default: Option<Vec<i32>>,
+    },
+    /// Components of ranges and defaults are stored column major.
+    Matrix {
+        columns: u8,
+        rows: u8,
+        range: Option<Range<Vec<f32>>>,
+        default: Option<Vec<f32>>,
+    },
+}
+
+impl VarType {
+    /// The pragma keyword this type is declared with.
+    pub fn type_name(&self) -> String {
+        match self {
+            VarType::Float { .. } => "float".into(),
+            VarType::Uint { .. } => "uint".into(),
+            VarType::Sint { .. } => "int".into(),
+            VarType::Bool { .. } => "bool".into(),
+            VarType::Color { .. } => "color".into(),
+            VarType::FloatVec { size, .. } => format!("vec{size}"),
+            VarType::UintVec { size, .. } => format!("uvec{size}"),
+            VarType::SintVec { size, .. } => format!("ivec{size}"),
+            VarType::Matrix { columns, rows, .. } => format!("mat{columns}x{rows}"),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GlobalVarHint {
+    /// dotted path from the global (or anonymous block member)
+    /// down to the annotated member, e.g. `["parent", "child"]`
+    pub path: Vec<String>,
     pub ty: VarType,
 }
+
+/// An untyped literal as written in a pragma, numbers are kept as text
+/// until the declared type of the variable is known.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal<'a> {
+    Number(&'a str),
+    Bool(bool),
+    List(Vec<Literal<'a>>),
+}
+
+impl<'a> Literal<'a> {
+    fn as_f32(&self) -> Result<f32, String> {
+        match self {
+            Literal::Number(n) => n.parse().map_err(|_| format!("invalid float `{n}`")),
+            other => Err(format!("expected a float, found {other}")),
+        }
+    }
+
+    fn as_u32(&self) -> Result<u32, String> {
+        match self {
+            Literal::Number(n) => n
+                .parse()
+                .map_err(|_| format!("invalid unsigned integer `{n}`")),
+            other => Err(format!("expected an unsigned integer, found {other}")),
+        }
+    }
+
+    fn as_i32(&self) -> Result<i32, String> {
+        match self {
+            Literal::Number(n) => n.parse().map_err(|_| format!("invalid integer `{n}`")),
+            other => Err(format!("expected an integer, found {other}")),
+        }
+    }
+
+    fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            Literal::Bool(b) => Ok(*b),
+            other => Err(format!("expected `true` or `false`, found {other}")),
+        }
+    }
+
+    /// `n` components from a list, nested lists (matrix columns) are
+    /// flattened and a lone scalar is splatted across every component.
+    fn as_components<T: Clone>(
+        &self,
+        n: usize,
+        conv: impl Fn(&Literal<'a>) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        fn flatten<'l, 'a>(lit: &'l Literal<'a>, out: &mut Vec<&'l Literal<'a>>) {
+            match lit {
+                Literal::List(items) => items.iter().for_each(|i| flatten(i, out)),
+                other => out.push(other),
+            }
+        }
+
+        if !matches!(self, Literal::List(_)) {
+            return Ok(vec![conv(self)?; n]);
+        }
+
+        let mut flat = vec![];
+        flatten(self, &mut flat);
+
+        if flat.len() != n {
+            return Err(format!("expected {n} components, found {}", flat.len()));
+        }
+
+        flat.into_iter().map(conv).collect()
+    }
+
+    fn as_color(&self) -> Result<[f32; 4], String> {
+        let Literal::List(items) = self else {
+            return Err(format!(
+                "expected a color list `[r, g, b, a]`, found {self}"
+            ));
+        };
+
+        let comps = items
+            .iter()
+            .map(Literal::as_f32)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match comps.as_slice() {
+            [r, g, b] => Ok([*r, *g, *b, 1.0]),
+            [r, g, b, a] => Ok([*r, *g, *b, *a]),
+            _ => Err(format!(
+                "expected 3 or 4 color components, found {}",
+                comps.len()
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Literal<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Literal::Number(n) => write!(f, "`{n}`"),
+            Literal::Bool(b) => write!(f, "`{b}`"),
+            Literal::List(_) => write!(f, "a list"),
+        }
+    }
+}
+
+fn typed_range<'a, T: PartialOrd>(
+    range: Option<(Literal<'a>, Literal<'a>)>,
+    conv: impl Fn(&Literal<'a>) -> Result<T, String>,
+) -> Result<Option<Range<T>>, String> {
+    let Some((start, end)) = range else {
+        return Ok(None);
+    };
+
+    let range = conv(&start)?..conv(&end)?;
+    if range.start > range.end {
+        return Err(format!("range start {start} is greater than its end {end}"));
+    }
+
+    Ok(Some(range))
+}
+
+fn check_default<T: PartialOrd + std::fmt::Debug>(
+    range: &Option<Range<T>>,
+    default: &Option<T>,
+) -> Result<(), String> {
+    match (range, default) {
+        (Some(r), Some(d)) if *d < r.start || *d > r.end => Err(format!(
+            "default {d:?} lies outside of range {:?}..{:?}",
+            r.start, r.end
+        )),
+        _ => Ok(()),
+    }
+}
+
+type Components<T> = (Option<Range<Vec<T>>>, Option<Vec<T>>);
+
+/// Component-wise equivalent of [`typed_range`] and [`check_default`].
+fn typed_components<'a, T: PartialOrd + Clone + std::fmt::Debug>(
+    n: usize,
+    range: Option<(Literal<'a>, Literal<'a>)>,
+    default: Option<Literal<'a>>,
+    conv: impl Fn(&Literal<'a>) -> Result<T, String>,
+) -> Result<Components<T>, String> {
+    let range = match range {
+        Some((start, end)) => {
+            let range = start.as_components(n, &conv)?..end.as_components(n, &conv)?;
+            if range.start.iter().zip(&range.end).any(|(s, e)| s > e) {
+                return Err(format!(
+                    "range start {:?} is greater than its end {:?} in some component",
+                    range.start, range.end
+                ));
+            }
+            Some(range)
+        }
+        None => None,
+    };
+
+    let default = default
+        .as_ref()
+        .map(|d| d.as_components(n, &conv))
+        .transpose()?;
+
+    if let (Some(r), Some(d)) = (&range, &default) {
+        let outside = d
+            .iter()
+            .zip(r.start.iter().zip(&r.end))
+            .any(|(d, (s, e))| d < s || d > e);
+
+        if outside {
+            return Err(format!(
+                "default {d:?} lies outside of range {:?}..{:?}",
+                r.start, r.end
+            ));
+        }
+    }
+
+    Ok((range, default))
+}
+
+impl GlobalVarHint {
+    /// Build a typed hint from the raw pieces of a variable pragma.
+    pub fn new(
+        keyword: &str,
+        path: Vec<String>,
+        range: Option<(Literal, Literal)>,
+        default: Option<Literal>,
+    ) -> Result<Self, String> {
+        let ty = match keyword {
+            "float" => {
+                let range = typed_range(range, Literal::as_f32)?;
+                let default = default.as_ref().map(Literal::as_f32).transpose()?;
+                check_default(&range, &default)?;
+                VarType::Float { range, default }
+            }
+            "uint" => {
+                let range = typed_range(range, Literal::as_u32)?;
+                let default = default.as_ref().map(Literal::as_u32).transpose()?;
+                check_default(&range, &default)?;
+                VarType::Uint { range, default }
+            }
+            "int" | "sint" => {
+                let range = typed_range(range, Literal::as_i32)?;
+                let default = default.as_ref().map(Literal::as_i32).transpose()?;
+                check_default(&range, &default)?;
+                VarType::Sint { range, default }
+            }
+            "bool" | "color" if range.is_some() => {
+                return Err(format!("`{keyword}` variables do not accept a range"))
+            }
+            "bool" => VarType::Bool {
+                default: default.as_ref().map(Literal::as_bool).transpose()?,
+            },
+            "color" => VarType::Color {
+                default: default.as_ref().map(Literal::as_color).transpose()?,
+            },
+            kw => {
+                if let Some((columns, rows)) = matrix_keyword(kw) {
+                    let n = (columns * rows) as usize;
+                    let (range, default) = typed_components(n, range, default, Literal::as_f32)?;
+                    VarType::Matrix {
+                        columns,
+                        rows,
+                        range,
+                        default,
+                    }
+                } else if let Some((prefix, size)) = vector_keyword(kw) {
+                    let n = size as usize;
+                    match prefix {
+                        "i" => {
+                            let (range, default) =
+                                typed_components(n, range, default, Literal::as_i32)?;
+                            VarType::SintVec {
+                                size,
+                                range,
+                                default,
+                            }
+                        }
+                        "u" => {
+                            let (range, default) =
+                                typed_components(n, range, default, Literal::as_u32)?;
+                            VarType::UintVec {
+                                size,
+                                range,
+                                default,
+                            }
+                        }
+                        _ => {
+                            let (range, default) =
+                                typed_components(n, range, default, Literal::as_f32)?;
+                            VarType::FloatVec {
+                                size,
+                                range,
+                                default,
+                            }
+                        }
+                    }
+                } else {
+                    return Err(format!("unknown variable type `{kw}`"));
+                }
+            }
+        };
+
+        Ok(Self { path, ty })
+    }
+}
+
+/// The untyped body of a variable pragma:
+/// `path.to.var [: range(<lit>..<lit>)] [= <lit>]`
+pub type RawVarHint<'a> = (
+    Vec<String>,
+    Option<(Literal<'a>, Literal<'a>)>,
+    Option<Literal<'a>>,
+);
+
+pub fn parse_var_hint(input: &str) -> IResult<&str, RawVarHint<'_>> {
+    tuple((
+        parse_global_access_name,
+        opt(preceded(
+            tuple((space0, char(':'), space0, tag("range"), space0)),
+            delimited(
+                char('('),
+                separated_pair(
+                    delimited(multispace0, parse_literal, multispace0),
+                    tag(".."),
+                    delimited(multispace0, parse_literal, multispace0),
+                ),
+                char(')'),
+            ),
+        )),
+        opt(preceded(tuple((space0, char('='), space0)), parse_literal)),
+    ))(input)
+}
+
+/// Numbers are recognized without consuming a trailing `.`
+/// so that `0..1` reads as a range rather than `0.` followed by `.1`.
+fn parse_number(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        opt(one_of("+-")),
+        digit1,
+        opt(pair(char('.'), digit1)),
+        opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+    )))(input)
+}
+
+pub fn parse_literal(input: &str) -> IResult<&str, Literal<'_>> {
+    alt((
+        value(Literal::Bool(true), tag("true")),
+        value(Literal::Bool(false), tag("false")),
+        map(parse_number, Literal::Number),
+        map(
+            delimited(
+                pair(char('['), multispace0),
+                separated_list1(
+                    delimited(multispace0, char(','), multispace0),
+                    parse_literal,
+                ),
+                pair(multispace0, char(']')),
+            ),
+            Literal::List,
+        ),
+    ))(input)
+}
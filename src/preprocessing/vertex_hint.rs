@@ -0,0 +1,95 @@
+use struct_patch::Patch;
+
+use super::HintOptions;
+
+#[derive(Debug, Clone, Copy, Patch)]
+#[patch(attribute(derive(Debug, Default, Clone)))]
+pub struct VertexHint {
+    /// index of the vertex buffer the attribute is read from
+    pub buffer: u32,
+    pub step_mode: wgpu::VertexStepMode,
+    /// the format of the data in the buffer, inferred from the
+    /// shader's input type if none is given
+    pub format: Option<wgpu::VertexFormat>,
+}
+
+impl Default for VertexHint {
+    fn default() -> Self {
+        Self {
+            buffer: 0,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            format: None,
+        }
+    }
+}
+
+impl HintOptions for VertexHintPatch {
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "buffer" => {
+                self.buffer = Some(value.parse().map_err(|_| {
+                    format!("`buffer` expects a vertex buffer index, found `{value}`")
+                })?)
+            }
+            "step" | "step_mode" => self.step_mode = Some(step_mode(value)?),
+            "format" => self.format = Some(Some(vertex_format(value)?)),
+            _ => return Err(format!("unknown vertex option `{key}`")),
+        }
+        Ok(())
+    }
+}
+
+fn step_mode(value: &str) -> Result<wgpu::VertexStepMode, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "vertex" => Ok(wgpu::VertexStepMode::Vertex),
+        "instance" => Ok(wgpu::VertexStepMode::Instance),
+        _ => Err(format!(
+            "invalid step mode `{value}`, expected `Vertex` or `Instance`"
+        )),
+    }
+}
+
+/// Formats are named as in wgpu, `Unorm8x4`, `Float32x3` and so on.
+fn vertex_format(value: &str) -> Result<wgpu::VertexFormat, String> {
+    use wgpu::VertexFormat as F;
+
+    let format = match value.to_ascii_lowercase().as_str() {
+        "uint8x2" => F::Uint8x2,
+        "uint8x4" => F::Uint8x4,
+        "sint8x2" => F::Sint8x2,
+        "sint8x4" => F::Sint8x4,
+        "unorm8x2" => F::Unorm8x2,
+        "unorm8x4" => F::Unorm8x4,
+        "snorm8x2" => F::Snorm8x2,
+        "snorm8x4" => F::Snorm8x4,
+        "uint16x2" => F::Uint16x2,
+        "uint16x4" => F::Uint16x4,
+        "sint16x2" => F::Sint16x2,
+        "sint16x4" => F::Sint16x4,
+        "unorm16x2" => F::Unorm16x2,
+        "unorm16x4" => F::Unorm16x4,
+        "snorm16x2" => F::Snorm16x2,
+        "snorm16x4" => F::Snorm16x4,
+        "float16x2" => F::Float16x2,
+        "float16x4" => F::Float16x4,
+        "float32" => F::Float32,
+        "float32x2" => F::Float32x2,
+        "float32x3" => F::Float32x3,
+        "float32x4" => F::Float32x4,
+        "uint32" => F::Uint32,
+        "uint32x2" => F::Uint32x2,
+        "uint32x3" => F::Uint32x3,
+        "uint32x4" => F::Uint32x4,
+        "sint32" => F::Sint32,
+        "sint32x2" => F::Sint32x2,
+        "sint32x3" => F::Sint32x3,
+        "sint32x4" => F::Sint32x4,
+        "float64" => F::Float64,
+        "float64x2" => F::Float64x2,
+        "float64x3" => F::Float64x3,
+        "float64x4" => F::Float64x4,
+        "unorm10_10_10_2" => F::Unorm10_10_10_2,
+        _ => return Err(format!("invalid vertex format `{value}`")),
+    };
+    Ok(format)
+}
@@ -0,0 +1,1226 @@
+use std::borrow::Cow;
+
+use wgpu::{
+    naga::{self, Binding, ScalarKind, ShaderStage, TypeInner},
+    BindGroupLayoutDescriptor, ErrorFilter, ShaderModuleDescriptor, VertexFormat, VertexStepMode,
+};
+
+use crate::{
+    bind_group::{
+        self,
+        requirements::{MissingPassSlot, PassSlot},
+        BindGroups, Labels, Reflected,
+    },
+    parameters,
+    preprocessing::{Directives, SourceMap},
+    traits::{self, Bindings, PushConstant},
+    DeviceUtils, Error, HostBuffer, HostTypeError, Parameter, ParsedModule, PreprocessOptions,
+    Samplers, ScalarType,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("No {0:?} entry point found in module")]
+    MissingStage(ShaderStage),
+    #[error("No {0:?} entry point named `{1}` in module")]
+    NoSuchEntryPoint(ShaderStage, String),
+    #[error("Vertex input `{0}` has a type which can't be read from a vertex buffer")]
+    UnsupportedVertexInput(String),
+    #[error("Vertex format {format:?} can't be read as the shader's `{input}` input")]
+    VertexFormatMismatch { input: String, format: VertexFormat },
+    #[error("Vertex inputs read from buffer {0} disagree on their step mode")]
+    StepModeConflict(u32),
+    #[error("No vertex input named `{0}` for vertex pragma")]
+    UnresolvedVertexHint(String),
+    #[error("Fragment output `{0}` has a type which can't be written to a color target")]
+    UnsupportedFragmentOutput(String),
+    #[error("Color target {location} has format {format:?}, which the fragment output can't be written to")]
+    IncompatibleTarget {
+        location: u32,
+        format: wgpu::TextureFormat,
+    },
+}
+
+/// The fixed function state of a render pipeline.
+#[derive(Debug, Clone, Default)]
+pub struct RenderState {
+    pub primitive: wgpu::PrimitiveState,
+    pub depth_stencil: Option<wgpu::DepthStencilState>,
+    pub multisample: wgpu::MultisampleState,
+    pub multiview: Option<std::num::NonZeroU32>,
+}
+
+pub struct UnboundRenderPipeline {
+    pipeline: wgpu::RenderPipeline,
+    reflection_ctx: RenderReflector,
+    samplers: Samplers,
+    vertex_entry: String,
+    fragment_entry: Option<String>,
+    color_formats: Vec<Option<wgpu::TextureFormat>>,
+    depth_format: Option<wgpu::TextureFormat>,
+}
+
+pub struct BoundRenderPipeline {
+    pipeline: wgpu::RenderPipeline,
+    bind_groups: Vec<wgpu::BindGroup>,
+    reflection_ctx: RenderReflector,
+    samplers: Samplers,
+    vertex_entry: String,
+    fragment_entry: Option<String>,
+    color_formats: Vec<Option<wgpu::TextureFormat>>,
+    depth_format: Option<wgpu::TextureFormat>,
+}
+
+impl UnboundRenderPipeline {
+    /// Create the pipeline, without a fragment entry point it only has
+    /// a vertex stage. `targets` are checked against the fragment outputs.
+    pub fn new(
+        device: &wgpu::Device,
+        vertex_entry: &str,
+        fragment_entry: Option<&str>,
+        targets: &[Option<wgpu::ColorTargetState>],
+        state: &RenderState,
+        options: wgpu::PipelineCompilationOptions,
+        context: RenderReflector,
+    ) -> Result<Self, Error> {
+        let pipeline = context.create_render_pipeline(
+            device,
+            vertex_entry,
+            fragment_entry,
+            targets,
+            state,
+            options,
+        )?;
+        let samplers = context.create_samplers(device);
+
+        Ok(Self {
+            pipeline,
+            reflection_ctx: context,
+            samplers,
+            vertex_entry: vertex_entry.to_owned(),
+            fragment_entry: fragment_entry.map(str::to_owned),
+            color_formats: targets
+                .iter()
+                .map(|target| target.as_ref().map(|target| target.format))
+                .collect(),
+            depth_format: state.depth_stencil.as_ref().map(|depth| depth.format),
+        })
+    }
+
+    /// The samplers created for the pipeline's sampler bindings.
+    pub fn samplers(&self) -> &Samplers {
+        &self.samplers
+    }
+
+    /// Create every bind group of the pipeline. Sampler bindings with a
+    /// sampler in [`Self::samplers`] are bound to it and never passed to
+    /// `bind_func`, so they always match their layout entry.
+    pub fn bind<'a, F>(
+        self,
+        device: &wgpu::Device,
+        bind_func: F,
+    ) -> Result<BoundRenderPipeline, Error>
+    where
+        F: FnMut(&bind_group::requirements::BindSlot<'a>),
+    {
+        let bind_groups =
+            self.reflection_ctx
+                .create_bind_groups(device, &self.samplers, bind_func)?;
+
+        let Self {
+            pipeline,
+            reflection_ctx,
+            samplers,
+            vertex_entry,
+            fragment_entry,
+            color_formats,
+            depth_format,
+        } = self;
+
+        Ok(BoundRenderPipeline {
+            pipeline,
+            bind_groups,
+            reflection_ctx,
+            samplers,
+            vertex_entry,
+            fragment_entry,
+            color_formats,
+            depth_format,
+        })
+    }
+
+    /// Like [`UnboundRenderPipeline::bind`], `bind_func` is given the shader
+    /// variable name of each slot so host code doesn't depend on binding
+    /// numbers. Slots without a name are never passed to `bind_func`.
+    pub fn bind_by_name<'a, F>(
+        self,
+        device: &wgpu::Device,
+        mut bind_func: F,
+    ) -> Result<BoundRenderPipeline, Error>
+    where
+        F: FnMut(&str, &bind_group::requirements::BindSlot<'a>),
+    {
+        self.bind(device, |slot| {
+            if let Some(name) = slot.name() {
+                bind_func(name, slot)
+            }
+        })
+    }
+
+    /// Like [`UnboundRenderPipeline::bind`], with every binding taken from
+    /// the fields of `bindings` instead of a closure.
+    pub fn bind_with<'a, B>(
+        self,
+        device: &wgpu::Device,
+        bindings: &B,
+    ) -> Result<BoundRenderPipeline, Error>
+    where
+        B: Bindings<'a> + ?Sized,
+    {
+        let fields = bindings.bindings();
+        traits::check_targets(&fields, &self.reflection_ctx.bind_groups)?;
+
+        let mut wrong_kinds = vec![];
+        let bound = self.bind(device, |slot| {
+            if let Err(problem) = traits::fill_slot(&fields, slot) {
+                wrong_kinds.push((slot.loc(), problem));
+            }
+        });
+
+        traits::with_wrong_kinds(bound, wrong_kinds)
+    }
+}
+
+impl BoundRenderPipeline {
+    pub fn unbind(self) -> UnboundRenderPipeline {
+        let Self {
+            pipeline,
+            reflection_ctx,
+            samplers,
+            vertex_entry,
+            fragment_entry,
+            color_formats,
+            depth_format,
+            ..
+        } = self;
+
+        UnboundRenderPipeline {
+            pipeline,
+            reflection_ctx,
+            samplers,
+            vertex_entry,
+            fragment_entry,
+            color_formats,
+            depth_format,
+        }
+    }
+
+    pub fn rebind_set<'a, F>(
+        &mut self,
+        device: &wgpu::Device,
+        set: u32,
+        bind_func: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&bind_group::requirements::BindSlot<'a>),
+    {
+        if set as usize >= self.bind_groups.len() {
+            return Err(Error::NoSuchSet(set));
+        }
+        self.bind_groups[set as usize] =
+            self.reflection_ctx
+                .create_bind_group_with(device, set, &self.samplers, bind_func)?;
+        Ok(())
+    }
+
+    pub fn rebind_all<'a, F>(&mut self, device: &wgpu::Device, bind_func: F) -> Result<(), Error>
+    where
+        F: FnMut(&bind_group::requirements::BindSlot<'a>),
+    {
+        self.bind_groups =
+            self.reflection_ctx
+                .create_bind_groups(device, &self.samplers, bind_func)?;
+        Ok(())
+    }
+
+    /// Like [`BoundRenderPipeline::rebind_all`], `bind_func` is given the
+    /// shader variable name of each slot.
+    pub fn rebind_all_by_name<'a, F>(
+        &mut self,
+        device: &wgpu::Device,
+        mut bind_func: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&str, &bind_group::requirements::BindSlot<'a>),
+    {
+        self.rebind_all(device, |slot| {
+            if let Some(name) = slot.name() {
+                bind_func(name, slot)
+            }
+        })
+    }
+
+    pub fn derail(self) -> (wgpu::RenderPipeline, Vec<wgpu::BindGroup>) {
+        (self.pipeline, self.bind_groups)
+    }
+
+    /// Begin a render pass with the pipeline and its bind groups set.
+    /// `pass_func` is asked for an attachment for every color target and
+    /// the depth target, a buffer for every vertex buffer slot, dynamic
+    /// offsets and push constants. It is also offered the index buffer,
+    /// which may be left empty for non-indexed draws.
+    pub fn create_pass<'a, 'b, F>(
+        &self,
+        enc: &'a mut wgpu::CommandEncoder,
+        mut pass_func: F,
+    ) -> Result<wgpu::RenderPass<'a>, Error>
+    where
+        F: FnMut(&PassSlot<'b>),
+    {
+        let mut missing = vec![];
+
+        let targets = match &self.fragment_entry {
+            Some(entry_point) => self.reflection_ctx.color_targets(entry_point)?,
+            None => &[],
+        };
+        let mut color_attachments = vec![];
+        for (location, format) in self.color_formats.iter().enumerate() {
+            let Some(format) = format else {
+                color_attachments.push(None);
+                continue;
+            };
+
+            let slot = PassSlot::color_attachment_for(location as u32, *format);
+            pass_func(&slot);
+            match slot.color_attachment() {
+                Some(attachment) => color_attachments.push(Some(attachment)),
+                None => missing.push(MissingPassSlot::ColorAttachment {
+                    location: location as u32,
+                    name: targets
+                        .get(location)
+                        .and_then(|target| target.as_ref()?.name.clone()),
+                    format: *format,
+                }),
+            }
+        }
+
+        let depth_stencil_attachment = self.depth_format.and_then(|format| {
+            let slot = PassSlot::depth_stencil_attachment_for(format);
+            pass_func(&slot);
+            let attachment = slot.depth_stencil_attachment();
+            if attachment.is_none() {
+                missing.push(MissingPassSlot::DepthStencilAttachment);
+            }
+            attachment
+        });
+
+        let mut vertex_buffers = vec![];
+        let buffers = self.reflection_ctx.vertex_buffers(&self.vertex_entry)?;
+        for (slot, buffer) in buffers.iter().enumerate() {
+            if buffer.attributes.is_empty() {
+                continue;
+            }
+
+            let vertex_slot = PassSlot::vertex_buffer_for(slot as u32);
+            pass_func(&vertex_slot);
+            match vertex_slot.vertex_buffer() {
+                Some(buffer) => vertex_buffers.push((slot as u32, buffer)),
+                None => missing.push(MissingPassSlot::VertexBuffer {
+                    slot: slot as u32,
+                    inputs: buffer
+                        .attributes
+                        .iter()
+                        .map(|attribute| {
+                            let name = self
+                                .reflection_ctx
+                                .vertex_input_name(&self.vertex_entry, attribute.shader_location);
+                            (name.map(str::to_owned), *attribute)
+                        })
+                        .collect(),
+                }),
+            }
+        }
+
+        let index_slot = PassSlot::index_buffer_slot();
+        pass_func(&index_slot);
+        let index_buffer = index_slot.index_buffer();
+
+        let mut pc_ranges = vec![];
+        for range in self.reflection_ctx.push_constant_range().unwrap_or(&[]) {
+            let pc_out = PassSlot::from(range);
+            pass_func(&pc_out);
+            match MissingPassSlot::push_constants(range, pc_out) {
+                Ok((offset, data)) => pc_ranges.push((range.stages, offset, data)),
+                Err(slot) => missing.push(slot),
+            }
+        }
+
+        let mut bg_and_offsets = vec![];
+        for (set, group) in self.bind_groups.iter().enumerate() {
+            let mut offsets = vec![];
+
+            for ent in self.reflection_ctx.iter_bind_group_entries(set as u32) {
+                if ent.ty.has_dynamic_offset() {
+                    let dyn_offset = PassSlot::offset_for(set as u32, ent.binding);
+                    pass_func(&dyn_offset);
+                    match dyn_offset.offset() {
+                        Some(offset) => offsets.push(offset),
+                        None => missing.push(MissingPassSlot::DynamicOffset {
+                            loc: (set as u32, ent.binding),
+                            name: self
+                                .reflection_ctx
+                                .binding_name(set as u32, ent.binding)
+                                .map(str::to_owned),
+                        }),
+                    }
+                }
+            }
+            bg_and_offsets.push((set, group, offsets));
+        }
+
+        if !missing.is_empty() {
+            return Err(Error::PassConstruction(missing));
+        }
+
+        let label = self.reflection_ctx.derived_label("pass");
+        let mut pass = enc.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: label.as_deref(),
+            color_attachments: &color_attachments,
+            depth_stencil_attachment,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        pass.set_pipeline(&self.pipeline);
+
+        for (stages, offset, data) in pc_ranges {
+            pass.set_push_constants(stages, offset, data);
+        }
+
+        for (set, group, offsets) in bg_and_offsets {
+            pass.set_bind_group(set as u32, group, &offsets);
+        }
+
+        for (slot, buffer) in vertex_buffers {
+            pass.set_vertex_buffer(slot, buffer);
+        }
+
+        if let Some((buffer, format)) = index_buffer {
+            pass.set_index_buffer(buffer, format);
+        }
+
+        Ok(pass)
+    }
+
+    /// [`Self::create_pass`] with the push constants taken from a rust
+    /// type, which is checked against the shader's push constant block
+    /// first. `pass_func` is only asked for the remaining slots.
+    pub fn create_pass_with<'a, 'b, P, F>(
+        &self,
+        enc: &'a mut wgpu::CommandEncoder,
+        constants: &'b P,
+        mut pass_func: F,
+    ) -> Result<wgpu::RenderPass<'a>, Error>
+    where
+        P: PushConstant + ?Sized,
+        F: FnMut(&PassSlot<'b>),
+    {
+        let reflected = match self.reflection_ctx.push_constant_buffer() {
+            Err(err @ HostTypeError::PushConstantMismatch) => return Err(err.into()),
+            reflected => reflected.ok(),
+        };
+        traits::check_push_constant(reflected.as_ref().map(HostBuffer::ty), constants)?;
+
+        self.create_pass(enc, |slot| match slot {
+            PassSlot::PushConstantRange { .. } => slot.fill_push_constants(constants.bytes()),
+            _ => pass_func(slot),
+        })
+    }
+}
+
+/// The layout of one vertex buffer, owning its attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexBuffer {
+    pub array_stride: wgpu::BufferAddress,
+    pub step_mode: VertexStepMode,
+    /// ordered by shader location
+    pub attributes: Vec<wgpu::VertexAttribute>,
+}
+
+impl VertexBuffer {
+    pub fn layout(&self) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.array_stride,
+            step_mode: self.step_mode,
+            attributes: &self.attributes,
+        }
+    }
+}
+
+/// A color target slot written by a fragment entry point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorTarget {
+    pub name: Option<String>,
+    pub location: u32,
+    pub scalar: ScalarType,
+    /// number of components the shader writes
+    pub components: u32,
+}
+
+impl ColorTarget {
+    /// Whether the shader's output can be written to a target of `format`,
+    /// the sample type must match and the shader must write at least as
+    /// many components as the format has.
+    pub fn accepts(&self, format: wgpu::TextureFormat) -> bool {
+        let scalar = match format.sample_type(None, None) {
+            Some(wgpu::TextureSampleType::Float { .. }) => ScalarType::F32,
+            Some(wgpu::TextureSampleType::Uint) => ScalarType::U32,
+            Some(wgpu::TextureSampleType::Sint) => ScalarType::I32,
+            _ => return false,
+        };
+
+        scalar == self.scalar && self.components >= format.components() as u32
+    }
+}
+
+#[derive(Debug, Clone)]
+struct VertexEntryPoint {
+    name: String,
+    buffers: Vec<VertexBuffer>,
+    /// locations and names of the inputs, vertex pragmas are resolved
+    /// against these
+    inputs: Vec<(u32, String)>,
+}
+
+#[derive(Debug, Clone)]
+struct FragmentEntryPoint {
+    name: String,
+    targets: Vec<Option<ColorTarget>>,
+}
+
+/// Reflection info on the vertex and fragment stages of a render pipeline,
+/// with the bind group layouts of both stages merged into one.
+#[derive(Debug, Clone)]
+pub struct RenderReflector {
+    bind_groups: BindGroups,
+    parameters: Vec<Parameter>,
+    labels: Labels,
+    vertex_entry_points: Vec<VertexEntryPoint>,
+    fragment_entry_points: Vec<FragmentEntryPoint>,
+    vertex_module: naga::Module,
+    /// `None` when both stages are in the vertex module
+    fragment_module: Option<naga::Module>,
+    /// locate validation errors in the original sources
+    vertex_source_map: SourceMap,
+    fragment_source_map: Option<SourceMap>,
+    /// downlevel flags of the adapter, see [`RenderReflector::set_downlevel_flags`]
+    downlevel: wgpu::DownlevelFlags,
+}
+
+impl RenderReflector {
+    /// Reflect a pipeline whose stages are given as separate sources,
+    /// such as one glsl source per stage. Without a fragment source
+    /// the pipeline only has a vertex stage.
+    pub fn new_render(
+        vertex: wgpu::ShaderSource,
+        fragment: Option<wgpu::ShaderSource>,
+    ) -> Result<Self, Error> {
+        let vertex_options = PreprocessOptions {
+            file_name: Some("<vertex>"),
+            ..Default::default()
+        };
+        let fragment_options = PreprocessOptions {
+            file_name: Some("<fragment>"),
+            ..Default::default()
+        };
+        Self::new_render_with(vertex, &vertex_options, fragment, &fragment_options)
+    }
+
+    /// Like [`RenderReflector::new_render`], with separate preprocessing
+    /// options for each stage's source.
+    pub fn new_render_with(
+        vertex: wgpu::ShaderSource,
+        vertex_options: &PreprocessOptions,
+        fragment: Option<wgpu::ShaderSource>,
+        fragment_options: &PreprocessOptions,
+    ) -> Result<Self, Error> {
+        let vertex = crate::parse_module(vertex, vertex_options)?;
+        let fragment = fragment
+            .map(|source| crate::parse_module(source, fragment_options))
+            .transpose()?;
+
+        Self::from_modules(vertex, fragment)
+    }
+
+    /// Reflect a pipeline whose stages are entry points of a single module,
+    /// as is usual for wgsl.
+    pub fn new_render_module(source: wgpu::ShaderSource) -> Result<Self, Error> {
+        Self::new_render_module_with(source, &PreprocessOptions::default())
+    }
+
+    /// Like [`RenderReflector::new_render_module`], `options` control how the
+    /// source is preprocessed and how it is named in diagnostics.
+    pub fn new_render_module_with(
+        source: wgpu::ShaderSource,
+        options: &PreprocessOptions,
+    ) -> Result<Self, Error> {
+        Self::from_modules(crate::parse_module(source, options)?, None)
+    }
+
+    fn from_modules(vertex: ParsedModule, fragment: Option<ParsedModule>) -> Result<Self, Error> {
+        let (vertex_directives, vertex_module, vertex_source_map) = vertex;
+
+        let vertex_entry_points = reflect_vertex_entry_points(&vertex_module, &vertex_directives)?;
+        if vertex_entry_points.is_empty() {
+            return Err(RenderError::MissingStage(ShaderStage::Vertex).into());
+        }
+        resolve_vertex_hints(&vertex_directives, &vertex_entry_points)?;
+
+        let mut bind_groups = BindGroups::new(&vertex_module, &vertex_directives)?;
+        let mut parameters = parameters::reflect_parameters(&vertex_module, &vertex_directives)?;
+        let mut label = vertex_directives.label().map(str::to_owned);
+
+        let fragment_entry_points = match &fragment {
+            Some((directives, module, _)) => {
+                let entry_points = reflect_fragment_entry_points(module)?;
+                if entry_points.is_empty() {
+                    return Err(RenderError::MissingStage(ShaderStage::Fragment).into());
+                }
+                resolve_vertex_hints(directives, &[])?;
+
+                bind_groups.merge(BindGroups::new(module, directives)?)?;
+                // both stages may declare the same parameter in a shared block
+                for param in parameters::reflect_parameters(module, directives)? {
+                    if !parameters.iter().any(|p| p.path == param.path) {
+                        parameters.push(param);
+                    }
+                }
+                label = label.or_else(|| directives.label().map(str::to_owned));
+
+                entry_points
+            }
+            None => reflect_fragment_entry_points(&vertex_module)?,
+        };
+
+        let labels = Labels::new(label, &bind_groups);
+
+        Ok(Self {
+            bind_groups,
+            parameters,
+            labels,
+            vertex_entry_points,
+            fragment_entry_points,
+            vertex_module,
+            vertex_source_map,
+            fragment_source_map: fragment.as_ref().map(|(_, _, map)| map.clone()),
+            fragment_module: fragment.map(|(_, module, _)| module),
+            downlevel: wgpu::DownlevelFlags::empty(),
+        })
+    }
+
+    /// The downlevel flags of the adapter pipelines are created on, from
+    /// `adapter.get_downlevel_capabilities()`. The modules are validated
+    /// against them before they reach wgpu, without them cube map arrays
+    /// and per sample shading are rejected.
+    pub fn set_downlevel_flags(&mut self, flags: wgpu::DownlevelFlags) {
+        self.downlevel = flags;
+    }
+
+    /// The base label given with `#pragma label "..."`, taken from
+    /// the vertex source first.
+    pub fn label(&self) -> Option<&str> {
+        self.labels.base()
+    }
+
+    /// `"{label}/{suffix}"`, the label used for the objects kinnara creates.
+    pub fn derived_label(&self, suffix: &str) -> Option<String> {
+        self.labels.derived(suffix)
+    }
+
+    /// A label for resources bound at `(set, binding)`, derived from the
+    /// base label and the binding's `label` option or variable name.
+    pub fn binding_label(&self, set: u32, binding: u32) -> Option<String> {
+        self.reflected().binding_label(set, binding)
+    }
+
+    /// The shader variable bound at `(set, binding)`, for anonymous
+    /// glsl blocks this is the block name.
+    pub fn binding_name(&self, set: u32, binding: u32) -> Option<&str> {
+        self.reflected().binding_name(set, binding)
+    }
+
+    /// The `(set, binding)` of the shader variable called `name`.
+    pub fn find_binding(&self, name: &str) -> Option<(u32, u32)> {
+        self.reflected().find_binding(name)
+    }
+
+    fn set_label(&self, set: u32) -> Option<&str> {
+        self.labels.set(set)
+    }
+
+    fn reflected(&self) -> Reflected<'_> {
+        Reflected {
+            bind_groups: &self.bind_groups,
+            labels: &self.labels,
+        }
+    }
+
+    /// Every uniform or push constant member annotated with a typed
+    /// variable pragma in either stage.
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    pub fn vertex_entry_points(&self) -> impl Iterator<Item = &str> {
+        self.vertex_entry_points.iter().map(|ep| ep.name.as_str())
+    }
+
+    pub fn fragment_entry_points(&self) -> impl Iterator<Item = &str> {
+        self.fragment_entry_points.iter().map(|ep| ep.name.as_str())
+    }
+
+    fn vertex_entry_point(&self, entry_point: &str) -> Result<&VertexEntryPoint, RenderError> {
+        self.vertex_entry_points
+            .iter()
+            .find(|ep| ep.name == entry_point)
+            .ok_or_else(|| RenderError::NoSuchEntryPoint(ShaderStage::Vertex, entry_point.into()))
+    }
+
+    fn fragment_entry_point(&self, entry_point: &str) -> Result<&FragmentEntryPoint, RenderError> {
+        self.fragment_entry_points
+            .iter()
+            .find(|ep| ep.name == entry_point)
+            .ok_or_else(|| RenderError::NoSuchEntryPoint(ShaderStage::Fragment, entry_point.into()))
+    }
+
+    fn vertex_input_name(&self, entry_point: &str, location: u32) -> Option<&str> {
+        let ep = self.vertex_entry_point(entry_point).ok()?;
+        ep.inputs
+            .iter()
+            .find(|(input, _)| *input == location)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// The vertex buffers read by a vertex entry point, indexed by buffer slot.
+    /// Attributes are packed in location order unless a vertex pragma
+    /// moves them to another buffer or gives them a different format.
+    pub fn vertex_buffers(&self, entry_point: &str) -> Result<&[VertexBuffer], Error> {
+        Ok(&self.vertex_entry_point(entry_point)?.buffers)
+    }
+
+    pub fn vertex_buffer_layouts(
+        &self,
+        entry_point: &str,
+    ) -> Result<Vec<wgpu::VertexBufferLayout<'_>>, Error> {
+        Ok(self
+            .vertex_buffers(entry_point)?
+            .iter()
+            .map(VertexBuffer::layout)
+            .collect())
+    }
+
+    /// The color targets written by a fragment entry point, indexed by
+    /// location, `None` for locations the shader doesn't write.
+    pub fn color_targets(&self, entry_point: &str) -> Result<&[Option<ColorTarget>], Error> {
+        Ok(&self.fragment_entry_point(entry_point)?.targets)
+    }
+
+    pub fn push_constant_range(&self) -> Option<&[wgpu::PushConstantRange]> {
+        self.bind_groups.push_constant_range.as_deref()
+    }
+
+    /// A zeroed [`HostBuffer`] laid out like the shader's push constants,
+    /// both stages share one push constant block so when each module
+    /// declares one they must match.
+    pub fn push_constant_buffer(&self) -> Result<HostBuffer, HostTypeError> {
+        let mut blocks = std::iter::once(&self.vertex_module)
+            .chain(&self.fragment_module)
+            .filter_map(|module| {
+                module
+                    .global_variables
+                    .iter()
+                    .find(|(_, global)| global.space == naga::AddressSpace::PushConstant)
+                    .map(|(_, global)| HostBuffer::for_global(module, global))
+            });
+
+        let buffer = blocks
+            .next()
+            .unwrap_or(Err(HostTypeError::NoPushConstants))?;
+        for other in blocks {
+            if other?.ty() != buffer.ty() {
+                return Err(HostTypeError::PushConstantMismatch);
+            }
+        }
+        Ok(buffer)
+    }
+
+    pub fn bind_group_count(&self) -> usize {
+        self.bind_groups.bind_group_count()
+    }
+
+    /// Create a bind group for `set`, `func` fills every slot of the set as
+    /// in [`ComputeReflector::create_bind_group`](crate::ComputeReflector::create_bind_group).
+    pub fn create_bind_group<'a, F>(
+        &self,
+        device: &wgpu::Device,
+        set: u32,
+        func: F,
+    ) -> Result<wgpu::BindGroup, Error>
+    where
+        F: FnMut(&bind_group::requirements::BindSlot<'a>),
+    {
+        self.create_bind_group_with(device, set, &Samplers::default(), func)
+    }
+
+    /// Like [`RenderReflector::create_bind_group`], sampler bindings with
+    /// a sampler in `samplers` are bound to it and never passed to `func`.
+    pub fn create_bind_group_with<'a, F>(
+        &self,
+        device: &wgpu::Device,
+        set: u32,
+        samplers: &Samplers,
+        func: F,
+    ) -> Result<wgpu::BindGroup, Error>
+    where
+        F: FnMut(&bind_group::requirements::BindSlot<'a>),
+    {
+        self.reflected()
+            .create_bind_group_with(device, set, samplers, func)
+    }
+
+    /// The descriptor of the sampler created for a sampler binding,
+    /// built from its sampler hint. `None` for anything but single samplers.
+    pub fn sampler_descriptor(
+        &self,
+        set: u32,
+        binding: u32,
+    ) -> Option<wgpu::SamplerDescriptor<'static>> {
+        self.bind_groups.sampler_descriptor(set, binding)
+    }
+
+    /// Create a sampler for every single sampler binding from its sampler hint.
+    pub fn create_samplers(&self, device: &wgpu::Device) -> Samplers {
+        self.reflected().create_samplers(device)
+    }
+
+    /// Create a bind group for every set of the pipeline layout.
+    fn create_bind_groups<'a, F>(
+        &self,
+        device: &wgpu::Device,
+        samplers: &Samplers,
+        mut bind_func: F,
+    ) -> Result<Vec<wgpu::BindGroup>, Error>
+    where
+        F: FnMut(&bind_group::requirements::BindSlot<'a>),
+    {
+        let mut bind_groups = vec![];
+        let mut issues = vec![];
+
+        for set in 0..=self.bind_group_count() as u32 {
+            match self.create_bind_group_with(device, set, samplers, &mut bind_func) {
+                Ok(group) => bind_groups.push(group),
+                Err(Error::InvalidBindings(ent)) => issues.extend(ent),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !issues.is_empty() {
+            return Err(Error::InvalidBindings(issues));
+        }
+
+        Ok(bind_groups)
+    }
+
+    /// Create the render pipeline of a vertex and optional fragment entry point,
+    /// with vertex buffer layouts reflected from the vertex inputs. `targets`
+    /// must be writable by the fragment outputs at the same locations.
+    pub fn create_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        vertex_entry: &str,
+        fragment_entry: Option<&str>,
+        targets: &[Option<wgpu::ColorTargetState>],
+        state: &RenderState,
+        options: wgpu::PipelineCompilationOptions,
+    ) -> Result<wgpu::RenderPipeline, Error> {
+        let buffers = self.vertex_buffer_layouts(vertex_entry)?;
+
+        if let Some(fragment_entry) = fragment_entry {
+            let outputs = self.color_targets(fragment_entry)?;
+            for (location, target) in targets.iter().enumerate() {
+                let (Some(target), Some(Some(output))) = (target, outputs.get(location)) else {
+                    continue;
+                };
+                if !output.accepts(target.format) {
+                    return Err(RenderError::IncompatibleTarget {
+                        location: location as u32,
+                        format: target.format,
+                    }
+                    .into());
+                }
+            }
+        }
+
+        let pipeline_label = self.derived_label("pipeline");
+        let (vertex_label, fragment_label) = match self.fragment_module {
+            Some(_) => (
+                self.derived_label("vertex_module"),
+                self.derived_label("fragment_module"),
+            ),
+            None => (self.derived_label("module"), None),
+        };
+
+        crate::validate_module(
+            device,
+            self.downlevel,
+            &self.vertex_module,
+            &self.vertex_source_map,
+        )?;
+        if let (Some(module), Some(source_map)) = (&self.fragment_module, &self.fragment_source_map)
+        {
+            crate::validate_module(device, self.downlevel, module, source_map)?;
+        }
+
+        device
+            .wgpu_try(ErrorFilter::Validation, |dev| {
+                let layout = self.create_pipeline_layout(dev);
+                let vertex_module = dev.create_shader_module(ShaderModuleDescriptor {
+                    label: vertex_label.as_deref(),
+                    source: wgpu::ShaderSource::Naga(Cow::Owned(self.vertex_module.clone())),
+                });
+                let fragment_module = self.fragment_module.as_ref().map(|module| {
+                    dev.create_shader_module(ShaderModuleDescriptor {
+                        label: fragment_label.as_deref(),
+                        source: wgpu::ShaderSource::Naga(Cow::Owned(module.clone())),
+                    })
+                });
+
+                let fragment = fragment_entry.map(|entry_point| wgpu::FragmentState {
+                    module: fragment_module.as_ref().unwrap_or(&vertex_module),
+                    entry_point,
+                    compilation_options: options.clone(),
+                    targets,
+                });
+
+                dev.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: pipeline_label.as_deref(),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &vertex_module,
+                        entry_point: vertex_entry,
+                        compilation_options: options.clone(),
+                        buffers: &buffers,
+                    },
+                    primitive: state.primitive,
+                    depth_stencil: state.depth_stencil.clone(),
+                    multisample: state.multisample,
+                    fragment,
+                    multiview: state.multiview,
+                    cache: None,
+                })
+            })
+            .map_err(Error::from)
+    }
+
+    pub fn create_pipeline_layout(&self, device: &wgpu::Device) -> wgpu::PipelineLayout {
+        let push_constant_range = self.push_constant_range().unwrap_or(&[]);
+
+        let bind_group_layouts: Vec<_> = (0..=self.bind_group_count())
+            .map(|set| self.create_bind_group_layout(device, set as u32))
+            .collect();
+        let bind_group_layouts: Vec<_> = bind_group_layouts.iter().collect();
+
+        let label = self.derived_label("pipeline_layout");
+        let desc = wgpu::PipelineLayoutDescriptor {
+            label: label.as_deref(),
+            bind_group_layouts: bind_group_layouts.as_slice(),
+            push_constant_ranges: push_constant_range,
+        };
+
+        device.create_pipeline_layout(&desc)
+    }
+
+    pub fn create_bind_group_layout(
+        &self,
+        device: &wgpu::Device,
+        set: u32,
+    ) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&self.get_bind_group_layout_descriptor(set))
+    }
+
+    pub fn get_bind_group_layout_descriptor(&self, set: u32) -> BindGroupLayoutDescriptor<'_> {
+        BindGroupLayoutDescriptor {
+            label: self.set_label(set),
+            entries: self.bind_groups.get_bind_group_layout_entries(set),
+        }
+    }
+
+    pub fn iter_bind_group_entries(
+        &self,
+        set: u32,
+    ) -> impl Iterator<Item = &wgpu::BindGroupLayoutEntry> {
+        self.bind_groups.iter_bind_group_entries(set)
+    }
+
+    pub fn get_bind_group_layout_entry(
+        &self,
+        set: u32,
+        binding: u32,
+    ) -> Option<wgpu::BindGroupLayoutEntry> {
+        self.bind_groups.get_bind_group_layout_entry(set, binding)
+    }
+}
+
+/// A shader input or output bound to a location, struct arguments
+/// and results are flattened into their members.
+struct Located {
+    name: Option<String>,
+    location: u32,
+    ty: naga::Handle<naga::Type>,
+}
+
+impl Located {
+    fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("location {}", self.location))
+    }
+}
+
+fn located(
+    module: &naga::Module,
+    name: &Option<String>,
+    binding: &Option<Binding>,
+    ty: naga::Handle<naga::Type>,
+    out: &mut Vec<Located>,
+) {
+    match binding {
+        // the second blend source shares a location with the first
+        Some(Binding::Location {
+            location,
+            second_blend_source: false,
+            ..
+        }) => out.push(Located {
+            name: name.clone(),
+            location: *location,
+            ty,
+        }),
+        Some(_) => {}
+        None => {
+            if let TypeInner::Struct { members, .. } = &module.types[ty].inner {
+                for member in members {
+                    located(module, &member.name, &member.binding, member.ty, out);
+                }
+            }
+        }
+    }
+}
+
+fn reflect_vertex_entry_points(
+    module: &naga::Module,
+    directives: &Directives,
+) -> Result<Vec<VertexEntryPoint>, RenderError> {
+    module
+        .entry_points
+        .iter()
+        .filter(|ep| ep.stage == ShaderStage::Vertex)
+        .map(|ep| {
+            let mut inputs = vec![];
+            for arg in &ep.function.arguments {
+                located(module, &arg.name, &arg.binding, arg.ty, &mut inputs);
+            }
+            inputs.sort_by_key(|input| input.location);
+
+            Ok(VertexEntryPoint {
+                name: ep.name.clone(),
+                buffers: vertex_buffers(module, directives, &inputs)?,
+                inputs: inputs
+                    .into_iter()
+                    .filter_map(|input| Some((input.location, input.name?)))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Lay out the inputs in their buffers in location order, each attribute
+/// aligned to its size up to 4 bytes as wgpu requires.
+fn vertex_buffers(
+    module: &naga::Module,
+    directives: &Directives,
+    inputs: &[Located],
+) -> Result<Vec<VertexBuffer>, RenderError> {
+    let mut buffers: Vec<(Option<VertexStepMode>, VertexBuffer)> = vec![];
+
+    for input in inputs {
+        let hint = directives.get_vertex_hint(input.name.as_deref());
+        let inferred = vertex_format(&module.types[input.ty].inner)
+            .ok_or_else(|| RenderError::UnsupportedVertexInput(input.display_name()))?;
+
+        let format = match hint.format {
+            Some(format) if format_kind(format) != format_kind(inferred) => {
+                return Err(RenderError::VertexFormatMismatch {
+                    input: input.display_name(),
+                    format,
+                })
+            }
+            Some(format) => format,
+            None => inferred,
+        };
+
+        let idx = hint.buffer as usize;
+        if buffers.len() <= idx {
+            buffers.resize_with(idx + 1, || {
+                let buffer = VertexBuffer {
+                    array_stride: 0,
+                    step_mode: VertexStepMode::Vertex,
+                    attributes: vec![],
+                };
+                (None, buffer)
+            });
+        }
+
+        let (step_mode, buffer) = &mut buffers[idx];
+        if step_mode.is_some_and(|mode| mode != hint.step_mode) {
+            return Err(RenderError::StepModeConflict(hint.buffer));
+        }
+        *step_mode = Some(hint.step_mode);
+        buffer.step_mode = hint.step_mode;
+
+        let offset = buffer.array_stride.next_multiple_of(format.size().min(4));
+        buffer.attributes.push(wgpu::VertexAttribute {
+            format,
+            offset,
+            shader_location: input.location,
+        });
+        buffer.array_stride = offset + format.size();
+    }
+
+    Ok(buffers
+        .into_iter()
+        .map(|(_, buffer)| VertexBuffer {
+            array_stride: buffer
+                .array_stride
+                .next_multiple_of(wgpu::VERTEX_STRIDE_ALIGNMENT),
+            ..buffer
+        })
+        .collect())
+}
+
+/// Every vertex pragma must name an input of one of the entry points.
+fn resolve_vertex_hints(
+    directives: &Directives,
+    entry_points: &[VertexEntryPoint],
+) -> Result<(), RenderError> {
+    match directives.vertex_hint_names().find(|name| {
+        !entry_points
+            .iter()
+            .any(|ep| ep.inputs.iter().any(|(_, input)| input == name))
+    }) {
+        Some(name) => Err(RenderError::UnresolvedVertexHint(name.to_owned())),
+        None => Ok(()),
+    }
+}
+
+fn vertex_format(inner: &TypeInner) -> Option<VertexFormat> {
+    use VertexFormat as F;
+
+    let (scalar, components) = match *inner {
+        TypeInner::Scalar(scalar) => (scalar, 1),
+        TypeInner::Vector { size, scalar } => (scalar, size as u8),
+        _ => return None,
+    };
+
+    let format = match (scalar.kind, scalar.width, components) {
+        (ScalarKind::Float, 4, 1) => F::Float32,
+        (ScalarKind::Float, 4, 2) => F::Float32x2,
+        (ScalarKind::Float, 4, 3) => F::Float32x3,
+        (ScalarKind::Float, 4, 4) => F::Float32x4,
+        (ScalarKind::Float, 8, 1) => F::Float64,
+        (ScalarKind::Float, 8, 2) => F::Float64x2,
+        (ScalarKind::Float, 8, 3) => F::Float64x3,
+        (ScalarKind::Float, 8, 4) => F::Float64x4,
+        (ScalarKind::Uint, 4, 1) => F::Uint32,
+        (ScalarKind::Uint, 4, 2) => F::Uint32x2,
+        (ScalarKind::Uint, 4, 3) => F::Uint32x3,
+        (ScalarKind::Uint, 4, 4) => F::Uint32x4,
+        (ScalarKind::Sint, 4, 1) => F::Sint32,
+        (ScalarKind::Sint, 4, 2) => F::Sint32x2,
+        (ScalarKind::Sint, 4, 3) => F::Sint32x3,
+        (ScalarKind::Sint, 4, 4) => F::Sint32x4,
+        _ => return None,
+    };
+    Some(format)
+}
+
+/// The kind of value the shader reads a vertex format as, normalized
+/// formats are read as floats.
+fn format_kind(format: VertexFormat) -> ScalarKind {
+    use VertexFormat as F;
+
+    match format {
+        F::Uint8x2
+        | F::Uint8x4
+        | F::Uint16x2
+        | F::Uint16x4
+        | F::Uint32
+        | F::Uint32x2
+        | F::Uint32x3
+        | F::Uint32x4 => ScalarKind::Uint,
+        F::Sint8x2
+        | F::Sint8x4
+        | F::Sint16x2
+        | F::Sint16x4
+        | F::Sint32
+        | F::Sint32x2
+        | F::Sint32x3
+        | F::Sint32x4 => ScalarKind::Sint,
+        _ => ScalarKind::Float,
+    }
+}
+
+fn reflect_fragment_entry_points(
+    module: &naga::Module,
+) -> Result<Vec<FragmentEntryPoint>, RenderError> {
+    module
+        .entry_points
+        .iter()
+        .filter(|ep| ep.stage == ShaderStage::Fragment)
+        .map(|ep| {
+            let mut outputs = vec![];
+            if let Some(result) = &ep.function.result {
+                located(module, &None, &result.binding, result.ty, &mut outputs);
+            }
+
+            let mut targets = vec![];
+            for output in outputs {
+                let unsupported = || RenderError::UnsupportedFragmentOutput(output.display_name());
+                let (scalar, components) = match module.types[output.ty].inner {
+                    TypeInner::Scalar(scalar) => (scalar, 1),
+                    TypeInner::Vector { size, scalar } => (scalar, size as u32),
+                    _ => return Err(unsupported()),
+                };
+                let scalar = match ScalarType::from_naga(scalar) {
+                    Ok(scalar @ (ScalarType::F32 | ScalarType::U32 | ScalarType::I32)) => scalar,
+                    _ => return Err(unsupported()),
+                };
+
+                let idx = output.location as usize;
+                if targets.len() <= idx {
+                    targets.resize(idx + 1, None);
+                }
+                targets[idx] = Some(ColorTarget {
+                    name: output.name,
+                    location: output.location,
+                    scalar,
+                    components,
+                });
+            }
+
+            Ok(FragmentEntryPoint {
+                name: ep.name.clone(),
+                targets,
+            })
+        })
+        .collect()
+}
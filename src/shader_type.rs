@@ -0,0 +1,165 @@
+//! Checking `encase::ShaderType` structs against the reflected layout of a
+//! binding or the push constants, and writing them through the checked
+//! handle. Nested structs are checked by size only, encase's metadata
+//! doesn't reach into members.
+
+use std::marker::PhantomData;
+
+use encase::{
+    internal::WriteInto,
+    private::{ArrayMetadata, MatrixMetadata, StructMetadata},
+    ShaderType, StorageBuffer,
+};
+use thiserror::Error;
+use wgpu::naga::{self, TypeInner};
+
+use crate::LayoutLoc;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ShaderTypeError {
+    #[error("{loc} is {expected} bytes, the rust type is {found}")]
+    SizeMismatch {
+        loc: LayoutLoc,
+        expected: u64,
+        found: u64,
+    },
+    #[error("{loc} is aligned to {expected} bytes, the rust type to {found}")]
+    AlignmentMismatch {
+        loc: LayoutLoc,
+        expected: u64,
+        found: u64,
+    },
+    #[error("{loc} has {expected} members, the rust type has {found}")]
+    MemberCountMismatch {
+        loc: LayoutLoc,
+        expected: usize,
+        found: usize,
+    },
+    #[error("{loc} member `{member}` is at offset {expected}, the rust type has it at {found}")]
+    OffsetMismatch {
+        loc: LayoutLoc,
+        member: String,
+        expected: u64,
+        found: u64,
+    },
+}
+
+/// Member offsets from the metadata encase keeps for a type,
+/// `None` for anything but structs.
+pub trait MemberOffsets {
+    fn member_offsets(&self) -> Option<&[u64]>;
+}
+
+impl<const N: usize> MemberOffsets for StructMetadata<N> {
+    fn member_offsets(&self) -> Option<&[u64]> {
+        Some(&self.offsets)
+    }
+}
+
+macro_rules! no_member_offsets {
+    ($($ty:ty),*) => {
+        $(impl MemberOffsets for $ty {
+            fn member_offsets(&self) -> Option<&[u64]> {
+                None
+            }
+        })*
+    };
+}
+
+no_member_offsets!((), ArrayMetadata, MatrixMetadata);
+
+/// A binding or the push constants checked to be laid out like `T`, so
+/// values of `T` can be written straight into their buffers.
+#[derive(Debug, Clone, Copy)]
+pub struct TypedBinding<T> {
+    loc: LayoutLoc,
+    _ty: PhantomData<fn(&T)>,
+}
+
+impl<T> TypedBinding<T>
+where
+    T: ShaderType + WriteInto,
+{
+    pub fn loc(&self) -> LayoutLoc {
+        self.loc
+    }
+
+    /// The bytes of `value` laid out like the shader expects.
+    pub fn to_bytes(&self, value: &T) -> Vec<u8> {
+        let mut buffer = StorageBuffer::new(vec![]);
+        buffer.write(value).expect("a Vec grows to fit any value");
+        buffer.into_inner()
+    }
+
+    /// Write `value` to the start of `buffer`.
+    pub fn write(&self, queue: &wgpu::Queue, buffer: &wgpu::Buffer, value: &T) {
+        queue.write_buffer(buffer, 0, &self.to_bytes(value));
+    }
+}
+
+/// Check `T` against the naga type `ty`, laid out for `loc`.
+pub(crate) fn check<T>(
+    module: &naga::Module,
+    ty: naga::Handle<naga::Type>,
+    loc: LayoutLoc,
+) -> Result<TypedBinding<T>, ShaderTypeError>
+where
+    T: ShaderType,
+    T::ExtraMetadata: MemberOffsets,
+{
+    let mut layouter = naga::proc::Layouter::default();
+    // the module already passed naga's validation, so layouting can't fail
+    let _ = layouter.update(module.to_ctx());
+
+    let inner = &module.types[ty].inner;
+    let expected = inner.size(module.to_ctx()) as u64;
+    let found = T::min_size().get();
+    if expected != found {
+        return Err(ShaderTypeError::SizeMismatch {
+            loc,
+            expected,
+            found,
+        });
+    }
+
+    // rounding 1 up gives the alignment itself
+    let expected = u64::from(layouter[ty].alignment.round_up(1));
+    let found = T::METADATA.alignment().get();
+    if expected != found {
+        return Err(ShaderTypeError::AlignmentMismatch {
+            loc,
+            expected,
+            found,
+        });
+    }
+
+    let metadata = T::METADATA;
+    if let (TypeInner::Struct { members, .. }, Some(offsets)) =
+        (inner, metadata.extra.member_offsets())
+    {
+        if members.len() != offsets.len() {
+            return Err(ShaderTypeError::MemberCountMismatch {
+                loc,
+                expected: members.len(),
+                found: offsets.len(),
+            });
+        }
+
+        for (member, &found) in members.iter().zip(offsets) {
+            let expected = u64::from(member.offset);
+            if expected != found {
+                return Err(ShaderTypeError::OffsetMismatch {
+                    loc,
+                    member: member.name.clone().unwrap_or_default(),
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+
+    Ok(TypedBinding {
+        loc,
+        _ty: PhantomData,
+    })
+}
@@ -0,0 +1,314 @@
+//! The resource interface host code expects from a shader, described by a
+//! [`BindingSignature`](crate::BindingSignature) and diffed against the
+//! reflected module, so interface drift shows up without a GPU.
+
+use std::fmt;
+
+use crate::HostType;
+
+/// What a binding holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    UniformBuffer,
+    StorageBuffer { read_only: bool },
+    Texture,
+    StorageTexture,
+    Sampler,
+    AccelerationStructure,
+}
+
+impl From<&wgpu::BindingType> for ResourceKind {
+    fn from(value: &wgpu::BindingType) -> Self {
+        match value {
+            wgpu::BindingType::Buffer { ty, .. } => match ty {
+                wgpu::BufferBindingType::Uniform => Self::UniformBuffer,
+                wgpu::BufferBindingType::Storage { read_only } => Self::StorageBuffer {
+                    read_only: *read_only,
+                },
+            },
+            wgpu::BindingType::Sampler(_) => Self::Sampler,
+            wgpu::BindingType::Texture { .. } => Self::Texture,
+            wgpu::BindingType::StorageTexture { .. } => Self::StorageTexture,
+            wgpu::BindingType::AccelerationStructure => Self::AccelerationStructure,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub name: &'static str,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// The host side layout of a buffer or push constant block. Members of the
+/// shader's struct are matched to `fields` by name, fields the shader
+/// doesn't name, like explicit padding, are allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout {
+    /// runtime sized arrays count as empty
+    pub size: u32,
+    pub fields: Vec<FieldLayout>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub kind: ResourceKind,
+    /// the element count of binding arrays
+    pub count: Option<u32>,
+    /// checked for buffers only
+    pub layout: Option<StructLayout>,
+}
+
+impl SignatureBinding {
+    pub fn new(set: u32, binding: u32, kind: ResourceKind) -> Self {
+        Self {
+            set,
+            binding,
+            kind,
+            count: None,
+            layout: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Signature {
+    pub bindings: Vec<SignatureBinding>,
+    pub push_constants: Option<StructLayout>,
+}
+
+/// Where a layout mismatch is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutLoc {
+    Binding(u32, u32),
+    PushConstants,
+}
+
+impl fmt::Display for LayoutLoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Binding(set, binding) => write!(f, "set {set} binding {binding}"),
+            Self::PushConstants => write!(f, "push constants"),
+        }
+    }
+}
+
+/// One difference between a [`Signature`] and the shader, `expected` is
+/// always the shader's side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureMismatch {
+    /// the host expects a binding the shader doesn't have
+    MissingBinding(u32, u32),
+    /// the shader has a binding the host doesn't expect
+    UnexpectedBinding(u32, u32),
+    KindMismatch {
+        loc: (u32, u32),
+        expected: ResourceKind,
+        found: ResourceKind,
+    },
+    CountMismatch {
+        loc: (u32, u32),
+        expected: Option<u32>,
+        found: Option<u32>,
+    },
+    MissingPushConstants,
+    UnexpectedPushConstants,
+    SizeMismatch {
+        loc: LayoutLoc,
+        expected: u32,
+        found: u32,
+    },
+    MissingField {
+        loc: LayoutLoc,
+        name: String,
+    },
+    FieldMismatch {
+        loc: LayoutLoc,
+        name: String,
+        /// offset and size
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+}
+
+impl fmt::Display for SignatureMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingBinding(set, binding) => {
+                write!(f, "set {set} binding {binding} is missing from the shader")
+            }
+            Self::UnexpectedBinding(set, binding) => {
+                write!(f, "set {set} binding {binding} is not in the signature")
+            }
+            Self::KindMismatch {
+                loc: (set, binding),
+                expected,
+                found,
+            } => write!(
+                f,
+                "set {set} binding {binding} is {expected:?}, the signature has {found:?}"
+            ),
+            Self::CountMismatch {
+                loc: (set, binding),
+                expected,
+                found,
+            } => write!(
+                f,
+                "set {set} binding {binding} has count {expected:?}, the signature has {found:?}"
+            ),
+            Self::MissingPushConstants => write!(f, "the shader has no push constants"),
+            Self::UnexpectedPushConstants => {
+                write!(f, "the shader's push constants are not in the signature")
+            }
+            Self::SizeMismatch {
+                loc,
+                expected,
+                found,
+            } => write!(f, "{loc} is {expected} bytes, the signature has {found}"),
+            Self::MissingField { loc, name } => {
+                write!(f, "{loc} member `{name}` is not in the signature")
+            }
+            Self::FieldMismatch {
+                loc,
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{loc} member `{name}` is at {}..{}, the signature has {}..{}",
+                expected.0,
+                expected.0 + expected.1,
+                found.0,
+                found.0 + found.1
+            ),
+        }
+    }
+}
+
+/// Render a list of mismatches one per line.
+pub(crate) fn render_all(mismatches: &[SignatureMismatch]) -> String {
+    mismatches
+        .iter()
+        .map(|mismatch| format!("  {mismatch}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Diff a signature against the shader's bindings, `reflected_layout` gives
+/// the layout of the buffer at a location, if it is one.
+pub(crate) fn diff<'a>(
+    signature: &Signature,
+    entries: impl Iterator<Item = (u32, &'a wgpu::BindGroupLayoutEntry)>,
+    reflected_layout: impl Fn(u32, u32) -> Option<HostType>,
+    reflected_push_constants: Option<HostType>,
+) -> Vec<SignatureMismatch> {
+    let entries: Vec<_> = entries.collect();
+    let mut mismatches = vec![];
+
+    for host in &signature.bindings {
+        let loc = (host.set, host.binding);
+        let Some((_, entry)) = entries
+            .iter()
+            .find(|(set, entry)| (*set, entry.binding) == loc)
+        else {
+            mismatches.push(SignatureMismatch::MissingBinding(loc.0, loc.1));
+            continue;
+        };
+
+        let kind = ResourceKind::from(&entry.ty);
+        if kind != host.kind {
+            mismatches.push(SignatureMismatch::KindMismatch {
+                loc,
+                expected: kind,
+                found: host.kind,
+            });
+            continue;
+        }
+
+        let count = entry.count.map(|count| count.get());
+        if count != host.count {
+            mismatches.push(SignatureMismatch::CountMismatch {
+                loc,
+                expected: count,
+                found: host.count,
+            });
+        }
+
+        if let (Some(layout), Some(reflected)) = (&host.layout, reflected_layout(loc.0, loc.1)) {
+            diff_layout(
+                LayoutLoc::Binding(loc.0, loc.1),
+                &reflected,
+                layout,
+                &mut mismatches,
+            );
+        }
+    }
+
+    for (set, entry) in &entries {
+        let known = signature
+            .bindings
+            .iter()
+            .any(|binding| (binding.set, binding.binding) == (*set, entry.binding));
+        if !known {
+            mismatches.push(SignatureMismatch::UnexpectedBinding(*set, entry.binding));
+        }
+    }
+
+    match (&signature.push_constants, reflected_push_constants) {
+        (Some(layout), Some(reflected)) => diff_layout(
+            LayoutLoc::PushConstants,
+            &reflected,
+            layout,
+            &mut mismatches,
+        ),
+        (Some(_), None) => mismatches.push(SignatureMismatch::MissingPushConstants),
+        (None, Some(_)) => mismatches.push(SignatureMismatch::UnexpectedPushConstants),
+        (None, None) => {}
+    }
+
+    mismatches
+}
+
+fn diff_layout(
+    loc: LayoutLoc,
+    reflected: &HostType,
+    layout: &StructLayout,
+    mismatches: &mut Vec<SignatureMismatch>,
+) {
+    let size = reflected.size();
+    if size != layout.size {
+        mismatches.push(SignatureMismatch::SizeMismatch {
+            loc,
+            expected: size,
+            found: layout.size,
+        });
+    }
+
+    let HostType::Struct { members, .. } = reflected else {
+        return;
+    };
+
+    for member in members {
+        let Some(field) = layout.fields.iter().find(|field| field.name == member.name) else {
+            mismatches.push(SignatureMismatch::MissingField {
+                loc,
+                name: member.name.clone(),
+            });
+            continue;
+        };
+
+        let expected = (member.offset, member.ty.size());
+        let found = (field.offset, field.size);
+        if expected != found {
+            mismatches.push(SignatureMismatch::FieldMismatch {
+                loc,
+                name: member.name.clone(),
+                expected,
+                found,
+            });
+        }
+    }
+}
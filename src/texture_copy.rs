@@ -0,0 +1,286 @@
+//! Getting pixels in and out of textures. Data on the host side is always
+//! tightly packed: rows of blocks without `COPY_BYTES_PER_ROW_ALIGNMENT`
+//! padding, images (array layers or depth slices) one after another.
+
+use std::future::Future;
+
+use thiserror::Error;
+
+use crate::wgpu_utils::{Readback, ReadbackError};
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TextureCopyError {
+    #[error("Texture needs {0:?} usage for this copy")]
+    MissingUsage(wgpu::TextureUsages),
+    #[error("{format:?} has no copyable {aspect:?} aspect")]
+    UnsupportedFormat {
+        format: wgpu::TextureFormat,
+        aspect: wgpu::TextureAspect,
+    },
+    #[error("Mip level {0} is out of range")]
+    NoSuchMipLevel(u32),
+    #[error("Multisampled textures can't be copied")]
+    Multisampled,
+    #[error("Region {0:?} is empty")]
+    EmptyRegion(wgpu::Extent3d),
+    #[error("Region {origin:?} + {size:?} is outside the mip level of size {level:?}")]
+    OutOfBounds {
+        origin: wgpu::Origin3d,
+        size: wgpu::Extent3d,
+        level: wgpu::Extent3d,
+    },
+    #[error("Region {origin:?} + {size:?} is not aligned to {block:?} blocks")]
+    UnalignedRegion {
+        origin: wgpu::Origin3d,
+        size: wgpu::Extent3d,
+        block: (u32, u32),
+    },
+    #[error("Expected {expected} bytes of texture data, found {found}")]
+    DataSize { expected: usize, found: usize },
+    #[error("Reading back the copy failed: {0}")]
+    Readback(#[from] ReadbackError),
+}
+
+/// A box of texels in one mip level. For array and cube textures
+/// `origin.z` is the first layer and `size.depth_or_array_layers` the
+/// layer count, cube faces are layers in +X, -X, +Y, -Y, +Z, -Z order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureRegion {
+    pub mip_level: u32,
+    pub origin: wgpu::Origin3d,
+    pub size: wgpu::Extent3d,
+    pub aspect: wgpu::TextureAspect,
+}
+
+impl TextureRegion {
+    /// The whole of a mip level, every layer.
+    pub fn mip(texture: &wgpu::Texture, mip_level: u32) -> Self {
+        Self {
+            mip_level,
+            origin: wgpu::Origin3d::ZERO,
+            size: mip_size(texture, mip_level),
+            aspect: wgpu::TextureAspect::All,
+        }
+    }
+
+    /// The whole of the base mip level.
+    pub fn whole(texture: &wgpu::Texture) -> Self {
+        Self::mip(texture, 0)
+    }
+
+    /// The given layers of a mip level, for array and cube textures.
+    pub fn layers(texture: &wgpu::Texture, mip_level: u32, layers: std::ops::Range<u32>) -> Self {
+        let size = mip_size(texture, mip_level);
+        Self {
+            mip_level,
+            origin: wgpu::Origin3d {
+                x: 0,
+                y: 0,
+                z: layers.start,
+            },
+            size: wgpu::Extent3d {
+                depth_or_array_layers: layers.len() as u32,
+                ..size
+            },
+            aspect: wgpu::TextureAspect::All,
+        }
+    }
+
+    fn copy_texture<'a>(&self, texture: &'a wgpu::Texture) -> wgpu::ImageCopyTexture<'a> {
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: self.mip_level,
+            origin: self.origin,
+            aspect: self.aspect,
+        }
+    }
+}
+
+fn mip_size(texture: &wgpu::Texture, mip_level: u32) -> wgpu::Extent3d {
+    texture
+        .size()
+        .mip_level_size(mip_level, texture.dimension())
+}
+
+/// How a region's bytes are laid out on either side of a copy.
+struct CopyLayout {
+    /// bytes of one tightly packed row of blocks
+    row_bytes: u32,
+    /// bytes of one row in a staging buffer
+    padded_row_bytes: u32,
+    /// rows of blocks per image
+    rows: u32,
+    images: u32,
+}
+
+impl CopyLayout {
+    fn new(texture: &wgpu::Texture, region: &TextureRegion) -> Result<Self, TextureCopyError> {
+        if region.mip_level >= texture.mip_level_count() {
+            return Err(TextureCopyError::NoSuchMipLevel(region.mip_level));
+        }
+        if texture.sample_count() > 1 {
+            return Err(TextureCopyError::Multisampled);
+        }
+
+        let level = mip_size(texture, region.mip_level);
+        let (origin, size) = (region.origin, region.size);
+        if size.width == 0 || size.height == 0 || size.depth_or_array_layers == 0 {
+            return Err(TextureCopyError::EmptyRegion(size));
+        }
+
+        let is_3d = texture.dimension() == wgpu::TextureDimension::D3;
+        let layer_count = match is_3d {
+            true => level.depth_or_array_layers,
+            false => texture.depth_or_array_layers(),
+        };
+        let fits =
+            |start: u32, len: u32, limit| start.checked_add(len).is_some_and(|end| end <= limit);
+        if !fits(origin.x, size.width, level.width)
+            || !fits(origin.y, size.height, level.height)
+            || !fits(origin.z, size.depth_or_array_layers, layer_count)
+        {
+            return Err(TextureCopyError::OutOfBounds {
+                origin,
+                size,
+                level,
+            });
+        }
+
+        let format = texture.format();
+        let block_size = format.block_copy_size(Some(region.aspect)).ok_or(
+            TextureCopyError::UnsupportedFormat {
+                format,
+                aspect: region.aspect,
+            },
+        )?;
+        let (block_width, block_height) = format.block_dimensions();
+        if !size.width.is_multiple_of(block_width)
+            || !size.height.is_multiple_of(block_height)
+            || !origin.x.is_multiple_of(block_width)
+            || !origin.y.is_multiple_of(block_height)
+        {
+            return Err(TextureCopyError::UnalignedRegion {
+                origin,
+                size,
+                block: (block_width, block_height),
+            });
+        }
+
+        let row_bytes = size.width / block_width * block_size;
+        Ok(Self {
+            row_bytes,
+            padded_row_bytes: row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+            rows: size.height / block_height,
+            images: size.depth_or_array_layers,
+        })
+    }
+
+    fn packed_size(&self) -> usize {
+        (self.row_bytes * self.rows * self.images) as usize
+    }
+
+    fn padded_size(&self) -> u64 {
+        u64::from(self.padded_row_bytes) * u64::from(self.rows) * u64::from(self.images)
+    }
+
+    /// Drop the padding at the end of each row.
+    fn unpad(&self, padded: &[u8]) -> Vec<u8> {
+        padded
+            .chunks(self.padded_row_bytes as usize)
+            .flat_map(|row| &row[..self.row_bytes as usize])
+            .copied()
+            .collect()
+    }
+}
+
+fn require_usage(
+    texture: &wgpu::Texture,
+    usage: wgpu::TextureUsages,
+) -> Result<(), TextureCopyError> {
+    match texture.usage().contains(usage) {
+        true => Ok(()),
+        false => Err(TextureCopyError::MissingUsage(usage)),
+    }
+}
+
+pub(crate) fn write_region(
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    region: &TextureRegion,
+    data: &[u8],
+) -> Result<(), TextureCopyError> {
+    require_usage(texture, wgpu::TextureUsages::COPY_DST)?;
+    let layout = CopyLayout::new(texture, region)?;
+    if data.len() != layout.packed_size() {
+        return Err(TextureCopyError::DataSize {
+            expected: layout.packed_size(),
+            found: data.len(),
+        });
+    }
+
+    // unlike buffer copies, queue writes take unpadded rows
+    queue.write_texture(
+        region.copy_texture(texture),
+        data,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(layout.row_bytes),
+            rows_per_image: Some(layout.rows),
+        },
+        region.size,
+    );
+    Ok(())
+}
+
+/// Copy the region into a staging buffer and start mapping it, the
+/// readback resolves to padded rows.
+fn stage_region<'a>(
+    device: &'a wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    region: &TextureRegion,
+) -> Result<(Readback<'a>, CopyLayout), TextureCopyError> {
+    require_usage(texture, wgpu::TextureUsages::COPY_SRC)?;
+    let layout = CopyLayout::new(texture, region)?;
+
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("kinnara/texture_staging"),
+        size: layout.padded_size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("kinnara/texture_readback"),
+    });
+    encoder.copy_texture_to_buffer(
+        region.copy_texture(texture),
+        wgpu::ImageCopyBuffer {
+            buffer: &staging,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(layout.padded_row_bytes),
+                rows_per_image: Some(layout.rows),
+            },
+        },
+        region.size,
+    );
+    queue.submit([encoder.finish()]);
+
+    Ok((Readback::staged(device, staging), layout))
+}
+
+/// Validation and the copy happen right away, the future only waits for
+/// the mapping.
+pub(crate) fn read_region<'a>(
+    device: &'a wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    region: &TextureRegion,
+) -> impl Future<Output = Result<Vec<u8>, TextureCopyError>> + 'a {
+    let staged = stage_region(device, queue, texture, region);
+    async move {
+        let (readback, layout) = staged?;
+        Ok(layout.unpad(&readback.await?))
+    }
+}
@@ -1,11 +1,262 @@
+use derive_more::From;
+use thiserror::Error;
+
+use crate::{bind_group::BindGroups, BindSlot, BindingProblem, DescribedView, HostType, Signature};
+
+/// Where a field of a [`PushConstant`] type sits in its bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PushConstantField {
+    pub name: &'static str,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// A host type holding a shader's whole push constant block, usually
+/// implemented with `#[derive(PushConstant)]`. Fields are matched to the
+/// members of the shader's block by name.
 pub trait PushConstant {
-    fn range(&self) -> (usize, &[u8]);
+    fn bytes(&self) -> &[u8];
+    fn fields(&self) -> &[PushConstantField];
 }
 
 impl PushConstant for () {
-    fn range(&self) -> (usize, &[u8]) {
-        (0, &[])
+    fn bytes(&self) -> &[u8] {
+        &[]
+    }
+
+    fn fields(&self) -> &[PushConstantField] {
+        &[]
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PushConstantError {
+    #[error("Shader has no push constants, but {0} bytes were given")]
+    NoPushConstants(u32),
+    #[error("Push constant block is {expected} bytes, the rust type is {found}")]
+    SizeMismatch { expected: u32, found: u32 },
+    #[error("Push constant member `{0}` has no field in the rust type")]
+    MissingField(String),
+    #[error("Field `{name}` is at offset {found}, the shader has it at {expected}")]
+    OffsetMismatch {
+        name: String,
+        expected: u32,
+        found: u32,
+    },
+    #[error("Field `{name}` is {found} bytes, the shader's is {expected}")]
+    FieldSizeMismatch {
+        name: String,
+        expected: u32,
+        found: u32,
+    },
+}
+
+/// Check a rust push constant type against the reflected block. Rust
+/// fields the shader doesn't name, like explicit padding, are allowed.
+pub(crate) fn check_push_constant<P: PushConstant + ?Sized>(
+    reflected: Option<&HostType>,
+    constants: &P,
+) -> Result<(), PushConstantError> {
+    let found = constants.bytes().len() as u32;
+    let Some(reflected) = reflected else {
+        return match found {
+            0 => Ok(()),
+            found => Err(PushConstantError::NoPushConstants(found)),
+        };
+    };
+
+    let expected = reflected.size();
+    if expected != found {
+        return Err(PushConstantError::SizeMismatch { expected, found });
+    }
+
+    let HostType::Struct { members, .. } = reflected else {
+        return Ok(());
+    };
+
+    for member in members {
+        let field = constants
+            .fields()
+            .iter()
+            .find(|field| field.name == member.name)
+            .ok_or_else(|| PushConstantError::MissingField(member.name.clone()))?;
+
+        if field.offset != member.offset {
+            return Err(PushConstantError::OffsetMismatch {
+                name: member.name.clone(),
+                expected: member.offset,
+                found: field.offset,
+            });
+        }
+
+        let expected = member.ty.size();
+        if field.size != expected {
+            return Err(PushConstantError::FieldSizeMismatch {
+                name: member.name.clone(),
+                expected,
+                found: field.size,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Which binding a field of a [`Bindings`] type fills.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingTarget {
+    Loc(u32, u32),
+    /// the shader variable name, see [`crate::ComputeReflector::find_binding`]
+    Name(&'static str),
+}
+
+/// A resource held by a field of a [`Bindings`] type.
+#[derive(Debug, Clone, From)]
+pub enum FieldResource<'a> {
+    Buffer(wgpu::BufferBinding<'a>),
+    BufferArray(&'a [wgpu::BufferBinding<'a>]),
+    Texture(&'a wgpu::TextureView),
+    /// a texture view checked against the binding before it is bound
+    DescribedTexture(&'a DescribedView),
+    TextureArray(&'a [&'a wgpu::TextureView]),
+    Sampler(&'a wgpu::Sampler),
+    SamplerArray(&'a [&'a wgpu::Sampler]),
+}
+
+impl<'a> From<&'a wgpu::Buffer> for FieldResource<'a> {
+    fn from(value: &'a wgpu::Buffer) -> Self {
+        Self::Buffer(value.as_entire_buffer_binding())
+    }
+}
+
+impl FieldResource<'_> {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Buffer(_) => "a buffer",
+            Self::BufferArray(_) => "a buffer array",
+            Self::Texture(_) | Self::DescribedTexture(_) => "a texture",
+            Self::TextureArray(_) => "a texture array",
+            Self::Sampler(_) => "a sampler",
+            Self::SamplerArray(_) => "a sampler array",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BindingField<'a> {
+    pub field: &'static str,
+    pub target: BindingTarget,
+    pub resource: FieldResource<'a>,
+}
+
+/// A host type holding the resources of a shader's bindings, usually
+/// implemented with `#[derive(Bindings)]` in place of a `BindSlot` closure.
+pub trait Bindings<'a> {
+    fn bindings(&self) -> Vec<BindingField<'a>>;
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum BindingsError {
+    #[error("Field `{field}` targets {target:?}, which the shader doesn't bind")]
+    NoSuchBinding {
+        field: &'static str,
+        target: BindingTarget,
+    },
+}
+
+/// Check every field targets a binding of the shader.
+pub(crate) fn check_targets(
+    fields: &[BindingField],
+    bind_groups: &BindGroups,
+) -> Result<(), BindingsError> {
+    let exists = |target| match target {
+        BindingTarget::Loc(set, binding) => bind_groups
+            .get_bind_group_layout_entry(set, binding)
+            .is_some(),
+        BindingTarget::Name(name) => bind_groups.find_binding(name).is_some(),
+    };
+
+    match fields.iter().find(|field| !exists(field.target)) {
+        Some(field) => Err(BindingsError::NoSuchBinding {
+            field: field.field,
+            target: field.target,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Fill `slot` from the field targeting it, slots no field targets are
+/// left empty.
+pub(crate) fn fill_slot<'a>(
+    fields: &[BindingField<'a>],
+    slot: &BindSlot<'a>,
+) -> Result<(), BindingProblem> {
+    let Some(field) = fields.iter().find(|field| match field.target {
+        BindingTarget::Loc(set, binding) => slot.loc() == (set, binding),
+        BindingTarget::Name(name) => slot.name() == Some(name),
+    }) else {
+        return Ok(());
+    };
+
+    match (slot, field.resource.clone()) {
+        (
+            BindSlot::StorageBuffer { slot, .. } | BindSlot::UniformBuffer { slot, .. },
+            FieldResource::Buffer(buffer),
+        ) => {
+            slot.replace(Some(buffer));
+        }
+        (
+            BindSlot::StorageBufferArray { slots, .. } | BindSlot::UniformBufferArray { slots, .. },
+            FieldResource::BufferArray(buffers),
+        ) => {
+            slots.replace(Some(buffers));
+        }
+        (BindSlot::Texture { slot, .. }, FieldResource::Texture(view)) => {
+            slot.replace(Some(view));
+        }
+        (slot @ BindSlot::Texture { .. }, FieldResource::DescribedTexture(view)) => {
+            slot.fill_view(view);
+        }
+        (BindSlot::TextureArray { slots, .. }, FieldResource::TextureArray(views)) => {
+            slots.replace(Some(views));
+        }
+        (BindSlot::Sampler { slot, .. }, FieldResource::Sampler(sampler)) => {
+            slot.replace(Some(sampler));
+        }
+        (BindSlot::SamplerArray { slots, .. }, FieldResource::SamplerArray(samplers)) => {
+            slots.replace(Some(samplers));
+        }
+        (_, resource) => {
+            return Err(BindingProblem::WrongKind {
+                field: field.field,
+                found: resource.kind(),
+            })
+        }
     }
+
+    Ok(())
+}
+
+/// Slots left empty by [`fill_slot`] are reported as missing, replace
+/// that with why they were left empty.
+pub(crate) fn with_wrong_kinds<T>(
+    result: Result<T, crate::Error>,
+    wrong_kinds: Vec<((u32, u32), BindingProblem)>,
+) -> Result<T, crate::Error> {
+    let Err(crate::Error::InvalidBindings(mut issues)) = result else {
+        return result;
+    };
+
+    for issue in &mut issues {
+        if let Some((_, problem)) = wrong_kinds.iter().find(|(loc, _)| *loc == issue.loc) {
+            issue.problem = problem.clone();
+        }
+    }
+    Err(crate::Error::InvalidBindings(issues))
 }
 
-pub trait BindingSignature {}
+/// The resource interface a host type expects from a shader, checked with
+/// [`crate::ComputeReflector::check_signature`].
+pub trait BindingSignature {
+    fn signature() -> Signature;
+}
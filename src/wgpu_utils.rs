@@ -1,3 +1,24 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use thiserror::Error;
+use wgpu::naga::valid::Capabilities;
+
+use crate::texture_copy::{self, TextureCopyError, TextureRegion};
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ReadbackError {
+    #[error("Buffer needs MAP_READ or COPY_SRC usage to be read back")]
+    NotReadable,
+    #[error("Buffer size {0} is not a multiple of {align}", align = wgpu::COPY_BUFFER_ALIGNMENT)]
+    UnalignedSize(u64),
+    #[error("Mapping the buffer failed: {0}")]
+    Map(#[from] wgpu::BufferAsyncError),
+    #[error("{len} bytes can't be read as elements of {size} bytes")]
+    ElementSize { len: usize, size: usize },
+}
 
 pub trait DeviceUtils {
     fn wgpu_try<T, F>(&self, filter: wgpu::ErrorFilter, func: F) -> Result<T, wgpu::Error>
@@ -7,6 +28,62 @@ pub trait DeviceUtils {
     fn buffer_view<T, F>(&self, buffer: &wgpu::Buffer, func: F) -> T
     where
         F: FnOnce(Option<&[u8]>) -> T;
+
+    /// Run `future` to completion on this thread, waiting on the device
+    /// whenever it is pending. Meant for the readbacks below, which only
+    /// make progress when the device is polled.
+    fn wait_for<F: Future>(&self, future: F) -> F::Output;
+
+    /// Read the whole buffer back, buffers without MAP_READ are copied
+    /// into a staging buffer made for the read.
+    fn read_buffer<'a>(&'a self, queue: &wgpu::Queue, buffer: &'a wgpu::Buffer) -> Readback<'a>;
+
+    /// Like [`DeviceUtils::read_buffer`], with the bytes cast to `T`.
+    fn read_buffer_as<'a, T: bytemuck::Pod>(
+        &'a self,
+        queue: &wgpu::Queue,
+        buffer: &'a wgpu::Buffer,
+    ) -> impl Future<Output = Result<Vec<T>, ReadbackError>> + 'a {
+        cast_readback(self.read_buffer(queue, buffer))
+    }
+
+    /// Read back the base mip level of a COPY_SRC texture as tightly
+    /// packed rows, every layer one after another.
+    fn read_texture<'a>(
+        &'a self,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+    ) -> impl Future<Output = Result<Vec<u8>, TextureCopyError>> + 'a;
+
+    /// Like [`DeviceUtils::read_texture`], for a region of any mip level.
+    fn read_texture_region<'a>(
+        &'a self,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        region: &TextureRegion,
+    ) -> impl Future<Output = Result<Vec<u8>, TextureCopyError>> + 'a;
+
+    /// Fill the base mip level of a COPY_DST texture from tightly packed
+    /// rows, laid out like [`DeviceUtils::read_texture`] returns them.
+    fn write_texture(
+        &self,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        data: &[u8],
+    ) -> Result<(), TextureCopyError> {
+        self.write_texture_region(queue, texture, &TextureRegion::whole(texture), data)
+    }
+
+    /// Like [`DeviceUtils::write_texture`], for a region of any mip level.
+    fn write_texture_region(
+        &self,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        region: &TextureRegion,
+        data: &[u8],
+    ) -> Result<(), TextureCopyError> {
+        texture_copy::write_region(queue, texture, region, data)
+    }
 }
 
 impl DeviceUtils for wgpu::Device {
@@ -14,16 +91,14 @@ impl DeviceUtils for wgpu::Device {
     where
         F: FnOnce(&wgpu::Device) -> T,
     {
-        //TODO: Naga PANICS! when the validation error is non-global.
-        // this makes this utility aggravating.
-
-        //self.push_error_scope(filter);
+        // shader modules are validated by kinnara before they get here, an
+        // invalid naga module would make wgpu panic rendering the error
+        self.push_error_scope(filter);
         let result = func(self);
-        //match self.pop_error_scope().block_on() {
-        //    Some(error) => Err(dbg!(error)),
-        //    None => Ok(result),
-        //}
-        Ok(result)
+        match pollster::block_on(self.pop_error_scope()) {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
     }
 
     /// funs the function on Some(&[u8]) if it the buffer can be mapped,
@@ -48,4 +123,396 @@ impl DeviceUtils for wgpu::Device {
             func(None)
         }
     }
+
+    fn wait_for<F: Future>(&self, future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            // runs the map callbacks once the submitted work is done
+            self.poll(wgpu::Maintain::Wait);
+        }
+    }
+
+    fn read_buffer<'a>(&'a self, queue: &wgpu::Queue, buffer: &'a wgpu::Buffer) -> Readback<'a> {
+        Readback::new(self, queue, buffer, None)
+    }
+
+    fn read_texture<'a>(
+        &'a self,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+    ) -> impl Future<Output = Result<Vec<u8>, TextureCopyError>> + 'a {
+        texture_copy::read_region(self, queue, texture, &TextureRegion::whole(texture))
+    }
+
+    fn read_texture_region<'a>(
+        &'a self,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        region: &TextureRegion,
+    ) -> impl Future<Output = Result<Vec<u8>, TextureCopyError>> + 'a {
+        texture_copy::read_region(self, queue, texture, region)
+    }
+}
+
+/// The naga capabilities of a device with `features`, created from an
+/// adapter with the `downlevel` flags, mirroring the ones wgpu validates
+/// shader modules against.
+pub(crate) fn capabilities(
+    features: wgpu::Features,
+    downlevel: wgpu::DownlevelFlags,
+) -> Capabilities {
+    use wgpu::{DownlevelFlags, Features};
+
+    let mut caps = Capabilities::empty();
+    caps.set(
+        Capabilities::MULTISAMPLED_SHADING,
+        downlevel.contains(DownlevelFlags::MULTISAMPLED_SHADING),
+    );
+    caps.set(
+        Capabilities::CUBE_ARRAY_TEXTURES,
+        downlevel.contains(DownlevelFlags::CUBE_ARRAY_TEXTURES),
+    );
+    let from_features = [
+        (Capabilities::PUSH_CONSTANT, Features::PUSH_CONSTANTS),
+        (Capabilities::FLOAT64, Features::SHADER_F64),
+        (
+            Capabilities::PRIMITIVE_INDEX,
+            Features::SHADER_PRIMITIVE_INDEX,
+        ),
+        (
+            Capabilities::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+            Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+        ),
+        (
+            Capabilities::UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING,
+            Features::UNIFORM_BUFFER_AND_STORAGE_TEXTURE_ARRAY_NON_UNIFORM_INDEXING,
+        ),
+        (
+            Capabilities::SAMPLER_NON_UNIFORM_INDEXING,
+            Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+        ),
+        (
+            Capabilities::STORAGE_TEXTURE_16BIT_NORM_FORMATS,
+            Features::TEXTURE_FORMAT_16BIT_NORM,
+        ),
+        (Capabilities::MULTIVIEW, Features::MULTIVIEW),
+        (
+            Capabilities::EARLY_DEPTH_TEST,
+            Features::SHADER_EARLY_DEPTH_TEST,
+        ),
+        (Capabilities::SHADER_INT64, Features::SHADER_INT64),
+        (
+            Capabilities::SHADER_INT64_ATOMIC_ALL_OPS,
+            Features::SHADER_INT64_ATOMIC_ALL_OPS,
+        ),
+        (
+            Capabilities::DUAL_SOURCE_BLENDING,
+            Features::DUAL_SOURCE_BLENDING,
+        ),
+        (
+            Capabilities::SUBGROUP_VERTEX_STAGE,
+            Features::SUBGROUP_VERTEX,
+        ),
+        (Capabilities::SUBGROUP_BARRIER, Features::SUBGROUP_BARRIER),
+    ];
+    for (cap, feature) in from_features {
+        caps.set(cap, features.contains(feature));
+    }
+
+    // these follow from more than one feature
+    caps.set(
+        Capabilities::SHADER_INT64_ATOMIC_MIN_MAX,
+        features.intersects(
+            Features::SHADER_INT64_ATOMIC_MIN_MAX | Features::SHADER_INT64_ATOMIC_ALL_OPS,
+        ),
+    );
+    caps.set(
+        Capabilities::SUBGROUP,
+        features.intersects(Features::SUBGROUP | Features::SUBGROUP_VERTEX),
+    );
+    caps
+}
+
+/// Staging buffers kept between readbacks of buffers without MAP_READ,
+/// so reading the same buffers every frame doesn't allocate.
+#[derive(Debug, Default)]
+pub struct StagingPool {
+    free: Mutex<Vec<wgpu::Buffer>>,
+}
+
+impl StagingPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Like [`DeviceUtils::read_buffer`], the staging buffer is taken from
+    /// the pool and returned to it once the bytes are read.
+    pub fn read<'a>(
+        &'a self,
+        device: &'a wgpu::Device,
+        queue: &wgpu::Queue,
+        buffer: &'a wgpu::Buffer,
+    ) -> Readback<'a> {
+        Readback::new(device, queue, buffer, Some(self))
+    }
+
+    /// Like [`StagingPool::read`], with the bytes cast to `T`.
+    pub fn read_as<'a, T: bytemuck::Pod>(
+        &'a self,
+        device: &'a wgpu::Device,
+        queue: &wgpu::Queue,
+        buffer: &'a wgpu::Buffer,
+    ) -> impl Future<Output = Result<Vec<T>, ReadbackError>> + 'a {
+        cast_readback(self.read(device, queue, buffer))
+    }
+
+    /// The smallest free buffer holding `size` bytes, or a new one.
+    fn take(&self, device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+        let mut free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+        let fitting = free
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.size() >= size)
+            .min_by_key(|(_, buffer)| buffer.size())
+            .map(|(i, _)| i);
+
+        match fitting {
+            Some(i) => free.swap_remove(i),
+            None => staging_buffer(device, size),
+        }
+    }
+
+    fn give(&self, buffer: wgpu::Buffer) {
+        let mut free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+        free.push(buffer);
+    }
+}
+
+fn staging_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("kinnara/staging"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+async fn cast_readback<T: bytemuck::Pod>(readback: Readback<'_>) -> Result<Vec<T>, ReadbackError> {
+    let bytes = readback.await?;
+    let size = std::mem::size_of::<T>();
+    if size == 0 || !bytes.len().is_multiple_of(size) {
+        return Err(ReadbackError::ElementSize {
+            len: bytes.len(),
+            size,
+        });
+    }
+    Ok(bytemuck::pod_collect_to_vec(&bytes))
+}
+
+enum MapState {
+    Pending(Option<Waker>),
+    Done(Result<(), wgpu::BufferAsyncError>),
+    Taken,
+}
+
+enum Source<'a> {
+    Mapped(&'a wgpu::Buffer),
+    Staged {
+        buffer: wgpu::Buffer,
+        pool: Option<&'a StagingPool>,
+    },
+}
+
+/// The bytes of a buffer being read back. It resolves once the buffer is
+/// mapped, which natively only happens while the device is polled: keep
+/// calling `device.poll(Maintain::Poll)`, e.g. once a frame, or block with
+/// [`DeviceUtils::wait_for`]. Awaiting it never blocks.
+pub struct Readback<'a> {
+    device: &'a wgpu::Device,
+    source: Option<Source<'a>>,
+    size: u64,
+    state: Arc<Mutex<MapState>>,
+    /// set when the result is known without mapping anything
+    ready: Option<Result<Vec<u8>, ReadbackError>>,
+}
+
+impl<'a> Readback<'a> {
+    fn new(
+        device: &'a wgpu::Device,
+        queue: &wgpu::Queue,
+        buffer: &'a wgpu::Buffer,
+        pool: Option<&'a StagingPool>,
+    ) -> Self {
+        let size = buffer.size();
+        let usage = buffer.usage();
+
+        if usage.contains(wgpu::BufferUsages::MAP_READ) {
+            return Self::map(device, Source::Mapped(buffer), size);
+        }
+        if !usage.contains(wgpu::BufferUsages::COPY_SRC) {
+            return Self::failed(device, ReadbackError::NotReadable);
+        }
+        if !size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Self::failed(device, ReadbackError::UnalignedSize(size));
+        }
+        if size == 0 {
+            return Self::ready(device, Ok(vec![]));
+        }
+
+        let staging = match pool {
+            Some(pool) => pool.take(device, size),
+            None => staging_buffer(device, size),
+        };
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("kinnara/readback"),
+        });
+        encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, size);
+        queue.submit([encoder.finish()]);
+
+        let source = Source::Staged {
+            buffer: staging,
+            pool,
+        };
+        Self::map(device, source, size)
+    }
+
+    /// Read back a staging buffer the caller already copied into.
+    pub(crate) fn staged(device: &'a wgpu::Device, buffer: wgpu::Buffer) -> Self {
+        let size = buffer.size();
+        let source = Source::Staged { buffer, pool: None };
+        Self::map(device, source, size)
+    }
+
+    fn failed(device: &'a wgpu::Device, error: ReadbackError) -> Self {
+        Self::ready(device, Err(error))
+    }
+
+    fn ready(device: &'a wgpu::Device, result: Result<Vec<u8>, ReadbackError>) -> Self {
+        Self {
+            device,
+            source: None,
+            size: 0,
+            state: Arc::new(Mutex::new(MapState::Taken)),
+            ready: Some(result),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, MapState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn map(device: &'a wgpu::Device, source: Source<'a>, size: u64) -> Self {
+        // empty slices can't be mapped
+        if size == 0 {
+            source.release();
+            return Self::ready(device, Ok(vec![]));
+        }
+
+        let state = Arc::new(Mutex::new(MapState::Pending(None)));
+
+        let callback_state = state.clone();
+        source
+            .buffer()
+            .slice(..size)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let mut state = callback_state.lock().unwrap_or_else(|e| e.into_inner());
+                if let MapState::Pending(Some(waker)) =
+                    std::mem::replace(&mut *state, MapState::Done(result))
+                {
+                    waker.wake();
+                }
+            });
+
+        Self {
+            device,
+            source: Some(source),
+            size,
+            state,
+            ready: None,
+        }
+    }
+}
+
+impl Source<'_> {
+    fn buffer(&self) -> &wgpu::Buffer {
+        match self {
+            Source::Mapped(buffer) => buffer,
+            Source::Staged { buffer, .. } => buffer,
+        }
+    }
+
+    /// Return a staging buffer to its pool, it must be unmapped.
+    fn release(self) {
+        if let Source::Staged {
+            buffer,
+            pool: Some(pool),
+        } = self
+        {
+            pool.give(buffer);
+        }
+    }
+}
+
+/// A readback dropped before it resolved still owns its buffer, which may
+/// be mapped or have a map in flight.
+impl Drop for Readback<'_> {
+    fn drop(&mut self) {
+        let Some(source) = self.source.take() else {
+            return;
+        };
+
+        let state = std::mem::replace(&mut *self.lock_state(), MapState::Taken);
+        match state {
+            // unmapping a pending map cancels it
+            MapState::Pending(_) | MapState::Done(Ok(())) => source.buffer().unmap(),
+            MapState::Done(Err(_)) | MapState::Taken => {}
+        }
+        source.release();
+    }
+}
+
+impl Future for Readback<'_> {
+    type Output = Result<Vec<u8>, ReadbackError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(result) = this.ready.take() {
+            return Poll::Ready(result);
+        }
+
+        // picks up a map that finished since the device was last polled,
+        // on the web the callback runs on its own and this does nothing
+        if matches!(*this.lock_state(), MapState::Pending(_)) {
+            this.device.poll(wgpu::Maintain::Poll);
+        }
+
+        let mut state = this.lock_state();
+        match std::mem::replace(&mut *state, MapState::Taken) {
+            MapState::Done(result) => {
+                drop(state);
+                let Some(source) = this.source.take() else {
+                    return Poll::Ready(Err(ReadbackError::NotReadable));
+                };
+                if let Err(e) = result {
+                    return Poll::Ready(Err(e.into()));
+                }
+
+                let buffer = source.buffer();
+                let bytes = buffer.slice(..this.size).get_mapped_range().to_vec();
+                buffer.unmap();
+                source.release();
+                Poll::Ready(Ok(bytes))
+            }
+            MapState::Pending(_) => {
+                *state = MapState::Pending(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+            MapState::Taken => Poll::Ready(Err(ReadbackError::NotReadable)),
+        }
+    }
 }
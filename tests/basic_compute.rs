@@ -1,7 +1,7 @@
 use kinnara::*;
 use wgpu::{BindGroupLayoutDescriptor, PushConstantRange, ShaderSource, ShaderStages};
 
-fn compute_stage(src: &str) -> ShaderSource {
+fn compute_stage(src: &str) -> ShaderSource<'_> {
     wgpu::ShaderSource::Glsl {
         shader: src.into(),
         stage: wgpu::naga::ShaderStage::Compute,
@@ -0,0 +1,128 @@
+mod common;
+
+use kinnara::*;
+use wgpu::{BufferUsages, ShaderSource};
+
+use common::set_up_device;
+
+const ISSUES_SRC: &str = r#"
+// @kinnara uniform uni (dynamic_offset=true)
+
+struct Uni { scale: f32 }
+
+@group(0) @binding(0) var tex: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read> data: array<f32>;
+@group(1) @binding(0) var<uniform> uni: Uni;
+@group(1) @binding(1) var<storage, read_write> out: array<f32>;
+
+@compute @workgroup_size(1)
+fn main() {
+    out[0] = textureLoad(tex, vec2<i32>(0), 0).x * data[0] * uni.scale;
+}
+"#;
+
+#[test]
+fn every_issue_at_once() {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(ISSUES_SRC.into())).unwrap();
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl).unwrap();
+
+    let out = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 4,
+        usage: BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+    let err = pipeline
+        .bind_by_name(&device, |name, slot| {
+            if let (true, BindSlot::StorageBuffer { slot, .. }) = (name == "out", slot) {
+                slot.replace(Some(out.as_entire_buffer_binding()));
+            }
+        })
+        .err()
+        .unwrap();
+
+    let expected = "\
+Invalid bindings:
+  set 0 binding 0 (`tex`) expects a D2 float texture, nothing was bound
+  set 0 binding 1 (`data`) expects a read-only storage buffer, nothing was bound
+  set 1 binding 0 (`uni`) expects a uniform buffer, nothing was bound";
+    assert_eq!(err.to_string(), expected);
+}
+
+#[test]
+fn missing_pass_slots() -> Result<(), Error> {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(ISSUES_SRC.into()))?;
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl)?;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 256,
+        usage: BufferUsages::STORAGE | BufferUsages::UNIFORM,
+        mapped_at_creation: false,
+    });
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&Default::default());
+
+    let bound = pipeline.bind(&device, |slot| match slot {
+        BindSlot::StorageBuffer { slot, .. } => {
+            slot.replace(Some(buffer.as_entire_buffer_binding()));
+        }
+        BindSlot::UniformBuffer { slot, .. } => {
+            slot.replace(Some(wgpu::BufferBinding {
+                buffer: &buffer,
+                offset: 0,
+                size: wgpu::BufferSize::new(4),
+            }));
+        }
+        BindSlot::Texture { slot, .. } => {
+            slot.replace(Some(&view));
+        }
+        _ => {}
+    })?;
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    let err = bound.create_pass(&mut encoder, |_| {}).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Incomplete pass, missing:\n  a dynamic offset for set 1 binding 0 (`uni`)"
+    );
+    assert!(matches!(
+        err,
+        Error::PassConstruction(slots) if slots == [MissingPassSlot::DynamicOffset {
+            loc: (1, 0),
+            name: Some("uni".to_owned()),
+        }]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn array_issue_message() {
+    let issue = BindingIssue {
+        loc: (2, 3),
+        name: None,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: Some(4),
+        problem: BindingProblem::WrongCount { found: 2 },
+    };
+    assert_eq!(
+        issue.to_string(),
+        "set 2 binding 3 expects an array of 4 filtering samplers, 2 were bound"
+    );
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use kinnara::*;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{BufferUsages, ShaderSource};
+
+use common::set_up_device;
+
+const NAMED_SRC: &str = r"
+#version 450
+
+layout(set = 0, binding = 0) uniform Params { uint len; };
+layout(set = 1, binding = 0) readonly buffer Input { float values[]; } input_buf;
+layout(set = 1, binding = 1) buffer Output { float values[]; } output_buf;
+
+layout(local_size_x = 1, local_size_y = 1, local_size_z = 1) in;
+void main() {
+    uint i = gl_GlobalInvocationID.x;
+    if (i < len) {
+        output_buf.values[i] = input_buf.values[i] * 2.0;
+    }
+}
+";
+
+fn compute_stage(src: &str) -> ShaderSource<'_> {
+    ShaderSource::Glsl {
+        shader: src.into(),
+        stage: wgpu::naga::ShaderStage::Compute,
+        defines: Default::default(),
+    }
+}
+
+#[test]
+fn binding_names() {
+    let refl = ComputeReflector::new_compute(compute_stage(NAMED_SRC)).unwrap();
+
+    assert_eq!(refl.binding_name(1, 0), Some("input_buf"));
+    assert_eq!(refl.binding_name(1, 1), Some("output_buf"));
+    // anonymous blocks are known by their block name
+    assert_eq!(refl.binding_name(0, 0), Some("Params"));
+    assert_eq!(refl.binding_name(2, 0), None);
+
+    assert_eq!(refl.find_binding("output_buf"), Some((1, 1)));
+    assert_eq!(refl.find_binding("Params"), Some((0, 0)));
+    assert_eq!(refl.find_binding("missing"), None);
+}
+
+#[test]
+fn bind_by_name() -> Result<(), Error> {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(compute_stage(NAMED_SRC))?;
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl)?;
+
+    let buffer = |usage| {
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: &[0; 16],
+            usage,
+        })
+    };
+    let params = buffer(BufferUsages::UNIFORM);
+    let input = buffer(BufferUsages::STORAGE);
+    let output = buffer(BufferUsages::STORAGE);
+
+    let mut seen = vec![];
+    let mut bound = pipeline.bind_by_name(&device, |name, slot| {
+        seen.push((name.to_owned(), slot.loc()));
+        let buffer = match name {
+            "Params" => &params,
+            "input_buf" => &input,
+            "output_buf" => &output,
+            other => panic!("unexpected binding {other}"),
+        };
+        match slot {
+            BindSlot::UniformBuffer { slot, .. } | BindSlot::StorageBuffer { slot, .. } => {
+                slot.borrow_mut().replace(buffer.as_entire_buffer_binding());
+            }
+            other => panic!("unexpected slot {other:?}"),
+        }
+    })?;
+
+    seen.sort();
+    assert_eq!(
+        seen,
+        [
+            ("Params".to_owned(), (0, 0)),
+            ("input_buf".to_owned(), (1, 0)),
+            ("output_buf".to_owned(), (1, 1)),
+        ]
+    );
+
+    assert!(matches!(
+        bound.rebind_set(&device, 2, |_| {}),
+        Err(Error::NoSuchSet(2))
+    ));
+
+    // leaving a named slot empty is reported with its name
+    let missing = bound.rebind_all_by_name(&device, |name, slot| {
+        if let (true, BindSlot::UniformBuffer { slot, .. }) = (name == "Params", slot) {
+            slot.borrow_mut().replace(params.as_entire_buffer_binding());
+        }
+    });
+    let Err(Error::InvalidBindings(issues)) = missing else {
+        panic!("expected invalid bindings");
+    };
+    let missing: Vec<_> = issues
+        .iter()
+        .map(|issue| (issue.loc, issue.name.as_deref()))
+        .collect();
+    assert_eq!(
+        missing,
+        [((1, 0), Some("input_buf")), ((1, 1), Some("output_buf"))]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "derive")]
+
+mod common;
+
+use kinnara::*;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{BufferUsages, ShaderSource};
+
+use common::set_up_device;
+
+const SCALE_SRC: &str = r"
+struct Params { factor: f32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(1) @binding(0) var<storage, read> input: array<f32>;
+@group(1) @binding(1) var<storage, read_write> output: array<f32>;
+
+@compute @workgroup_size(1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    output[id.x] = input[id.x] * params.factor;
+}
+";
+
+#[derive(Bindings)]
+struct ScaleBindings<'a> {
+    #[binding(0, 0)]
+    params: &'a wgpu::Buffer,
+    #[binding(name = "input")]
+    values: wgpu::BufferBinding<'a>,
+    // matched by the field name
+    output: &'a wgpu::Buffer,
+}
+
+#[derive(Bindings)]
+struct ScaleParams<'a> {
+    params: &'a wgpu::Buffer,
+}
+
+#[derive(Bindings)]
+struct WrongKind<'a> {
+    #[binding(0, 0)]
+    params: &'a wgpu::Sampler,
+}
+
+#[derive(Bindings)]
+struct Unknown<'a> {
+    #[binding(name = "missing")]
+    params: &'a wgpu::Buffer,
+}
+
+fn floats(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[test]
+fn derived_bindings() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(SCALE_SRC.into()))?;
+
+    let buffer = |contents: &[u8], usage| {
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents,
+            usage,
+        })
+    };
+    let params = buffer(&floats(&[3.0]), BufferUsages::UNIFORM);
+    let input = buffer(&floats(&[1.0, 2.0, 3.0]), BufferUsages::STORAGE);
+    let output = buffer(&[0; 12], BufferUsages::STORAGE | BufferUsages::COPY_SRC);
+
+    let bindings = ScaleBindings {
+        params: &params,
+        values: input.as_entire_buffer_binding(),
+        output: &output,
+    };
+
+    // a single set from the same struct
+    refl.create_bind_group_from(&device, 1, &bindings)?;
+
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl)?;
+    let bound = pipeline.bind_with(&device, &bindings)?;
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut pass = bound.create_pass(&mut encoder, |_| {})?;
+        pass.dispatch_workgroups(3, 1, 1);
+    }
+    queue.submit([encoder.finish()]);
+
+    let values = device.wait_for(device.read_buffer_as::<f32>(&queue, &output))?;
+    assert_eq!(values, [3.0, 6.0, 9.0]);
+
+    Ok(())
+}
+
+#[test]
+fn binding_errors() {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(SCALE_SRC.into())).unwrap();
+    let sampler = device.create_sampler(&Default::default());
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 4,
+        usage: BufferUsages::UNIFORM,
+        mapped_at_creation: false,
+    });
+
+    let Err(Error::InvalidBindings(issues)) =
+        refl.create_bind_group_from(&device, 0, &WrongKind { params: &sampler })
+    else {
+        panic!("expected invalid bindings");
+    };
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].loc, (0, 0));
+    assert_eq!(
+        issues[0].problem,
+        BindingProblem::WrongKind {
+            field: "params",
+            found: "a sampler",
+        }
+    );
+
+    assert!(matches!(
+        refl.create_bind_group_from(&device, 0, &Unknown { params: &buffer }),
+        Err(Error::BindingsError(BindingsError::NoSuchBinding {
+            field: "params",
+            target: BindingTarget::Name("missing"),
+        }))
+    ));
+
+    // set 1 is left unbound
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl).unwrap();
+    let partial = pipeline.bind_with(&device, &ScaleParams { params: &buffer });
+    let Err(Error::InvalidBindings(issues)) = partial else {
+        panic!("expected invalid bindings");
+    };
+    let locs: Vec<_> = issues.iter().map(|issue| issue.loc).collect();
+    assert_eq!(locs, [(1, 0), (1, 1)]);
+    assert!(issues
+        .iter()
+        .all(|issue| issue.problem == BindingProblem::Missing));
+}
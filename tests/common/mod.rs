@@ -0,0 +1,19 @@
+//! Setup shared by the integration tests.
+#![allow(dead_code)]
+
+/// A device on the default adapter which panics on any error that isn't
+/// caught by kinnara. Keep the queue alive for as long as the device is used.
+pub fn set_up_device() -> (wgpu::Device, wgpu::Queue) {
+    set_up_device_with(&Default::default())
+}
+
+pub fn set_up_device_with(desc: &wgpu::DeviceDescriptor) -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::default();
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+        .expect("Failed to find an appropriate adapter");
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(desc, None)).expect("Failed to create device");
+
+    device.on_uncaptured_error(Box::new(|e| panic!("{e}")));
+    (device, queue)
+}
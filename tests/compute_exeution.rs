@@ -34,10 +34,7 @@ fn addition_next() -> Result<(), kinnara::Error> {
     let buffer = device.create_buffer_init(&BufferInitDescriptor {
         label: None,
         // TODO: reflector should be able to provide minimum viable flags
-        usage: BufferUsages::UNIFORM
-            | BufferUsages::MAP_READ
-            | BufferUsages::COPY_DST
-            | BufferUsages::STORAGE,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
         // TODO: be able to provide the data for this through a serde json like object
         contents: &data,
     });
@@ -46,7 +43,10 @@ fn addition_next() -> Result<(), kinnara::Error> {
     let wg_size = railed.work_group_size().unwrap();
 
     let bound_pipline = railed.bind(&device, |slot| {
-        if let BindSlot::StorageBuffer { loc: (0, 0), slot } = slot {
+        if let BindSlot::StorageBuffer {
+            loc: (0, 0), slot, ..
+        } = slot
+        {
             slot.borrow_mut().replace(buffer.as_entire_buffer_binding());
         }
     })?;
@@ -65,14 +65,7 @@ fn addition_next() -> Result<(), kinnara::Error> {
 
     queue.submit([encoder.finish()]);
 
-    let result_floats: Vec<_> = device.buffer_view(&buffer, |slice| {
-        // TODO:  bytemuck
-        slice
-            .unwrap()
-            .chunks_exact(4)
-            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-            .collect()
-    });
+    let result_floats = device.wait_for(device.read_buffer_as::<f32>(&queue, &buffer))?;
 
     for (i, &value) in result_floats.iter().enumerate() {
         assert_eq!(value, (i as f32 + add), "Mismatch at index {}", i);
@@ -81,7 +74,7 @@ fn addition_next() -> Result<(), kinnara::Error> {
     Ok(())
 }
 
-fn compute_stage(src: &str) -> wgpu::ShaderSource {
+fn compute_stage(src: &str) -> wgpu::ShaderSource<'_> {
     wgpu::ShaderSource::Glsl {
         shader: src.into(),
         stage: wgpu::naga::ShaderStage::Compute,
@@ -121,7 +114,6 @@ fn set_up_wgpu() -> (wgpu::Device, wgpu::Queue) {
                     label: None,
                     required_features: wgpu::Features::PUSH_CONSTANTS
                         | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
-                        | wgpu::Features::MAPPABLE_PRIMARY_BUFFERS
                         | wgpu::Features::CLEAR_TEXTURE,
                     required_limits,
                     memory_hints: wgpu::MemoryHints::Performance,
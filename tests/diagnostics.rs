@@ -0,0 +1,108 @@
+use kinnara::*;
+use wgpu::ShaderSource;
+
+fn compute_stage(src: &str) -> ShaderSource<'_> {
+    wgpu::ShaderSource::Glsl {
+        shader: src.into(),
+        stage: wgpu::naga::ShaderStage::Compute,
+        defines: Default::default(),
+    }
+}
+
+fn named(name: &str) -> PreprocessOptions<'_> {
+    PreprocessOptions {
+        file_name: Some(name),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn pragma_error_location() {
+    let src = "#version 450\n\n  #pragma sampler samp (filter=Linear, wrap=Sideways)\n";
+    let err = ComputeReflector::new_compute_with(compute_stage(src), &named("blur.glsl"))
+        .err()
+        .unwrap();
+
+    let [diagnostic] = err.diagnostics() else {
+        panic!("expected a single diagnostic, got {err}");
+    };
+
+    let loc = diagnostic.location.as_ref().unwrap();
+    assert_eq!(loc.file, "blur.glsl");
+    assert_eq!(loc.line, 3);
+    assert_eq!(loc.column, 3);
+    assert_eq!(
+        loc.line_text,
+        "  #pragma sampler samp (filter=Linear, wrap=Sideways)"
+    );
+    assert_eq!(&src[loc.span.clone()], loc.line_text.trim_start());
+
+    let rendered = diagnostic.render();
+    let expected = "\
+error: invalid wrap `Sideways`, expected one of `ClampToEdge`, `Repeat`, `MirrorRepeat` or `ClampToBorder`
+ --> blur.glsl:3:3
+  |
+3 |   #pragma sampler samp (filter=Linear, wrap=Sideways)
+  |   ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn malformed_pragma_points_at_options() {
+    let src = "#pragma uniform uni (dynamic_offset=true,, calculate_size=true)";
+    let err = ComputeReflector::new_compute(compute_stage(src))
+        .err()
+        .unwrap();
+    let loc = err.diagnostics()[0].location.clone().unwrap();
+
+    assert_eq!(loc.line, 1);
+    assert_eq!(loc.column, 21);
+}
+
+const GLSL_ERROR_SRC: &str = r"#version 450
+#pragma label 'Broken'
+#pragma uniform uni (dynamic_offset=true)
+#pragma sampler samp (filter=Linear)
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {
+    float x = undefined_value;
+}
+";
+
+#[test]
+fn glsl_compile_error_location() {
+    let src = GLSL_ERROR_SRC.replace('\'', "\"");
+    let err = ComputeReflector::new_compute_with(compute_stage(&src), &named("broken.glsl"))
+        .err()
+        .unwrap();
+
+    assert!(matches!(err, Error::CompilationError(_)));
+    let loc = err.diagnostics()[0].location.clone().unwrap();
+
+    assert_eq!(loc.file, "broken.glsl");
+    assert_eq!(loc.line, 8);
+    assert_eq!(loc.line_text, "    float x = undefined_value;");
+    assert_eq!(&src[loc.span.clone()], "undefined_value");
+}
+
+#[test]
+fn wgsl_compile_error_location() {
+    let src = r"#pragma sampler samp (filter=Linear)
+@group(0) @binding(0) var samp: sampler;
+
+@compute @workgroup_size(1)
+fn main() {
+    let x = ;
+}
+";
+    let err = ComputeReflector::new_compute_with(ShaderSource::Wgsl(src.into()), &named("k.wgsl"))
+        .err()
+        .unwrap();
+
+    let loc = err.diagnostics()[0].location.clone().unwrap();
+    assert_eq!(loc.file, "k.wgsl");
+    assert_eq!(loc.line, 6);
+    assert_eq!(loc.column, 13);
+    assert!(err.to_string().contains("--> k.wgsl:6:13"), "{err}");
+}
@@ -0,0 +1,414 @@
+use kinnara::*;
+use wgpu::{ShaderSource, ShaderStages};
+
+fn compute_stage(src: &str) -> ShaderSource<'_> {
+    wgpu::ShaderSource::Glsl {
+        shader: src.into(),
+        stage: wgpu::naga::ShaderStage::Compute,
+        defines: Default::default(),
+    }
+}
+
+const HINTED_SRC: &str = r"
+#version 450
+#pragma sampler samp (filter=Linear, wrap=ClampToEdge)
+#pragma sampler dumb (comparison=NotEqual, wrap=ClampToEdge)
+#pragma uniform uni_name (dynamic_offset=true, calculate_min_size=true)
+#pragma texture multisampled (sample_count=2)
+
+layout(set=0, binding=0) uniform sampler samp;
+layout(set=0, binding=1) uniform samplerShadow dumb;
+layout(set=0, binding=2) uniform texture2DMS multisampled;
+layout(set=0, binding=3) uniform sampler plain;
+layout(set=1, binding=0) uniform Uni {
+    vec4 color;
+    float scale;
+} uni_name;
+
+layout(local_size_x=16, local_size_y=16, local_size_z=1) in;
+void main() {}
+";
+
+#[test]
+fn pragma_hints_reflection() {
+    let refl = ComputeReflector::new_compute(compute_stage(HINTED_SRC)).unwrap();
+
+    let samp = refl.get_bind_group_layout_entry(0, 0).unwrap();
+    assert_eq!(
+        samp.ty,
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+    );
+
+    let dumb = refl.get_bind_group_layout_entry(0, 1).unwrap();
+    assert_eq!(
+        dumb.ty,
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison)
+    );
+
+    let multisampled = refl.get_bind_group_layout_entry(0, 2).unwrap();
+    assert!(matches!(
+        multisampled.ty,
+        wgpu::BindingType::Texture {
+            multisampled: true,
+            ..
+        }
+    ));
+
+    let plain = refl.get_bind_group_layout_entry(0, 3).unwrap();
+    assert_eq!(
+        plain.ty,
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering)
+    );
+
+    let uni = wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: std::num::NonZeroU64::new(32),
+        },
+        count: None,
+    };
+    assert_eq!(uni, refl.get_bind_group_layout_entry(1, 0).unwrap());
+}
+
+const DEFAULTS_SRC: &str = r"
+#version 450
+#pragma default uniform (calculate_size=true)
+#pragma default sampler (filter=Linear)
+#pragma sampler nearest (filter=Nearest)
+
+layout(set=0, binding=0) uniform sampler linear;
+layout(set=0, binding=1) uniform sampler nearest;
+layout(set=0, binding=2) uniform Block {
+    vec4 a;
+} block;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+
+#[test]
+fn default_pragmas() {
+    let refl = ComputeReflector::new_compute(compute_stage(DEFAULTS_SRC)).unwrap();
+
+    let linear = refl.get_bind_group_layout_entry(0, 0).unwrap();
+    let nearest = refl.get_bind_group_layout_entry(0, 1).unwrap();
+    let block = refl.get_bind_group_layout_entry(0, 2).unwrap();
+
+    assert_eq!(
+        linear.ty,
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+    );
+    assert_eq!(
+        nearest.ty,
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering)
+    );
+    assert_eq!(
+        block.ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: std::num::NonZeroU64::new(16),
+        }
+    );
+}
+
+const VAR_AND_LABEL_SRC: &str = r#"
+#version 450
+#pragma label "Base Label"
+#pragma float super.name : range(0..1) = 0.5
+#pragma color var = [1.0, 1.0, 0.0, 1.0]
+#pragma bool grand_parent.parent.bool_name = true
+#pragma uint count : range(1..64) // trailing comments are fine
+#pragma optimize(on)
+
+struct Parent { bool bool_name; };
+struct GrandParent { Parent parent; };
+
+layout(set=0, binding=0) uniform Super { float name; } super;
+layout(set=0, binding=1) uniform Globals {
+    vec4 var;
+    GrandParent grand_parent;
+    uint count;
+};
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+"#;
+
+#[test]
+fn pragmas_are_stripped() {
+    assert!(ComputeReflector::new_compute(compute_stage(VAR_AND_LABEL_SRC)).is_ok());
+}
+
+#[test]
+fn wgsl_pragmas_are_stripped() {
+    let src = r"
+#pragma sampler samp (filter=Linear)
+@group(0) @binding(0) var samp: sampler;
+
+@compute @workgroup_size(1)
+fn main() {}
+";
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(src.into())).unwrap();
+    let samp = refl.get_bind_group_layout_entry(0, 0).unwrap();
+    assert_eq!(
+        samp.ty,
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+    );
+}
+
+fn preprocessing_error(src: &str) -> String {
+    match ComputeReflector::new_compute(compute_stage(src)) {
+        Err(Error::PreprocessingError(e)) => e.to_string(),
+        Err(e) => panic!("expected a preprocessing error, got {e}"),
+        Ok(_) => panic!("expected a preprocessing error"),
+    }
+}
+
+#[test]
+fn malformed_pragmas() {
+    let err = preprocessing_error("#version 450\n#pragma sampler samp (filter=Cubic)\n");
+    assert!(err.contains("<source>:2:1"), "{err}");
+    assert!(err.contains("Cubic"), "{err}");
+
+    let err = preprocessing_error("#pragma uniform uni (dynamic_offset=yes)");
+    assert!(err.contains("dynamic_offset"), "{err}");
+
+    let err = preprocessing_error("#pragma float f : range(1..0)");
+    assert!(err.contains("greater"), "{err}");
+
+    let err = preprocessing_error("#pragma float f : range(0..1) = 2.0");
+    assert!(err.contains("outside"), "{err}");
+
+    let err = preprocessing_error("#pragma bool b = 1.0");
+    assert!(err.contains("true"), "{err}");
+
+    let err = preprocessing_error("#pragma label \"a\"\n#pragma label \"b\"");
+    assert!(err.contains("duplicate"), "{err}");
+
+    let err = preprocessing_error("#pragma sampler samp (filter=Linear");
+    assert!(err.contains("malformed"), "{err}");
+}
+
+const LABEL_SRC: &str = r#"
+#version 450
+#pragma label "Base Label"
+#pragma uniform uni (dynamic_offset=false, label="Uniforms")
+
+layout(set=0, binding=0) uniform Uni { vec4 a; } uni;
+layout(set=1, binding=0) uniform sampler samp;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+"#;
+
+#[test]
+fn label_pragma() {
+    let refl = ComputeReflector::new_compute(compute_stage(LABEL_SRC)).unwrap();
+
+    assert_eq!(refl.label(), Some("Base Label"));
+    assert_eq!(
+        refl.derived_label("pipeline").as_deref(),
+        Some("Base Label/pipeline")
+    );
+    assert_eq!(
+        refl.get_bind_group_layout_descriptor(0).label,
+        Some("Base Label/set0")
+    );
+    assert_eq!(
+        refl.get_bind_group_layout_descriptor(1).label,
+        Some("Base Label/set1")
+    );
+    assert_eq!(
+        refl.binding_label(0, 0).as_deref(),
+        Some("Base Label/Uniforms")
+    );
+    assert_eq!(refl.binding_label(1, 0).as_deref(), Some("Base Label/samp"));
+    assert!(refl.binding_label(1, 1).is_none());
+}
+
+#[test]
+fn unlabeled_shader() {
+    let refl = ComputeReflector::new_compute(compute_stage(DEFAULTS_SRC)).unwrap();
+
+    assert!(refl.label().is_none());
+    assert!(refl.derived_label("pass").is_none());
+    assert!(refl.get_bind_group_layout_descriptor(0).label.is_none());
+    assert_eq!(refl.binding_label(0, 0).as_deref(), Some("linear"));
+}
+
+const TEXTURE_HINT_SRC: &str = r"
+#version 450
+#pragma texture unfilterable (filterable=false)
+#pragma texture msaa (sample_count=4)
+#pragma texture shadow_map (sample_type=depth)
+#pragma texture as_float (sample_type=float, filterable=false)
+
+layout(set=0, binding=0) uniform texture2D unfilterable;
+layout(set=0, binding=1) uniform texture2DMS msaa;
+layout(set=0, binding=2) uniform texture2D shadow_map;
+layout(set=0, binding=3) uniform texture2D filtered;
+layout(set=0, binding=4) uniform utexture2D counts;
+layout(set=0, binding=5) uniform textureCube as_float;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+
+#[test]
+fn texture_hints() {
+    let refl = ComputeReflector::new_compute(compute_stage(TEXTURE_HINT_SRC)).unwrap();
+
+    let texture = |sample_type, multisampled, view_dimension| wgpu::BindingType::Texture {
+        sample_type,
+        view_dimension,
+        multisampled,
+    };
+
+    let unfilterable = wgpu::TextureSampleType::Float { filterable: false };
+    let d2 = wgpu::TextureViewDimension::D2;
+    let expected = [
+        texture(unfilterable, false, d2),
+        texture(unfilterable, true, d2),
+        texture(wgpu::TextureSampleType::Depth, false, d2),
+        texture(
+            wgpu::TextureSampleType::Float { filterable: true },
+            false,
+            d2,
+        ),
+        texture(wgpu::TextureSampleType::Uint, false, d2),
+        texture(unfilterable, false, wgpu::TextureViewDimension::Cube),
+    ];
+
+    for (binding, ty) in expected.into_iter().enumerate() {
+        let entry = refl.get_bind_group_layout_entry(0, binding as u32).unwrap();
+        assert_eq!(entry.ty, ty, "binding {binding}");
+    }
+}
+
+#[test]
+fn sample_count_needs_multisampled_texture() {
+    let src = r"
+#version 450
+#pragma texture msaa (sample_count=4)
+layout(set=0, binding=0) uniform texture2D msaa;
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+    let err = ComputeReflector::new_compute(compute_stage(src))
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::BindGroupError(_)), "{err}");
+    assert!(err.to_string().contains("sample count hint 4"), "{err}");
+}
+
+#[test]
+fn default_texture_hint() {
+    let src = r"
+#version 450
+#pragma default texture (filterable=false)
+layout(set=0, binding=0) uniform texture2D tex;
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+    let refl = ComputeReflector::new_compute(compute_stage(src)).unwrap();
+    let entry = refl.get_bind_group_layout_entry(0, 0).unwrap();
+    assert!(matches!(
+        entry.ty,
+        wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            ..
+        }
+    ));
+}
+
+#[test]
+fn incompatible_sample_type_hint() {
+    let src = r"
+#version 450
+#pragma texture tex (sample_type=uint)
+layout(set=0, binding=0) uniform texture2D tex;
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+    let res = ComputeReflector::new_compute(compute_stage(src));
+    assert!(matches!(res, Err(Error::BindGroupError(_))));
+
+    let err = preprocessing_error("#pragma texture tex (sample_type=half)");
+    assert!(err.contains("half"), "{err}");
+}
+
+const WGSL_COMMENT_SRC: &str = r#"
+// @kinnara label "Wgsl"
+// @kinnara sampler samp (filter=Linear, wrap=ClampToEdge)
+// @kinnara uniform uni (dynamic_offset=true, calculate_size=true)
+// @kinnara texture tex (filterable=false)
+// @kinnara float uni.scale : range(0..2) = 1.0 // trailing comments are fine
+// plain comments mentioning @kinnara are left alone
+
+struct Uni {
+    color: vec4<f32>,
+    scale: f32,
+}
+
+@group(0) @binding(0) var samp: sampler;
+@group(0) @binding(1) var tex: texture_2d<f32>;
+@group(1) @binding(0) var<uniform> uni: Uni;
+
+@compute @workgroup_size(1)
+fn main() {}
+"#;
+
+#[test]
+fn wgsl_comment_directives() {
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(WGSL_COMMENT_SRC.into())).unwrap();
+
+    assert_eq!(refl.label(), Some("Wgsl"));
+    assert_eq!(
+        refl.get_bind_group_layout_entry(0, 0).unwrap().ty,
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+    );
+    assert!(matches!(
+        refl.get_bind_group_layout_entry(0, 1).unwrap().ty,
+        wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            ..
+        }
+    ));
+    assert_eq!(
+        refl.get_bind_group_layout_entry(1, 0).unwrap().ty,
+        wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: std::num::NonZeroU64::new(32),
+        }
+    );
+
+    let [scale] = refl.parameters() else {
+        panic!("expected a single parameter");
+    };
+    assert_eq!(scale.path, "uni.scale");
+    assert_eq!(scale.offset, 16);
+}
+
+#[test]
+fn malformed_comment_directives() {
+    let wgsl_error = |src: &str| {
+        ComputeReflector::new_compute(ShaderSource::Wgsl(src.into()))
+            .err()
+            .unwrap()
+            .to_string()
+    };
+
+    let err = wgsl_error("// @kinnara sampler samp (filter=Cubic)");
+    assert!(err.contains("Cubic"), "{err}");
+
+    let err = wgsl_error("// @kinnara optimize(on)");
+    assert!(
+        err.contains("unknown kinnara directive `optimize`"),
+        "{err}"
+    );
+}
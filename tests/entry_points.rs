@@ -0,0 +1,155 @@
+mod common;
+
+use kinnara::*;
+use wgpu::ShaderSource;
+
+use common::set_up_device;
+
+const KERNELS_SRC: &str = r"
+struct Params { len: u32 }
+struct Offset { value: u32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(1) @binding(0) var<storage, read> input: array<f32>;
+@group(1) @binding(1) var<storage, read_write> sums: array<f32>;
+@group(1) @binding(2) var<storage, read_write> prefix: array<f32>;
+@group(2) @binding(0) var<storage, read_write> scattered: array<f32>;
+var<push_constant> offset: Offset;
+
+@compute @workgroup_size(64)
+fn reduce(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.len {
+        sums[0] += input[id.x];
+    }
+}
+
+@compute @workgroup_size(64)
+fn scan(@builtin(global_invocation_id) id: vec3<u32>) {
+    prefix[id.x] = input[id.x];
+}
+
+@compute @workgroup_size(64)
+fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+    scattered[id.x + offset.value] = 1.0;
+}
+";
+
+fn bindings(refl: &ComputeReflector, entry_point: &str) -> Vec<Vec<u32>> {
+    (0..refl.set_count_for(entry_point).unwrap())
+        .map(|set| {
+            refl.bind_group_layout_entries_for(entry_point, set as u32)
+                .unwrap()
+                .iter()
+                .map(|entry| entry.binding)
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn per_entry_point_layouts() {
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(KERNELS_SRC.into())).unwrap();
+
+    assert_eq!(bindings(&refl, "reduce"), [vec![0], vec![0, 1]]);
+    assert_eq!(bindings(&refl, "scan"), [vec![], vec![0, 2]]);
+    assert_eq!(bindings(&refl, "scatter"), [vec![], vec![], vec![0]]);
+
+    assert!(refl.push_constant_range_for("reduce").unwrap().is_none());
+    assert_eq!(
+        refl.push_constant_range_for("scatter").unwrap().unwrap()[0].range,
+        0..4
+    );
+
+    assert!(matches!(
+        refl.set_count_for("missing"),
+        Err(Error::BindGroupError(_))
+    ));
+}
+
+#[test]
+fn shared_sets() {
+    let mut refl = ComputeReflector::new_compute(ShaderSource::Wgsl(KERNELS_SRC.into())).unwrap();
+    refl.share_set(0);
+    refl.share_set(1);
+
+    assert_eq!(bindings(&refl, "scan"), [vec![0], vec![0, 1, 2]]);
+    assert_eq!(
+        bindings(&refl, "scatter"),
+        [vec![0], vec![0, 1, 2], vec![0]]
+    );
+}
+
+const GPU_KERNELS_SRC: &str = r"
+struct Params { len: u32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(1) @binding(0) var<storage, read_write> a: array<f32>;
+@group(1) @binding(1) var<storage, read_write> b: array<f32>;
+
+@compute @workgroup_size(1)
+fn fill_a(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.len { a[id.x] = 1.0; }
+}
+
+@compute @workgroup_size(1)
+fn fill_b(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.len { b[id.x] = 2.0; }
+}
+";
+
+#[test]
+fn entry_point_pipelines() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let mut refl = ComputeReflector::new_compute(ShaderSource::Wgsl(GPU_KERNELS_SRC.into()))?;
+    refl.share_set(0);
+
+    let buffer = |usage| {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 64,
+            usage,
+            mapped_at_creation: false,
+        })
+    };
+    let params = buffer(wgpu::BufferUsages::UNIFORM);
+    let a = buffer(wgpu::BufferUsages::STORAGE);
+
+    // a single bind group for the shared set works with both pipelines
+    let shared = refl.create_bind_group(&device, 0, |slot| {
+        if let BindSlot::UniformBuffer { slot, .. } = slot {
+            slot.borrow_mut().replace(params.as_entire_buffer_binding());
+        }
+    })?;
+
+    let fill_a = UnboundComputePipeline::new(&device, "fill_a", Default::default(), refl.clone())?;
+    let fill_b = UnboundComputePipeline::new(&device, "fill_b", Default::default(), refl)?;
+    assert!(fill_b.bind(&device, |_| {}).is_err());
+
+    // only the bindings `fill_a` uses are requested
+    let bound = fill_a.bind(&device, |slot| match slot {
+        BindSlot::UniformBuffer {
+            loc: (0, 0), slot, ..
+        } => {
+            slot.borrow_mut().replace(params.as_entire_buffer_binding());
+        }
+        BindSlot::StorageBuffer {
+            loc: (1, 0), slot, ..
+        } => {
+            slot.borrow_mut().replace(a.as_entire_buffer_binding());
+        }
+        other => panic!("unexpected slot {other:?}"),
+    })?;
+
+    let (pipeline, groups) = bound.derail();
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut pass = encoder.begin_compute_pass(&Default::default());
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &shared, &[]);
+        pass.set_bind_group(1, &groups[1], &[]);
+        pass.dispatch_workgroups(1, 1, 1);
+    }
+    queue.submit([encoder.finish()]);
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use kinnara::*;
+use wgpu::ShaderSource;
+
+fn compute_stage(src: &str) -> ShaderSource<'_> {
+    wgpu::ShaderSource::Glsl {
+        shader: src.into(),
+        stage: wgpu::naga::ShaderStage::Compute,
+        defines: Default::default(),
+    }
+}
+
+fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+const GLSL_SRC: &str = r"
+#version 450
+#pragma float super.name : range(0..1) = 0.5
+
+struct Light {
+    vec3 pos;
+    float radius;
+};
+
+layout(set=0, binding=0) uniform Super {
+    float name;
+    float arr[3];
+    vec3 dir;
+    float after;
+    mat3 rot;
+    Light lights[2];
+} super;
+
+layout(set=0, binding=1) buffer Data {
+    float arr[3];
+    uint count;
+    vec4 values[];
+} data;
+
+layout(push_constant) uniform Push {
+    float scale;
+    int steps;
+    bool enabled;
+} pc;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+
+#[test]
+fn std140_uniform_layout() {
+    let refl = ComputeReflector::new_compute(compute_stage(GLSL_SRC)).unwrap();
+    let mut buf = refl.host_buffer(0, 0).unwrap();
+
+    assert_eq!(buf.as_bytes().len(), 160);
+
+    // parameter paths address the buffer directly
+    let param = &refl.parameters()[0];
+    buf.set(&param.path, 0.5).unwrap();
+    assert_eq!(f32_at(buf.as_bytes(), param.offset as usize), 0.5);
+    assert_eq!(buf.get("name").unwrap(), HostValue::F32(0.5));
+
+    // array elements are padded to 16 bytes
+    buf.set("arr", [1.0f32, 2.0, 3.0]).unwrap();
+    assert_eq!(f32_at(buf.as_bytes(), 32), 2.0);
+
+    // a float packs into the tail of a vec3
+    buf.set("dir", [0.0, 1.0, 0.0]).unwrap();
+    buf.set("after", 7.0).unwrap();
+    assert_eq!(f32_at(buf.as_bytes(), 76), 7.0);
+    assert_eq!(buf.get_as::<[f32; 3]>("dir").unwrap(), [0.0, 1.0, 0.0]);
+
+    // matrix columns are padded to 16 bytes
+    buf.set("rot", [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+        .unwrap();
+    assert_eq!(f32_at(buf.as_bytes(), 80 + 16 + 4), 1.0);
+    assert_eq!(buf.get_as::<Vec<f32>>("rot[2]").unwrap(), [0.0, 0.0, 1.0]);
+
+    buf.set("super.lights[1].radius", 3).unwrap();
+    assert_eq!(f32_at(buf.as_bytes(), 128 + 16 + 12), 3.0);
+}
+
+#[test]
+fn std430_runtime_array() {
+    let refl = ComputeReflector::new_compute(compute_stage(GLSL_SRC)).unwrap();
+    let mut buf = refl.host_buffer(0, 1).unwrap();
+
+    // tightly packed array, runtime array starts empty
+    assert_eq!(buf.as_bytes().len(), 16);
+    buf.set("data.arr[2]", 1.5).unwrap();
+    assert_eq!(f32_at(buf.as_bytes(), 8), 1.5);
+    buf.set("count", 3u32).unwrap();
+
+    assert!(matches!(
+        buf.set("values[0]", [0.0; 4]),
+        Err(HostTypeError::OutOfBounds { len: 0, .. })
+    ));
+
+    buf.set_runtime_len(3).unwrap();
+    assert_eq!(buf.as_bytes().len(), 16 + 3 * 16);
+    buf.set("values[2]", [1.0, 2.0, 3.0, 4.0]).unwrap();
+    assert_eq!(f32_at(buf.as_bytes(), 16 + 32 + 12), 4.0);
+    assert_eq!(buf.get_as::<Vec<[f32; 4]>>("values").unwrap().len(), 3);
+}
+
+#[test]
+fn push_constant_buffer() {
+    let refl = ComputeReflector::new_compute(compute_stage(GLSL_SRC)).unwrap();
+    let mut buf = refl.push_constant_buffer().unwrap();
+
+    buf.set("pc.steps", -4).unwrap();
+    buf.set("enabled", true).unwrap();
+    buf.set("scale", 2.0f32).unwrap();
+
+    assert_eq!(buf.get_as::<i32>("steps").unwrap(), -4);
+    assert_eq!(&buf.as_bytes()[8..12], &1u32.to_le_bytes());
+    assert_eq!(
+        buf.get("").unwrap(),
+        HostValue::Struct(vec![
+            ("scale".into(), HostValue::F32(2.0)),
+            ("steps".into(), HostValue::I32(-4)),
+            ("enabled".into(), HostValue::Bool(true)),
+        ])
+    );
+}
+
+#[test]
+fn wgsl_layout() {
+    let src = r"
+struct Params {
+    a: vec3<f32>,
+    b: f32,
+    c: vec2<f32>,
+    m: mat2x2<f32>,
+    n: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+@compute @workgroup_size(1)
+fn main() {}
+";
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(src.into())).unwrap();
+    let mut buf = refl.host_buffer(0, 0).unwrap();
+
+    let HostType::Struct { members, size } = buf.ty() else {
+        panic!("expected a struct");
+    };
+    let offsets: Vec<_> = members.iter().map(|m| m.offset).collect();
+    assert_eq!(offsets, [0, 12, 16, 24, 40]);
+    assert_eq!(*size, 48);
+
+    buf.set("params.m[1]", [5.0, 6.0]).unwrap();
+    assert_eq!(f32_at(buf.as_bytes(), 24 + 8 + 4), 6.0);
+}
+
+#[test]
+fn host_buffer_errors() {
+    let refl = ComputeReflector::new_compute(compute_stage(GLSL_SRC)).unwrap();
+    let mut buf = refl.host_buffer(0, 0).unwrap();
+
+    assert!(matches!(
+        buf.set("missing", 1.0),
+        Err(HostTypeError::NoSuchField(_))
+    ));
+    assert!(matches!(
+        buf.set("name", true),
+        Err(HostTypeError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        buf.set("dir", [1.0, 2.0]),
+        Err(HostTypeError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        buf.set("arr[3]", 1.0),
+        Err(HostTypeError::OutOfBounds {
+            index: 3,
+            len: 3,
+            ..
+        })
+    ));
+    assert!(matches!(
+        buf.set("arr[x]", 1.0),
+        Err(HostTypeError::MalformedPath(_))
+    ));
+    assert!(matches!(
+        buf.get_as::<u32>("name"),
+        Err(HostTypeError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        buf.set_runtime_len(2),
+        Err(HostTypeError::NoRuntimeArray)
+    ));
+
+    let mut pc = refl.push_constant_buffer().unwrap();
+    assert!(pc.set("steps", u32::MAX).is_err());
+
+    assert!(matches!(
+        refl.host_buffer(3, 0),
+        Err(HostTypeError::NoSuchBuffer(3, 0))
+    ));
+}
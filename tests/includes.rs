@@ -0,0 +1,165 @@
+use kinnara::*;
+use wgpu::ShaderSource;
+
+fn compute_stage(src: &str) -> ShaderSource<'_> {
+    wgpu::ShaderSource::Glsl {
+        shader: src.into(),
+        stage: wgpu::naga::ShaderStage::Compute,
+        defines: Default::default(),
+    }
+}
+
+fn with_resolver(resolver: &dyn IncludeResolver) -> PreprocessOptions<'_> {
+    PreprocessOptions {
+        file_name: Some("main.glsl"),
+        include_resolver: Some(resolver),
+    }
+}
+
+const MAIN_SRC: &str = r#"#version 450
+#include "noise.glsl"
+#include <hash.glsl>
+
+layout(set=0, binding=0) uniform Seed { uint value; } seed;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {
+    float n = noise(hash(seed.value));
+}
+"#;
+
+#[test]
+fn filesystem_includes() {
+    let resolver = FileSystemResolver::new([concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/test_files/include"
+    )]);
+    let refl =
+        ComputeReflector::new_compute_with(compute_stage(MAIN_SRC), &with_resolver(&resolver))
+            .unwrap();
+
+    // pragmas in included files apply to the including shader
+    let seed = refl.get_bind_group_layout_entry(0, 0).unwrap();
+    assert!(matches!(
+        seed.ty,
+        wgpu::BindingType::Buffer {
+            has_dynamic_offset: true,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn memory_includes() {
+    let resolver = MemoryResolver::new()
+        .with_file("noise.glsl", include_str!("test_files/include/noise.glsl"))
+        .with_file("hash.glsl", include_str!("test_files/include/hash.glsl"));
+
+    let res =
+        ComputeReflector::new_compute_with(compute_stage(MAIN_SRC), &with_resolver(&resolver));
+    assert!(res.is_ok(), "{}", res.err().unwrap());
+}
+
+#[test]
+fn wgsl_import() {
+    let resolver = MemoryResolver::new().with_file(
+        "util",
+        "#pragma sampler samp (filter=Linear)\nfn double(x: f32) -> f32 { return x * 2.0; }\n",
+    );
+    let src = r"#import <util>
+@group(0) @binding(0) var samp: sampler;
+
+@compute @workgroup_size(1)
+fn main() {
+    let x = double(1.0);
+}
+";
+    let refl = ComputeReflector::new_compute_with(
+        ShaderSource::Wgsl(src.into()),
+        &with_resolver(&resolver),
+    )
+    .unwrap();
+    assert_eq!(
+        refl.get_bind_group_layout_entry(0, 0).unwrap().ty,
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+    );
+}
+
+#[test]
+fn include_cycle() {
+    let resolver = MemoryResolver::new()
+        .with_file("a.glsl", "#include \"b.glsl\"\n")
+        .with_file("b.glsl", "\n#include \"a.glsl\"\n");
+
+    let err = ComputeReflector::new_compute_with(
+        compute_stage("#include \"a.glsl\"\n"),
+        &with_resolver(&resolver),
+    )
+    .err()
+    .unwrap();
+
+    assert!(matches!(
+        err,
+        Error::PreprocessingError(PreprocessingError::IncludeError(_))
+    ));
+    let diagnostic = &err.diagnostics()[0];
+    assert!(
+        diagnostic
+            .message
+            .contains("`a.glsl` -> `b.glsl` -> `a.glsl`"),
+        "{diagnostic}"
+    );
+
+    let loc = diagnostic.location.as_ref().unwrap();
+    assert_eq!((loc.file.as_str(), loc.line), ("b.glsl", 2));
+}
+
+#[test]
+fn missing_include() {
+    let err = ComputeReflector::new_compute_with(
+        compute_stage("#version 450\n#include \"missing.glsl\"\n"),
+        &with_resolver(&MemoryResolver::new()),
+    )
+    .err()
+    .unwrap();
+
+    let loc = err.diagnostics()[0].location.clone().unwrap();
+    assert_eq!((loc.file.as_str(), loc.line), ("main.glsl", 2));
+
+    let res = ComputeReflector::new_compute(compute_stage("#include \"missing.glsl\"\n"));
+    assert!(res.unwrap_err().to_string().contains("resolver"));
+}
+
+#[test]
+fn errors_point_into_included_files() {
+    let resolver = MemoryResolver::new()
+        .with_file(
+            "bad_pragma.glsl",
+            "\n\n#pragma sampler samp (filter=Cubic)\n",
+        )
+        .with_file(
+            "bad_code.glsl",
+            "float broken() {\n    return undefined_value;\n}\n",
+        );
+
+    let err = ComputeReflector::new_compute_with(
+        compute_stage("#version 450\n#include \"bad_pragma.glsl\"\n"),
+        &with_resolver(&resolver),
+    )
+    .err()
+    .unwrap();
+    let loc = err.diagnostics()[0].location.clone().unwrap();
+    assert_eq!((loc.file.as_str(), loc.line), ("bad_pragma.glsl", 3));
+
+    let src = r#"#version 450
+#include "bad_code.glsl"
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+"#;
+    let err = ComputeReflector::new_compute_with(compute_stage(src), &with_resolver(&resolver))
+        .err()
+        .unwrap();
+    let loc = err.diagnostics()[0].location.clone().unwrap();
+    assert_eq!((loc.file.as_str(), loc.line), ("bad_code.glsl", 2));
+    assert_eq!(loc.line_text, "    return undefined_value;");
+}
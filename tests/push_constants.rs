@@ -0,0 +1,264 @@
+mod common;
+
+use kinnara::*;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{BufferUsages, PushConstantRange, ShaderSource, ShaderStages};
+
+use common::set_up_device_with;
+
+const SPLIT_SRC: &str = r"
+struct Constants {
+    transform: mat4x4<f32>,
+    tint: vec4<f32>,
+}
+
+var<push_constant> pc: Constants;
+
+@vertex
+fn vs_main(@location(0) position: vec4<f32>) -> @builtin(position) vec4<f32> {
+    return pc.transform * position;
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return pc.tint;
+}
+";
+
+fn ranges(refl: &RenderReflector) -> Vec<(ShaderStages, std::ops::Range<u32>)> {
+    refl.push_constant_range()
+        .unwrap()
+        .iter()
+        .map(|range| (range.stages, range.range.clone()))
+        .collect()
+}
+
+#[test]
+fn split_by_stage() {
+    let refl = RenderReflector::new_render_module(ShaderSource::Wgsl(SPLIT_SRC.into())).unwrap();
+    assert_eq!(
+        ranges(&refl),
+        [
+            (ShaderStages::VERTEX, 0..64),
+            (ShaderStages::FRAGMENT, 64..80),
+        ]
+    );
+
+    // a member read by both stages puts them in one range
+    let shared = SPLIT_SRC.replace(
+        "return pc.transform * position;",
+        "return pc.tint * position;",
+    );
+    let refl = RenderReflector::new_render_module(ShaderSource::Wgsl(shared.into())).unwrap();
+    assert_eq!(ranges(&refl), [(ShaderStages::VERTEX_FRAGMENT, 0..80)]);
+
+    // push constants no entry point reads stay visible to every stage
+    let unused = SPLIT_SRC
+        .replace("return pc.transform * position;", "return position;")
+        .replace("return pc.tint;", "return vec4<f32>(1.0);");
+    let refl = RenderReflector::new_render_module(ShaderSource::Wgsl(unused.into())).unwrap();
+    assert_eq!(ranges(&refl), [(ShaderStages::VERTEX_FRAGMENT, 0..80)]);
+}
+
+const BLOCK: &str = "layout(push_constant) uniform Constants { mat4 transform; vec4 tint; } pc;";
+
+#[test]
+fn split_across_glsl_stages() {
+    let vert = format!(
+        "#version 450\n{BLOCK}\nlayout(location = 0) in vec4 position;\n\
+         void main() {{ gl_Position = pc.transform * position; }}"
+    );
+    let frag = format!(
+        "#version 450\n{BLOCK}\nlayout(location = 0) out vec4 color;\n\
+         void main() {{ color = pc.tint; }}"
+    );
+    let glsl = |src: &str, stage| ShaderSource::Glsl {
+        shader: src.to_owned().into(),
+        stage,
+        defines: Default::default(),
+    };
+
+    let refl = RenderReflector::new_render(
+        glsl(&vert, wgpu::naga::ShaderStage::Vertex),
+        Some(glsl(&frag, wgpu::naga::ShaderStage::Fragment)),
+    )
+    .unwrap();
+    assert_eq!(
+        ranges(&refl),
+        [
+            (ShaderStages::VERTEX, 0..64),
+            (ShaderStages::FRAGMENT, 64..80),
+        ]
+    );
+    assert_eq!(refl.push_constant_buffer().unwrap().as_bytes().len(), 80);
+
+    let other = "#version 450\n\
+        layout(push_constant) uniform Constants { vec4 tint; mat4 transform; } pc;\n\
+        layout(location = 0) out vec4 color;\n\
+        void main() { color = pc.tint; }";
+    let refl = RenderReflector::new_render(
+        glsl(&vert, wgpu::naga::ShaderStage::Vertex),
+        Some(glsl(other, wgpu::naga::ShaderStage::Fragment)),
+    )
+    .unwrap();
+    assert!(matches!(
+        refl.push_constant_buffer(),
+        Err(HostTypeError::PushConstantMismatch)
+    ));
+}
+
+#[test]
+fn fill_from_block() {
+    let block: Vec<u8> = (0..80).collect();
+    let range = PushConstantRange {
+        stages: ShaderStages::FRAGMENT,
+        range: 64..80,
+    };
+
+    let slot = PassSlot::from(&range);
+    slot.fill_push_constants(&block);
+    assert_eq!(slot.push_const_slice(), Some((64, &block[64..80])));
+}
+
+const AFFINE_SRC: &str = r"
+struct Affine {
+    scale: f32,
+    bias: f32,
+}
+
+var<push_constant> affine: Affine;
+@group(0) @binding(0) var<storage, read_write> values: array<f32>;
+
+@compute @workgroup_size(1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    values[id.x] = values[id.x] * affine.scale + affine.bias;
+}
+";
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, PushConstant)]
+struct Affine {
+    scale: f32,
+    bias: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, PushConstant)]
+struct Swapped {
+    bias: f32,
+    scale: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, PushConstant)]
+struct Padded {
+    scale: f32,
+    bias: f32,
+    _pad: [f32; 2],
+}
+
+#[test]
+fn typed_push_constants() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(AFFINE_SRC.into()))?;
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl)?;
+
+    let values: Vec<u8> = [1.0f32, 2.0, 3.0, 4.0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let storage = device.create_buffer_init(&BufferInitDescriptor {
+        label: None,
+        contents: &values,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+    });
+
+    let bound = pipeline.bind(&device, |slot| {
+        if let BindSlot::StorageBuffer { slot, .. } = slot {
+            slot.borrow_mut()
+                .replace(storage.as_entire_buffer_binding());
+        }
+    })?;
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+
+    // the shader's members, in another order
+    let swapped = Swapped {
+        bias: 1.0,
+        scale: 2.0,
+    };
+    assert!(matches!(
+        bound.create_pass_with(&mut encoder, &swapped, |_| {}).err(),
+        Some(Error::PushConstantError(PushConstantError::OffsetMismatch { name, expected: 0, found: 4 }))
+            if name == "scale"
+    ));
+
+    let padded = Padded {
+        scale: 2.0,
+        bias: 1.0,
+        _pad: [0.0; 2],
+    };
+    assert!(matches!(
+        bound.create_pass_with(&mut encoder, &padded, |_| {}).err(),
+        Some(Error::PushConstantError(PushConstantError::SizeMismatch {
+            expected: 8,
+            found: 16
+        }))
+    ));
+
+    {
+        let affine = Affine {
+            scale: 2.0,
+            bias: 1.0,
+        };
+        let mut pass = bound.create_pass_with(&mut encoder, &affine, |_| {})?;
+        pass.dispatch_workgroups(4, 1, 1);
+    }
+    queue.submit([encoder.finish()]);
+
+    let values = device.wait_for(device.read_buffer_as::<f32>(&queue, &storage))?;
+    assert_eq!(values, [3.0, 5.0, 7.0, 9.0]);
+
+    Ok(())
+}
+
+#[test]
+fn short_push_constants() -> Result<(), Error> {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(AFFINE_SRC.into()))?;
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl)?;
+    let storage = device.create_buffer_init(&BufferInitDescriptor {
+        label: None,
+        contents: &[0; 16],
+        usage: BufferUsages::STORAGE,
+    });
+    let bound = pipeline.bind(&device, |slot| {
+        if let BindSlot::StorageBuffer { slot, .. } = slot {
+            slot.replace(Some(storage.as_entire_buffer_binding()));
+        }
+    })?;
+
+    let block = 2.0f32.to_le_bytes();
+    let mut encoder = device.create_command_encoder(&Default::default());
+    let err = bound
+        .create_pass(&mut encoder, |slot| slot.fill_push_constants(&block))
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Incomplete pass, missing:\n  8 bytes of push constants for ShaderStages(COMPUTE), 4 were given"
+    );
+
+    Ok(())
+}
+
+fn set_up_device() -> (wgpu::Device, wgpu::Queue) {
+    set_up_device_with(&wgpu::DeviceDescriptor {
+        required_features: wgpu::Features::PUSH_CONSTANTS,
+        required_limits: wgpu::Limits {
+            max_push_constant_size: 128,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
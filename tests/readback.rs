@@ -0,0 +1,188 @@
+mod common;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use kinnara::*;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::BufferUsages;
+
+use common::set_up_device;
+
+fn buffer(device: &wgpu::Device, contents: &[u8], usage: BufferUsages) -> wgpu::Buffer {
+    device.create_buffer_init(&BufferInitDescriptor {
+        label: None,
+        contents,
+        usage,
+    })
+}
+
+#[test]
+fn staged_readback() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let values: Vec<u32> = (0..64).collect();
+    let storage = buffer(
+        &device,
+        bytemuck::cast_slice(&values),
+        BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+    );
+
+    assert_eq!(
+        device.wait_for(device.read_buffer_as::<u32>(&queue, &storage))?,
+        values
+    );
+
+    // the pool hands its staging buffer to the next read
+    let pool = StagingPool::new();
+    assert_eq!(
+        device.wait_for(pool.read_as::<u32>(&device, &queue, &storage))?,
+        values
+    );
+    queue.write_buffer(&storage, 0, bytemuck::cast_slice(&[7u32; 64]));
+    assert_eq!(
+        device.wait_for(pool.read(&device, &queue, &storage))?,
+        [7, 0, 0, 0].repeat(64)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn mappable_readback() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let mappable = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 8,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    queue.write_buffer(&mappable, 0, bytemuck::cast_slice(&[1.5f32, -2.0]));
+    queue.submit([]);
+
+    assert_eq!(
+        device.wait_for(device.read_buffer_as::<f32>(&queue, &mappable))?,
+        [1.5, -2.0]
+    );
+    Ok(())
+}
+
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn polled_readback() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let values: Vec<u32> = (0..16).collect();
+    let storage = buffer(
+        &device,
+        bytemuck::cast_slice(&values),
+        BufferUsages::COPY_SRC,
+    );
+
+    // polling the readback never waits, the map callback wakes it once
+    // the device is polled
+    let flag = Arc::new(Flag(AtomicBool::new(false)));
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut readback = device.read_buffer(&queue, &storage);
+    let bytes = loop {
+        match Pin::new(&mut readback).poll(&mut cx) {
+            Poll::Ready(bytes) => break bytes?,
+            Poll::Pending => {
+                device.poll(wgpu::Maintain::Wait);
+                assert!(flag.0.swap(false, Ordering::SeqCst));
+            }
+        }
+    };
+    assert_eq!(bytes, bytemuck::cast_slice(&values));
+    Ok(())
+}
+
+#[test]
+fn dropped_readback() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let mappable = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 8,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    queue.write_buffer(&mappable, 0, bytemuck::cast_slice(&[1.5f32, -2.0]));
+    queue.submit([]);
+
+    // dropped once mapped, then with the map still in flight
+    let mapped = device.read_buffer(&queue, &mappable);
+    device.poll(wgpu::Maintain::Wait);
+    drop(mapped);
+    drop(device.read_buffer(&queue, &mappable));
+    assert_eq!(
+        device.wait_for(device.read_buffer_as::<f32>(&queue, &mappable))?,
+        [1.5, -2.0]
+    );
+
+    let values: Vec<u32> = (0..16).collect();
+    let storage = buffer(
+        &device,
+        bytemuck::cast_slice(&values),
+        BufferUsages::COPY_SRC,
+    );
+    let pool = StagingPool::new();
+    drop(pool.read(&device, &queue, &storage));
+    assert_eq!(
+        device.wait_for(pool.read_as::<u32>(&device, &queue, &storage))?,
+        values
+    );
+    Ok(())
+}
+
+#[test]
+fn empty_readback() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let empty = |usage| {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 0,
+            usage,
+            mapped_at_creation: false,
+        })
+    };
+
+    let staged = empty(BufferUsages::STORAGE | BufferUsages::COPY_SRC);
+    assert!(device
+        .wait_for(device.read_buffer(&queue, &staged))?
+        .is_empty());
+    assert!(device
+        .wait_for(StagingPool::new().read_as::<u32>(&device, &queue, &staged))?
+        .is_empty());
+
+    let mappable = empty(BufferUsages::MAP_READ | BufferUsages::COPY_DST);
+    assert!(device
+        .wait_for(device.read_buffer(&queue, &mappable))?
+        .is_empty());
+    Ok(())
+}
+
+#[test]
+fn readback_errors() {
+    let (device, queue) = set_up_device();
+
+    let uniform = buffer(&device, &[0; 16], BufferUsages::UNIFORM);
+    assert!(matches!(
+        device.wait_for(device.read_buffer(&queue, &uniform)),
+        Err(ReadbackError::NotReadable)
+    ));
+
+    let odd = buffer(&device, &[0; 12], BufferUsages::COPY_SRC);
+    assert!(matches!(
+        device.wait_for(device.read_buffer_as::<[u32; 2]>(&queue, &odd)),
+        Err(ReadbackError::ElementSize { len: 12, size: 8 })
+    ));
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use kinnara::*;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{BufferUsages, ShaderSource};
+
+use common::set_up_device;
+
+const QUAD_SRC: &str = r#"
+// @kinnara label "quad"
+// @kinnara vertex tint (buffer=1, step=Instance, format=Unorm8x4)
+
+struct Params { scale: f32 }
+
+struct VertexOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tint: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>, @location(1) tint: vec4<f32>) -> VertexOut {
+    return VertexOut(vec4<f32>(position * params.scale, 0.0, 1.0), tint);
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    return in.tint;
+}
+"#;
+
+const SIZE: u32 = 4;
+
+#[test]
+fn draw_indexed_quad() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let refl = RenderReflector::new_render_module(ShaderSource::Wgsl(QUAD_SRC.into()))?;
+
+    let format = wgpu::TextureFormat::Rgba8Unorm;
+    let pipeline = UnboundRenderPipeline::new(
+        &device,
+        "vs_main",
+        Some("fs_main"),
+        &[Some(format.into())],
+        &RenderState::default(),
+        Default::default(),
+        refl,
+    )?;
+
+    let params = device.create_buffer_init(&BufferInitDescriptor {
+        label: None,
+        contents: &2.0f32.to_le_bytes(),
+        usage: BufferUsages::UNIFORM,
+    });
+    let mut bound = pipeline.bind(&device, |slot| {
+        if let BindSlot::UniformBuffer {
+            loc: (0, 0), slot, ..
+        } = slot
+        {
+            slot.borrow_mut().replace(params.as_entire_buffer_binding());
+        }
+    })?;
+    assert!(matches!(
+        bound.rebind_set(&device, 3, |_| {}),
+        Err(Error::NoSuchSet(3))
+    ));
+
+    let corners: Vec<u8> = [[-0.5f32, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]]
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let vertices = device.create_buffer_init(&BufferInitDescriptor {
+        label: None,
+        contents: &corners,
+        usage: BufferUsages::VERTEX,
+    });
+    let tint = device.create_buffer_init(&BufferInitDescriptor {
+        label: None,
+        contents: &[255, 0, 255, 255],
+        usage: BufferUsages::VERTEX,
+    });
+    let indices: Vec<u8> = [0u16, 1, 2, 0, 2, 3]
+        .iter()
+        .flat_map(|i| i.to_le_bytes())
+        .collect();
+    let indices = device.create_buffer_init(&BufferInitDescriptor {
+        label: None,
+        contents: &indices,
+        usage: BufferUsages::INDEX,
+    });
+
+    let target = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = target.create_view(&Default::default());
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+
+    // every required slot is reported at once
+    let missing = bound.create_pass(&mut encoder, |_| {}).err().unwrap();
+    let attribute = |format, offset, shader_location| wgpu::VertexAttribute {
+        format,
+        offset,
+        shader_location,
+    };
+    assert!(matches!(
+        &missing,
+        Error::PassConstruction(slots) if *slots == [
+            MissingPassSlot::ColorAttachment {
+                location: 0,
+                name: None,
+                format,
+            },
+            MissingPassSlot::VertexBuffer {
+                slot: 0,
+                inputs: vec![(
+                    Some("position".to_owned()),
+                    attribute(wgpu::VertexFormat::Float32x2, 0, 0),
+                )],
+            },
+            MissingPassSlot::VertexBuffer {
+                slot: 1,
+                inputs: vec![(
+                    Some("tint".to_owned()),
+                    attribute(wgpu::VertexFormat::Unorm8x4, 0, 1),
+                )],
+            },
+        ]
+    ));
+    assert_eq!(
+        missing.to_string(),
+        "Incomplete pass, missing:\n  \
+         a Rgba8Unorm color attachment for location 0\n  \
+         vertex buffer 0 with `position` as Float32x2 at location 0\n  \
+         vertex buffer 1 with `tint` as Unorm8x4 at location 1"
+    );
+
+    {
+        let mut pass = bound.create_pass(&mut encoder, |slot| match slot {
+            PassSlot::ColorAttachment { attachment, .. } => {
+                attachment.replace(Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                }));
+            }
+            PassSlot::VertexBuffer { slot: 0, buffer } => {
+                buffer.replace(Some(vertices.slice(..)));
+            }
+            PassSlot::VertexBuffer { slot: 1, buffer } => {
+                buffer.replace(Some(tint.slice(..)));
+            }
+            PassSlot::IndexBuffer { buffer } => {
+                buffer.replace(Some((indices.slice(..), wgpu::IndexFormat::Uint16)));
+            }
+            _ => {}
+        })?;
+        pass.draw_indexed(0..6, 0, 0..1);
+    }
+
+    queue.submit([encoder.finish()]);
+
+    // the quad is scaled to cover the whole target
+    let pixels = device.wait_for(device.read_texture(&queue, &target))?;
+    for pixel in pixels.chunks(4) {
+        assert_eq!(pixel, [255, 0, 255, 255]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn incompatible_targets() {
+    let (device, _queue) = set_up_device();
+    let refl = RenderReflector::new_render_module(ShaderSource::Wgsl(QUAD_SRC.into())).unwrap();
+
+    let result = UnboundRenderPipeline::new(
+        &device,
+        "vs_main",
+        Some("fs_main"),
+        &[Some(wgpu::TextureFormat::R32Uint.into())],
+        &RenderState::default(),
+        Default::default(),
+        refl,
+    );
+    assert!(matches!(
+        result,
+        Err(Error::RenderError(RenderError::IncompatibleTarget {
+            location: 0,
+            ..
+        }))
+    ));
+}
@@ -0,0 +1,201 @@
+use kinnara::*;
+use wgpu::{ShaderSource, ShaderStages, VertexFormat, VertexStepMode};
+
+const VERT_SRC: &str = r"
+#version 450
+#pragma vertex color (format=Unorm8x4)
+#pragma vertex offset (buffer=1, step=Instance)
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+layout(location = 3) in vec2 offset;
+
+layout(set = 0, binding = 0) uniform Camera { mat4 view_proj; } camera;
+
+layout(location = 0) out vec2 v_uv;
+layout(location = 1) out vec4 v_color;
+
+void main() {
+    v_uv = uv;
+    v_color = color;
+    gl_Position = camera.view_proj * vec4(position.xy + offset, position.z, 1.0);
+}
+";
+
+const FRAG_SRC: &str = r"
+#version 450
+#pragma sampler samp (filter=Linear)
+
+layout(location = 0) in vec2 v_uv;
+layout(location = 1) in vec4 v_color;
+
+layout(set = 0, binding = 0) uniform Camera { mat4 view_proj; } camera;
+layout(set = 0, binding = 1) uniform texture2D tex;
+layout(set = 0, binding = 2) uniform sampler samp;
+
+layout(location = 0) out vec4 frag_color;
+layout(location = 2) out uint object_id;
+
+void main() {
+    frag_color = texture(sampler2D(tex, samp), v_uv) * v_color;
+    object_id = 7;
+}
+";
+
+fn glsl(src: &str, stage: wgpu::naga::ShaderStage) -> ShaderSource<'_> {
+    ShaderSource::Glsl {
+        shader: src.into(),
+        stage,
+        defines: Default::default(),
+    }
+}
+
+fn attributes(buffer: &VertexBuffer) -> Vec<(u32, VertexFormat, u64)> {
+    buffer
+        .attributes
+        .iter()
+        .map(|attr| (attr.shader_location, attr.format, attr.offset))
+        .collect()
+}
+
+#[test]
+fn glsl_stages() {
+    use wgpu::naga::ShaderStage;
+
+    let refl = RenderReflector::new_render(
+        glsl(VERT_SRC, ShaderStage::Vertex),
+        Some(glsl(FRAG_SRC, ShaderStage::Fragment)),
+    )
+    .unwrap();
+
+    let buffers = refl.vertex_buffers("main").unwrap();
+    assert_eq!(buffers.len(), 2);
+    assert_eq!(
+        attributes(&buffers[0]),
+        [
+            (0, VertexFormat::Float32x3, 0),
+            (1, VertexFormat::Float32x2, 12),
+            (2, VertexFormat::Unorm8x4, 20),
+        ]
+    );
+    assert_eq!(buffers[0].array_stride, 24);
+    assert_eq!(buffers[0].step_mode, VertexStepMode::Vertex);
+    assert_eq!(attributes(&buffers[1]), [(3, VertexFormat::Float32x2, 0)]);
+    assert_eq!(buffers[1].step_mode, VertexStepMode::Instance);
+
+    let targets = refl.color_targets("main").unwrap();
+    assert_eq!(targets.len(), 3);
+    let color = targets[0].as_ref().unwrap();
+    assert_eq!(color.name.as_deref(), Some("frag_color"));
+    assert!(color.accepts(wgpu::TextureFormat::Rgba8UnormSrgb));
+    assert!(!color.accepts(wgpu::TextureFormat::R32Uint));
+    assert!(targets[1].is_none());
+    assert!(targets[2]
+        .as_ref()
+        .unwrap()
+        .accepts(wgpu::TextureFormat::R32Uint));
+
+    let camera = refl.get_bind_group_layout_entry(0, 0).unwrap();
+    assert_eq!(camera.visibility, ShaderStages::VERTEX_FRAGMENT);
+    let tex = refl.get_bind_group_layout_entry(0, 1).unwrap();
+    assert_eq!(tex.visibility, ShaderStages::FRAGMENT);
+    assert!(refl.sampler_descriptor(0, 2).is_some());
+}
+
+const WGSL_SRC: &str = r"
+struct VertexIn {
+    @location(0) position: vec2<f32>,
+    @location(1) id: u32,
+}
+
+struct VertexOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) id: u32,
+}
+
+@group(0) @binding(0) var<uniform> scale: f32;
+@group(1) @binding(0) var<storage, read> palette: array<vec4<f32>>;
+
+@vertex
+fn vs_main(in: VertexIn, @builtin(vertex_index) index: u32) -> VertexOut {
+    return VertexOut(vec4<f32>(in.position * scale, 0.0, 1.0), in.id);
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    return palette[in.id];
+}
+";
+
+#[test]
+fn wgsl_module() {
+    let refl = RenderReflector::new_render_module(ShaderSource::Wgsl(WGSL_SRC.into())).unwrap();
+
+    assert_eq!(refl.vertex_entry_points().collect::<Vec<_>>(), ["vs_main"]);
+    assert_eq!(
+        refl.fragment_entry_points().collect::<Vec<_>>(),
+        ["fs_main"]
+    );
+
+    let layouts = refl.vertex_buffer_layouts("vs_main").unwrap();
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(layouts[0].array_stride, 12);
+    assert_eq!(layouts[0].attributes[1].format, VertexFormat::Uint32);
+
+    let targets = refl.color_targets("fs_main").unwrap();
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].as_ref().unwrap().components, 4);
+
+    assert_eq!(
+        refl.get_bind_group_layout_entry(0, 0).unwrap().visibility,
+        ShaderStages::VERTEX
+    );
+    assert_eq!(
+        refl.get_bind_group_layout_entry(1, 0).unwrap().visibility,
+        ShaderStages::FRAGMENT
+    );
+
+    assert!(matches!(
+        refl.color_targets("vs_main"),
+        Err(Error::RenderError(RenderError::NoSuchEntryPoint(..)))
+    ));
+}
+
+#[test]
+fn render_errors() {
+    use wgpu::naga::ShaderStage;
+
+    let mismatched = WGSL_SRC.replacen(
+        "struct VertexIn",
+        "// @kinnara vertex id (format=Unorm8x4)\nstruct VertexIn",
+        1,
+    );
+    assert!(matches!(
+        RenderReflector::new_render_module(ShaderSource::Wgsl(mismatched.into())),
+        Err(Error::RenderError(RenderError::VertexFormatMismatch { .. }))
+    ));
+
+    let unresolved = format!("// @kinnara vertex normal (buffer=1)\n{WGSL_SRC}");
+    assert!(matches!(
+        RenderReflector::new_render_module(ShaderSource::Wgsl(unresolved.into())),
+        Err(Error::RenderError(RenderError::UnresolvedVertexHint(name))) if name == "normal"
+    ));
+
+    // a storage buffer in one stage, a uniform buffer in the other
+    let conflicting = FRAG_SRC.replace("uniform Camera", "readonly buffer Camera");
+    assert!(matches!(
+        RenderReflector::new_render(
+            glsl(VERT_SRC, ShaderStage::Vertex),
+            Some(glsl(&conflicting, ShaderStage::Fragment)),
+        ),
+        Err(Error::BindGroupError(_))
+    ));
+
+    assert!(matches!(
+        RenderReflector::new_render(glsl(FRAG_SRC, ShaderStage::Fragment), None),
+        Err(Error::RenderError(RenderError::MissingStage(
+            ShaderStage::Vertex
+        )))
+    ));
+}
@@ -0,0 +1,306 @@
+mod common;
+
+use kinnara::*;
+use wgpu::{BufferUsages, ShaderSource, TextureUsages};
+
+use common::set_up_device;
+
+const CHECKS_SRC: &str = r#"
+// @kinnara uniform params (calculate_min_size=true)
+
+struct Params { scale: vec4<f32> }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> data: array<f32>;
+@group(0) @binding(2) var img: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(1)
+fn main() {
+    data[0] = params.scale.x;
+    textureStore(img, vec2<i32>(0), params.scale);
+}
+"#;
+
+fn buffer(device: &wgpu::Device, size: u64, usage: BufferUsages) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size,
+        usage,
+        mapped_at_creation: false,
+    })
+}
+
+fn texture(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    layers: u32,
+    usage: TextureUsages,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: layers,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage,
+        view_formats: &[],
+    })
+}
+
+/// Bind `uniform`, `storage` and `img` to the three bindings, returning
+/// the problem for each binding that was rejected.
+fn problems(
+    device: &wgpu::Device,
+    refl: &ComputeReflector,
+    uniform: wgpu::BufferBinding,
+    storage: wgpu::BufferBinding,
+    img: &DescribedView,
+) -> Vec<(u32, BindingProblem)> {
+    let err = refl
+        .create_bind_group(device, 0, |slot| match slot {
+            BindSlot::UniformBuffer { slot, .. } => {
+                slot.replace(Some(uniform.clone()));
+            }
+            BindSlot::StorageBuffer { slot, .. } => {
+                slot.replace(Some(storage.clone()));
+            }
+            BindSlot::Texture { .. } => slot.fill_view(img),
+            _ => {}
+        })
+        .err();
+
+    match err {
+        None => vec![],
+        Some(Error::InvalidBindings(issues)) => issues
+            .into_iter()
+            .map(|issue| (issue.loc.1, issue.problem))
+            .collect(),
+        Some(err) => panic!("unexpected error: {err}"),
+    }
+}
+
+#[test]
+fn valid_resources_bind() -> Result<(), Error> {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(CHECKS_SRC.into()))?;
+
+    let uniform = buffer(&device, 16, BufferUsages::UNIFORM);
+    let storage = buffer(&device, 64, BufferUsages::STORAGE);
+    let tex = texture(
+        &device,
+        wgpu::TextureFormat::Rgba8Unorm,
+        1,
+        TextureUsages::STORAGE_BINDING,
+    );
+    let img = DescribedView::new(&tex, &Default::default());
+
+    assert_eq!(
+        problems(
+            &device,
+            &refl,
+            uniform.as_entire_buffer_binding(),
+            storage.as_entire_buffer_binding(),
+            &img,
+        ),
+        []
+    );
+    Ok(())
+}
+
+#[test]
+fn buffer_problems() -> Result<(), Error> {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(CHECKS_SRC.into()))?;
+    let tex = texture(
+        &device,
+        wgpu::TextureFormat::Rgba8Unorm,
+        1,
+        TextureUsages::STORAGE_BINDING,
+    );
+    let img = DescribedView::new(&tex, &Default::default());
+
+    // a uniform buffer bound as storage, and a uniform smaller than `Params`
+    let uniform = buffer(&device, 256, BufferUsages::UNIFORM);
+    assert_eq!(
+        problems(
+            &device,
+            &refl,
+            wgpu::BufferBinding {
+                buffer: &uniform,
+                offset: 0,
+                size: wgpu::BufferSize::new(8),
+            },
+            uniform.as_entire_buffer_binding(),
+            &img,
+        ),
+        [
+            (0, BindingProblem::TooSmall { size: 8, min: 16 }),
+            (
+                1,
+                BindingProblem::BufferUsage {
+                    missing: BufferUsages::STORAGE
+                }
+            ),
+        ]
+    );
+
+    let storage = buffer(&device, 64, BufferUsages::STORAGE);
+    let alignment = device.limits().min_storage_buffer_offset_alignment;
+    let found = problems(
+        &device,
+        &refl,
+        uniform.as_entire_buffer_binding(),
+        wgpu::BufferBinding {
+            buffer: &storage,
+            offset: 4,
+            size: None,
+        },
+        &img,
+    );
+    assert_eq!(
+        found,
+        [(
+            1,
+            BindingProblem::UnalignedOffset {
+                offset: 4,
+                alignment
+            }
+        )]
+    );
+
+    assert_eq!(
+        problems(
+            &device,
+            &refl,
+            uniform.as_entire_buffer_binding(),
+            wgpu::BufferBinding {
+                buffer: &storage,
+                offset: 0,
+                size: wgpu::BufferSize::new(128),
+            },
+            &img,
+        ),
+        [(
+            1,
+            BindingProblem::OutOfBounds {
+                offset: 0,
+                size: 128,
+                buffer_size: 64
+            }
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn view_problems() -> Result<(), Error> {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(CHECKS_SRC.into()))?;
+    let uniform = buffer(&device, 16, BufferUsages::UNIFORM);
+    let storage = buffer(&device, 64, BufferUsages::STORAGE);
+    let bind = |img: &DescribedView| {
+        problems(
+            &device,
+            &refl,
+            uniform.as_entire_buffer_binding(),
+            storage.as_entire_buffer_binding(),
+            img,
+        )
+    };
+
+    let sampled = texture(
+        &device,
+        wgpu::TextureFormat::Rgba8Unorm,
+        1,
+        TextureUsages::TEXTURE_BINDING,
+    );
+    assert_eq!(
+        bind(&DescribedView::new(&sampled, &Default::default())),
+        [(
+            2,
+            BindingProblem::TextureUsage {
+                missing: TextureUsages::STORAGE_BINDING
+            }
+        )]
+    );
+
+    let layered = texture(
+        &device,
+        wgpu::TextureFormat::Rgba8Unorm,
+        3,
+        TextureUsages::STORAGE_BINDING,
+    );
+    assert_eq!(
+        bind(&DescribedView::new(&layered, &Default::default())),
+        [(
+            2,
+            BindingProblem::ViewDimension {
+                found: wgpu::TextureViewDimension::D2Array
+            }
+        )]
+    );
+
+    let float = texture(
+        &device,
+        wgpu::TextureFormat::R32Float,
+        1,
+        TextureUsages::STORAGE_BINDING,
+    );
+    assert_eq!(
+        bind(&DescribedView::new(&float, &Default::default())),
+        [(
+            2,
+            BindingProblem::Format {
+                found: wgpu::TextureFormat::R32Float
+            }
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn problem_names_binding() -> Result<(), Error> {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(CHECKS_SRC.into()))?;
+    let uniform = buffer(&device, 16, BufferUsages::UNIFORM);
+    let storage = buffer(&device, 64, BufferUsages::STORAGE);
+    let tex = texture(
+        &device,
+        wgpu::TextureFormat::Rgba8Unorm,
+        1,
+        TextureUsages::STORAGE_BINDING,
+    );
+    let img = DescribedView::new(&tex, &Default::default());
+
+    let err = refl
+        .create_bind_group(&device, 0, |slot| match slot {
+            BindSlot::UniformBuffer { slot, .. } => {
+                slot.replace(Some(uniform.as_entire_buffer_binding()));
+            }
+            BindSlot::StorageBuffer { slot, .. } => {
+                slot.replace(Some(wgpu::BufferBinding {
+                    buffer: &storage,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(6),
+                }));
+            }
+            BindSlot::Texture { .. } => slot.fill_view(&img),
+            _ => {}
+        })
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        err.to_string(),
+        "Invalid bindings:\n  set 0 binding 1 (`data`) expects a storage buffer, \
+         6 bytes were bound, not a multiple of 4"
+    );
+    Ok(())
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use kinnara::*;
+use wgpu::ShaderSource;
+
+use common::set_up_device;
+
+fn compute_stage(src: &str) -> ShaderSource<'_> {
+    wgpu::ShaderSource::Glsl {
+        shader: src.into(),
+        stage: wgpu::naga::ShaderStage::Compute,
+        defines: Default::default(),
+    }
+}
+
+const SAMPLER_SRC: &str = r"
+#version 450
+#pragma sampler linear (filter=Linear, wrap=Repeat)
+#pragma sampler shadow (comparison=Greater)
+
+layout(set=0, binding=0) uniform texture2D tex;
+layout(set=0, binding=1) uniform sampler linear;
+layout(set=0, binding=2) uniform sampler nearest;
+layout(set=0, binding=3) uniform texture2D depth;
+layout(set=0, binding=4) uniform samplerShadow shadow;
+layout(set=0, binding=5) uniform texture2D other;
+layout(set=1, binding=0) buffer Out { vec4 result; } out_buf;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {
+    vec4 a = textureLod(sampler2D(tex, linear), vec2(0.5), 0.0);
+    vec4 b = textureLod(sampler2D(other, nearest), vec2(0.5), 0.0);
+    float c = textureLod(sampler2DShadow(depth, shadow), vec3(0.5), 0.0);
+    out_buf.result = a + b + c;
+}
+";
+
+#[test]
+fn sampler_descriptors() {
+    let refl = ComputeReflector::new_compute(compute_stage(SAMPLER_SRC)).unwrap();
+
+    let linear = refl.sampler_descriptor(0, 1).unwrap();
+    assert_eq!(linear.mag_filter, wgpu::FilterMode::Linear);
+    assert_eq!(linear.min_filter, wgpu::FilterMode::Linear);
+    assert_eq!(linear.address_mode_u, wgpu::AddressMode::Repeat);
+    assert_eq!(linear.compare, None);
+
+    let nearest = refl.sampler_descriptor(0, 2).unwrap();
+    assert_eq!(nearest.mag_filter, wgpu::FilterMode::Nearest);
+    assert_eq!(nearest.address_mode_v, wgpu::AddressMode::ClampToEdge);
+
+    let shadow = refl.sampler_descriptor(0, 4).unwrap();
+    assert_eq!(shadow.compare, Some(wgpu::CompareFunction::Greater));
+
+    assert!(refl.sampler_descriptor(0, 0).is_none());
+    assert!(refl.sampler_descriptor(0, 9).is_none());
+}
+
+#[test]
+fn samplers_are_bound_automatically() -> Result<(), Error> {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(compute_stage(SAMPLER_SRC))?;
+
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl)?;
+    assert_eq!(pipeline.samplers().len(), 3);
+    assert!(pipeline.samplers().get(0, 0).is_none());
+
+    let texture = |format| {
+        device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width: 4,
+                    height: 4,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&Default::default())
+    };
+    let color = texture(wgpu::TextureFormat::Rgba8Unorm);
+    let other = texture(wgpu::TextureFormat::Rgba8Unorm);
+    let depth = texture(wgpu::TextureFormat::Depth32Float);
+    let out = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+
+    let bound = pipeline.bind(&device, |slot| match slot {
+        BindSlot::Texture {
+            loc: (0, 0), slot, ..
+        } => {
+            slot.borrow_mut().replace(&color);
+        }
+        BindSlot::Texture {
+            loc: (0, 3), slot, ..
+        } => {
+            slot.borrow_mut().replace(&depth);
+        }
+        BindSlot::Texture {
+            loc: (0, 5), slot, ..
+        } => {
+            slot.borrow_mut().replace(&other);
+        }
+        BindSlot::StorageBuffer {
+            loc: (1, 0), slot, ..
+        } => {
+            slot.borrow_mut().replace(out.as_entire_buffer_binding());
+        }
+        // the hinted samplers are never passed in
+        other => panic!("unexpected slot {other:?}"),
+    })?;
+
+    let (_, bind_groups) = bound.derail();
+    assert_eq!(bind_groups.len(), 2);
+    Ok(())
+}
+
+const OVERRIDE_SRC: &str = r"
+// @kinnara sampler samp (filter=Linear, wrap=ClampToEdge)
+
+@group(0) @binding(0) var tex: texture_2d<f32>;
+@group(0) @binding(1) var samp: sampler;
+@group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+@compute @workgroup_size(1)
+fn main() {
+    out[0] = textureSampleLevel(tex, samp, vec2<f32>(0.4, 0.5), 0.0).x;
+}
+";
+
+#[test]
+fn hinted_sampler_cannot_be_replaced() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(OVERRIDE_SRC.into()))?;
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl)?;
+
+    // a black and a white texel, sampled between their centers
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 2,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    device.write_texture(&queue, &texture, &[0, 0, 0, 255, 255, 255, 255, 255])?;
+    let view = texture.create_view(&Default::default());
+    let out = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 4,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let mut bound = pipeline.bind(&device, |slot| match slot {
+        BindSlot::Texture { slot, .. } => {
+            slot.replace(Some(&view));
+        }
+        BindSlot::StorageBuffer { slot, .. } => {
+            slot.replace(Some(out.as_entire_buffer_binding()));
+        }
+        other => panic!("unexpected slot {other:?}"),
+    })?;
+    let linear = run(&device, &queue, &bound, &out)?;
+    assert!((linear - 0.3).abs() < 0.02, "{linear}");
+
+    // rebinding keeps the linear sampler
+    bound.rebind_all(&device, |slot| match slot {
+        BindSlot::Texture { slot, .. } => {
+            slot.replace(Some(&view));
+        }
+        BindSlot::StorageBuffer { slot, .. } => {
+            slot.replace(Some(out.as_entire_buffer_binding()));
+        }
+        other => panic!("unexpected slot {other:?}"),
+    })?;
+    let linear = run(&device, &queue, &bound, &out)?;
+    assert!((linear - 0.3).abs() < 0.02, "{linear}");
+
+    Ok(())
+}
+
+fn run(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    bound: &BoundComputePipeline,
+    out: &wgpu::Buffer,
+) -> Result<f32, Error> {
+    let mut encoder = device.create_command_encoder(&Default::default());
+    bound
+        .create_pass(&mut encoder, |_| {})?
+        .dispatch_workgroups(1, 1, 1);
+    queue.submit([encoder.finish()]);
+    Ok(device.wait_for(device.read_buffer_as::<f32>(queue, out))?[0])
+}
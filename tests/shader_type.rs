@@ -0,0 +1,110 @@
+#![cfg(feature = "encase")]
+// encase's derive emits per field checks that are never called
+#![allow(dead_code)]
+
+use encase::ShaderType;
+use kinnara::*;
+use wgpu::ShaderSource;
+
+const SRC: &str = r"
+struct Params {
+    factor: f32,
+    count: u32,
+}
+
+struct Padded {
+    a: u32,
+    @size(8) b: u32,
+    c: u32,
+}
+
+struct Offset { value: f32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> padded: Padded;
+@group(0) @binding(2) var samp: sampler;
+var<push_constant> offset: Offset;
+
+@compute @workgroup_size(1)
+fn main() {
+    padded.a = u32(params.factor * offset.value) + params.count;
+    _ = samp;
+}
+";
+
+#[derive(ShaderType)]
+struct Params {
+    factor: f32,
+    count: u32,
+}
+
+#[derive(ShaderType)]
+struct Offset {
+    value: f32,
+}
+
+#[derive(ShaderType)]
+struct Unpadded {
+    a: u32,
+    b: u32,
+    c: [u32; 2],
+}
+
+#[test]
+fn typed_bindings() -> Result<(), Error> {
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(SRC.into()))?;
+
+    let params = refl.typed_binding::<Params>(0, 0)?;
+    assert_eq!(params.loc(), LayoutLoc::Binding(0, 0));
+    let bytes = params.to_bytes(&Params {
+        factor: 2.0,
+        count: 3,
+    });
+    assert_eq!(bytes, [2.0f32.to_le_bytes(), 3u32.to_le_bytes()].concat());
+
+    let offset = refl.typed_push_constants::<Offset>()?;
+    assert_eq!(
+        offset.to_bytes(&Offset { value: 1.5 }),
+        1.5f32.to_le_bytes()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn layout_mismatches() {
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(SRC.into())).unwrap();
+
+    assert!(matches!(
+        refl.typed_binding::<Unpadded>(0, 1),
+        Err(Error::ShaderTypeError(ShaderTypeError::OffsetMismatch {
+            loc: LayoutLoc::Binding(0, 1),
+            member,
+            expected: 12,
+            found: 8,
+        })) if member == "c"
+    ));
+
+    assert!(matches!(
+        refl.typed_binding::<Offset>(0, 0),
+        Err(Error::ShaderTypeError(ShaderTypeError::SizeMismatch {
+            expected: 8,
+            found: 4,
+            ..
+        }))
+    ));
+
+    assert!(matches!(
+        refl.typed_push_constants::<Params>(),
+        Err(Error::ShaderTypeError(ShaderTypeError::SizeMismatch {
+            loc: LayoutLoc::PushConstants,
+            ..
+        }))
+    ));
+
+    // samplers have no layout
+    assert!(matches!(
+        refl.typed_binding::<Params>(0, 2),
+        Err(Error::HostTypeError(HostTypeError::NoSuchBuffer(0, 2)))
+    ));
+}
@@ -0,0 +1,126 @@
+use kinnara::*;
+use wgpu::ShaderSource;
+
+const SRC: &str = r"
+struct Params {
+    factor: f32,
+    count: u32,
+}
+
+struct Offset { value: f32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> data: array<f32>;
+@group(1) @binding(0) var samp: sampler;
+var<push_constant> offset: Offset;
+
+@compute @workgroup_size(1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.count {
+        data[id.x] = data[id.x] * params.factor + offset.value;
+    }
+    _ = samp;
+}
+";
+
+fn params_layout(count_offset: u32) -> StructLayout {
+    StructLayout {
+        size: 8,
+        fields: vec![
+            FieldLayout {
+                name: "factor",
+                offset: 0,
+                size: 4,
+            },
+            FieldLayout {
+                name: "count",
+                offset: count_offset,
+                size: 4,
+            },
+        ],
+    }
+}
+
+fn offset_layout() -> StructLayout {
+    StructLayout {
+        size: 4,
+        fields: vec![FieldLayout {
+            name: "value",
+            offset: 0,
+            size: 4,
+        }],
+    }
+}
+
+struct Matching;
+
+impl BindingSignature for Matching {
+    fn signature() -> Signature {
+        Signature {
+            bindings: vec![
+                SignatureBinding {
+                    layout: Some(params_layout(4)),
+                    ..SignatureBinding::new(0, 0, ResourceKind::UniformBuffer)
+                },
+                SignatureBinding::new(0, 1, ResourceKind::StorageBuffer { read_only: false }),
+                SignatureBinding::new(1, 0, ResourceKind::Sampler),
+            ],
+            push_constants: Some(offset_layout()),
+        }
+    }
+}
+
+struct Drifted;
+
+impl BindingSignature for Drifted {
+    fn signature() -> Signature {
+        Signature {
+            bindings: vec![
+                SignatureBinding {
+                    layout: Some(params_layout(8)),
+                    ..SignatureBinding::new(0, 0, ResourceKind::UniformBuffer)
+                },
+                SignatureBinding::new(0, 1, ResourceKind::StorageBuffer { read_only: true }),
+                SignatureBinding::new(2, 0, ResourceKind::Texture),
+            ],
+            push_constants: None,
+        }
+    }
+}
+
+#[test]
+fn matching_signature() {
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(SRC.into())).unwrap();
+    refl.check_signature::<Matching>().unwrap();
+}
+
+#[test]
+fn drifted_signature() {
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(SRC.into())).unwrap();
+
+    let Err(Error::SignatureMismatch(mismatches)) = refl.check_signature::<Drifted>() else {
+        panic!("signature should not match");
+    };
+    assert_eq!(
+        mismatches,
+        [
+            SignatureMismatch::FieldMismatch {
+                loc: LayoutLoc::Binding(0, 0),
+                name: "count".to_owned(),
+                expected: (4, 4),
+                found: (8, 4),
+            },
+            SignatureMismatch::KindMismatch {
+                loc: (0, 1),
+                expected: ResourceKind::StorageBuffer { read_only: false },
+                found: ResourceKind::StorageBuffer { read_only: true },
+            },
+            SignatureMismatch::MissingBinding(2, 0),
+            SignatureMismatch::UnexpectedBinding(1, 0),
+            SignatureMismatch::UnexpectedPushConstants,
+        ]
+    );
+
+    let message = Error::SignatureMismatch(mismatches).to_string();
+    assert!(message.contains("set 0 binding 0 member `count` is at 4..8, the signature has 8..12"));
+}
@@ -0,0 +1,9 @@
+#pragma once
+#pragma uniform seed (dynamic_offset=true)
+
+uint hash(uint x) {
+    x ^= x >> 16;
+    x *= 0x7feb352du;
+    x ^= x >> 15;
+    return x;
+}
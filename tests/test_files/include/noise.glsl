@@ -0,0 +1,6 @@
+#pragma once
+#include "hash.glsl"
+
+float noise(uint x) {
+    return float(hash(x)) / 4294967295.0;
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use kinnara::*;
+use wgpu::{Extent3d, TextureFormat, TextureUsages};
+
+use common::set_up_device;
+
+fn texture(
+    device: &wgpu::Device,
+    format: TextureFormat,
+    size: Extent3d,
+    mip_level_count: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size,
+        mip_level_count,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: TextureUsages::COPY_SRC | TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+fn extent(width: u32, height: u32, layers: u32) -> Extent3d {
+    Extent3d {
+        width,
+        height,
+        depth_or_array_layers: layers,
+    }
+}
+
+#[test]
+fn padded_round_trip() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    // 3 rgba8 pixels make 12 byte rows, padded to 256 in the staging buffer
+    let tex = texture(&device, TextureFormat::Rgba8Unorm, extent(3, 5, 1), 1);
+    let pixels: Vec<u8> = (0..3 * 5 * 4).map(|i| i as u8).collect();
+
+    device.write_texture(&queue, &tex, &pixels)?;
+    assert_eq!(device.wait_for(device.read_texture(&queue, &tex))?, pixels);
+
+    // a 2x2 box in the middle
+    let region = TextureRegion {
+        origin: wgpu::Origin3d { x: 1, y: 2, z: 0 },
+        size: extent(2, 2, 1),
+        ..TextureRegion::whole(&tex)
+    };
+    let expected: Vec<u8> = [2, 3]
+        .into_iter()
+        .flat_map(|row| pixels[(row * 3 + 1) * 4..(row * 3 + 3) * 4].to_vec())
+        .collect();
+    assert_eq!(
+        device.wait_for(device.read_texture_region(&queue, &tex, &region))?,
+        expected
+    );
+
+    device.write_texture_region(&queue, &tex, &region, &[0xff; 16])?;
+    let after = device.wait_for(device.read_texture(&queue, &tex))?;
+    assert_eq!(after[(2 * 3 + 1) * 4..(2 * 3 + 3) * 4], [0xff; 8]);
+    assert_eq!(after[..(2 * 3 + 1) * 4], pixels[..(2 * 3 + 1) * 4]);
+
+    Ok(())
+}
+
+#[test]
+fn layers_and_mips() -> Result<(), Error> {
+    let (device, queue) = set_up_device();
+    // six square layers would be taken for a cube map by the GL backend,
+    // which can't copy them, cube faces are plain layers everywhere else
+    let array = texture(&device, TextureFormat::R32Float, extent(4, 4, 3), 3);
+
+    let layers: Vec<f32> = (0..3).flat_map(|layer| [layer as f32; 16]).collect();
+    device.write_texture(&queue, &array, bytemuck::cast_slice(&layers))?;
+    let read = device.wait_for(device.read_texture(&queue, &array))?;
+    assert_eq!(bytemuck::cast_slice::<u8, f32>(&read), layers);
+
+    let last = TextureRegion::layers(&array, 0, 2..3);
+    let read = device.wait_for(device.read_texture_region(&queue, &array, &last))?;
+    assert_eq!(bytemuck::cast_slice::<u8, f32>(&read), [2.0; 16]);
+
+    // mip 2 is 1x1 on every layer
+    let smallest = TextureRegion::mip(&array, 2);
+    assert_eq!(smallest.size, extent(1, 1, 3));
+    let values = [10.0f32, 11.0, 12.0];
+    device.write_texture_region(&queue, &array, &smallest, bytemuck::cast_slice(&values))?;
+    let read = device.wait_for(device.read_texture_region(&queue, &array, &smallest))?;
+    assert_eq!(bytemuck::cast_slice::<u8, f32>(&read), values);
+
+    Ok(())
+}
+
+#[test]
+fn texture_copy_errors() {
+    let (device, queue) = set_up_device();
+    let tex = texture(&device, TextureFormat::Rgba8Unorm, extent(4, 4, 1), 1);
+
+    assert_eq!(
+        device.write_texture(&queue, &tex, &[0; 60]),
+        Err(TextureCopyError::DataSize {
+            expected: 64,
+            found: 60
+        })
+    );
+
+    let outside = TextureRegion {
+        origin: wgpu::Origin3d { x: 2, y: 0, z: 0 },
+        ..TextureRegion::whole(&tex)
+    };
+    assert!(matches!(
+        device.wait_for(device.read_texture_region(&queue, &tex, &outside)),
+        Err(TextureCopyError::OutOfBounds { .. })
+    ));
+    let overflowing = TextureRegion {
+        origin: wgpu::Origin3d {
+            x: u32::MAX,
+            y: 0,
+            z: 0,
+        },
+        ..TextureRegion::whole(&tex)
+    };
+    assert!(matches!(
+        device.write_texture_region(&queue, &tex, &overflowing, &[0; 64]),
+        Err(TextureCopyError::OutOfBounds { .. })
+    ));
+    let empty = TextureRegion {
+        size: extent(4, 0, 1),
+        ..TextureRegion::whole(&tex)
+    };
+    assert_eq!(
+        device.wait_for(device.read_texture_region(&queue, &tex, &empty)),
+        Err(TextureCopyError::EmptyRegion(extent(4, 0, 1)))
+    );
+    assert_eq!(
+        device.wait_for(device.read_texture_region(&queue, &tex, &TextureRegion::mip(&tex, 1))),
+        Err(TextureCopyError::NoSuchMipLevel(1))
+    );
+
+    let multisampled = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: extent(4, 4, 1),
+        mip_level_count: 1,
+        sample_count: 4,
+        dimension: wgpu::TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    assert_eq!(
+        device.wait_for(device.read_texture(&queue, &multisampled)),
+        Err(TextureCopyError::Multisampled)
+    );
+
+    let sampled_only = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: extent(4, 4, 1),
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    assert_eq!(
+        device.wait_for(device.read_texture(&queue, &sampled_only)),
+        Err(TextureCopyError::MissingUsage(TextureUsages::COPY_SRC))
+    );
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use kinnara::*;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{BufferUsages, ShaderSource};
+
+use common::set_up_device;
+
+const PUSH_SRC: &str = r#"
+struct Constants { scale: f32 }
+
+var<push_constant> constants: Constants;
+@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    data[id.x] *= constants.scale;
+}
+"#;
+
+const TEXTURE_SRC: &str = r#"
+@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+@group(0) @binding(1) var tex: texture_2d<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    data[id.x] += textureLoad(tex, vec2<i32>(0), 0).x;
+}
+"#;
+
+const QUAD_SRC: &str = r#"
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(position, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+"#;
+
+#[test]
+fn missing_capability() {
+    // the device lacks PUSH_CONSTANTS, naga's validator reports it
+    // before wgpu sees the module
+    let (device, _queue) = set_up_device();
+    let options = PreprocessOptions {
+        file_name: Some("scale.wgsl"),
+        ..Default::default()
+    };
+    let refl =
+        ComputeReflector::new_compute_with(ShaderSource::Wgsl(PUSH_SRC.into()), &options).unwrap();
+
+    let err = UnboundComputePipeline::new(&device, "main", Default::default(), refl)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::CompilationError(_)), "{err}");
+
+    let [diagnostic] = err.diagnostics() else {
+        panic!("expected a single diagnostic, got {err}");
+    };
+    assert!(diagnostic.message.contains("PUSH_CONSTANT"), "{err}");
+    let loc = diagnostic.location.as_ref().unwrap();
+    assert_eq!(loc.file, "scale.wgsl");
+    assert_eq!(loc.line, 4);
+}
+
+const CUBE_ARRAY_SRC: &str = r#"
+@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+@group(0) @binding(1) var cubes: texture_cube_array<f32>;
+@group(0) @binding(2) var samp: sampler;
+
+@compute @workgroup_size(1)
+fn main() {
+    data[0] = textureSampleLevel(cubes, samp, vec3<f32>(1.0), 0, 0.0).x;
+}
+"#;
+
+#[test]
+fn missing_downlevel_flag() {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(CUBE_ARRAY_SRC.into())).unwrap();
+
+    // without the adapter's flags cube map arrays are taken as unsupported
+    let err = UnboundComputePipeline::new(&device, "main", Default::default(), refl)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::CompilationError(_)), "{err}");
+    assert!(err.to_string().contains("CUBE_ARRAY_TEXTURES"), "{err}");
+}
+
+#[test]
+fn captured_bind_group_error() {
+    let (device, _queue) = set_up_device();
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(TEXTURE_SRC.into())).unwrap();
+    let pipeline = UnboundComputePipeline::new(&device, "main", Default::default(), refl).unwrap();
+
+    let storage = device.create_buffer_init(&BufferInitDescriptor {
+        label: None,
+        contents: &[0; 256],
+        usage: BufferUsages::STORAGE,
+    });
+    // a plain view isn't checked before binding, a storage only texture
+    // is left for wgpu to reject
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::STORAGE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&Default::default());
+    let result = pipeline.bind(&device, |slot| match slot {
+        BindSlot::StorageBuffer { slot, .. } => {
+            slot.replace(Some(storage.as_entire_buffer_binding()));
+        }
+        BindSlot::Texture { slot, .. } => {
+            slot.replace(Some(&view));
+        }
+        _ => {}
+    });
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
+
+#[test]
+fn captured_pipeline_error() {
+    let (device, _queue) = set_up_device();
+    let refl = RenderReflector::new_render_module(ShaderSource::Wgsl(QUAD_SRC.into())).unwrap();
+
+    let state = RenderState {
+        multisample: wgpu::MultisampleState {
+            count: 3,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let result = UnboundRenderPipeline::new(
+        &device,
+        "vs_main",
+        Some("fs_main"),
+        &[Some(wgpu::TextureFormat::Rgba8Unorm.into())],
+        &state,
+        Default::default(),
+        refl,
+    );
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
@@ -0,0 +1,257 @@
+use kinnara::*;
+use wgpu::ShaderSource;
+
+fn compute_stage(src: &str) -> ShaderSource<'_> {
+    wgpu::ShaderSource::Glsl {
+        shader: src.into(),
+        stage: wgpu::naga::ShaderStage::Compute,
+        defines: Default::default(),
+    }
+}
+
+const PARAMETERS_SRC: &str = r"
+#version 450
+#pragma float super.name : range(0..1) = 0.5
+#pragma color var = [1.0, 1.0, 0.0, 1.0]
+#pragma bool grand_parent.parent.bool_name = true
+#pragma int pc.steps : range(1..8) = 4
+
+struct Parent {
+    float pad;
+    bool bool_name;
+};
+
+struct GrandParent {
+    vec4 pad;
+    Parent parent;
+};
+
+layout(set=0, binding=0) uniform Super {
+    float other;
+    float name;
+} super;
+
+layout(set=1, binding=2) uniform Params {
+    vec4 var;
+    GrandParent grand_parent;
+};
+
+layout(push_constant) uniform Push {
+    float scale;
+    int steps;
+} pc;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+
+#[test]
+fn parameter_schema() {
+    let refl = ComputeReflector::new_compute(compute_stage(PARAMETERS_SRC)).unwrap();
+    let params = refl.parameters();
+
+    let expected = [
+        Parameter {
+            path: "super.name".into(),
+            ty: VarType::Float {
+                range: Some(0.0..1.0),
+                default: Some(0.5),
+            },
+            location: ParameterLocation::Uniform { set: 0, binding: 0 },
+            offset: 4,
+            size: 4,
+        },
+        Parameter {
+            path: "var".into(),
+            ty: VarType::Color {
+                default: Some([1.0, 1.0, 0.0, 1.0]),
+            },
+            location: ParameterLocation::Uniform { set: 1, binding: 2 },
+            offset: 0,
+            size: 16,
+        },
+        Parameter {
+            path: "grand_parent.parent.bool_name".into(),
+            ty: VarType::Bool {
+                default: Some(true),
+            },
+            location: ParameterLocation::Uniform { set: 1, binding: 2 },
+            offset: 36,
+            size: 4,
+        },
+        Parameter {
+            path: "pc.steps".into(),
+            ty: VarType::Sint {
+                range: Some(1..8),
+                default: Some(4),
+            },
+            location: ParameterLocation::PushConstant,
+            offset: 4,
+            size: 4,
+        },
+    ];
+
+    assert_eq!(params, &expected);
+}
+
+#[test]
+fn unresolved_parameter() {
+    let src = r"
+#version 450
+#pragma float missing.name = 0.5
+
+layout(set=0, binding=0) uniform Block { float name; } block;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+
+    let res = ComputeReflector::new_compute(compute_stage(src));
+    assert!(matches!(res, Err(Error::ParameterError(_))));
+}
+
+const VECTOR_SRC: &str = r"
+#version 450
+#pragma vec2 point : range([-1.0, -1.0]..[1.0, 1.0]) = [0.0, 0.0]
+#pragma ivec3 cell : range(0..[8, 8, 4]) = 1
+#pragma uvec4 mask = [1, 2, 3, 4]
+#pragma mat3 rot = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+#pragma mat4x3 affine : range(-1.0..1.0) = 0.0
+
+layout(set=0, binding=0) uniform Block {
+    vec2 point;
+    ivec3 cell;
+    uvec4 mask;
+    mat3 rot;
+    mat4x3 affine;
+};
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+
+#[test]
+fn vector_and_matrix_parameters() {
+    let refl = ComputeReflector::new_compute(compute_stage(VECTOR_SRC)).unwrap();
+    let params = refl.parameters();
+
+    assert_eq!(params.len(), 5);
+
+    assert_eq!(
+        params[0].ty,
+        VarType::FloatVec {
+            size: 2,
+            range: Some(vec![-1.0, -1.0]..vec![1.0, 1.0]),
+            default: Some(vec![0.0, 0.0]),
+        }
+    );
+
+    assert_eq!(
+        params[1].ty,
+        VarType::SintVec {
+            size: 3,
+            range: Some(vec![0, 0, 0]..vec![8, 8, 4]),
+            default: Some(vec![1, 1, 1]),
+        }
+    );
+    assert_eq!(params[1].offset, 16);
+
+    assert_eq!(
+        params[2].ty,
+        VarType::UintVec {
+            size: 4,
+            range: None,
+            default: Some(vec![1, 2, 3, 4]),
+        }
+    );
+
+    assert_eq!(
+        params[3].ty,
+        VarType::Matrix {
+            columns: 3,
+            rows: 3,
+            range: None,
+            default: Some(vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]),
+        }
+    );
+
+    assert_eq!(
+        params[4].ty,
+        VarType::Matrix {
+            columns: 4,
+            rows: 3,
+            range: Some(vec![-1.0; 12]..vec![1.0; 12]),
+            default: Some(vec![0.0; 12]),
+        }
+    );
+    assert_eq!(params[4].size, 64);
+}
+
+#[test]
+fn parameter_type_mismatch() {
+    let src = r"
+#version 450
+#pragma vec3 block.point = 0.0
+
+layout(set=0, binding=0) uniform Block { vec2 point; } block;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+";
+
+    match ComputeReflector::new_compute(compute_stage(src)) {
+        Err(Error::ParameterError(e)) => {
+            let msg = e.to_string();
+            assert!(msg.contains("vec3") && msg.contains("vec2"), "{msg}");
+        }
+        other => panic!("expected a type mismatch, got {other:?}"),
+    }
+}
+
+#[test]
+fn wrong_component_count() {
+    let src = "#pragma vec2 point = [0.0, 0.0, 0.0]\n";
+    let res = ComputeReflector::new_compute(compute_stage(src));
+    assert!(matches!(res, Err(Error::PreprocessingError(_))));
+}
+
+#[test]
+fn basic_directives_file() {
+    let directives = include_str!("test_files/basic_directives.glsl");
+    let src = format!(
+        "#version 450\n{directives}\n{}",
+        r"
+struct Parent { bool bool_name; };
+struct GrandParent { Parent parent; };
+
+layout(set=0, binding=0) uniform Super { float name; } super;
+layout(set=0, binding=1) uniform Globals {
+    vec4 var;
+    GrandParent grand_parent;
+    bool bool_2_name;
+    vec2 point;
+};
+layout(set=0, binding=2) uniform sampler samp;
+layout(set=0, binding=3) uniform samplerShadow dumb;
+layout(set=0, binding=4) uniform Uni { float x; } uni_name;
+layout(set=0, binding=5) uniform texture2DMS multisampled;
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {}
+"
+    );
+
+    let refl = ComputeReflector::new_compute(compute_stage(&src)).unwrap();
+    let paths: Vec<_> = refl.parameters().iter().map(|p| p.path.as_str()).collect();
+
+    assert_eq!(
+        paths,
+        [
+            "super.name",
+            "var",
+            "grand_parent.parent.bool_name",
+            "bool_2_name",
+            "point"
+        ]
+    );
+}
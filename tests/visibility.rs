@@ -0,0 +1,81 @@
+use kinnara::*;
+use wgpu::{ShaderSource, ShaderStages};
+
+const RENDER_SRC: &str = r"
+struct Camera { view_proj: mat4x4<f32> }
+struct Tint { color: vec4<f32> }
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(0) @binding(1) var tex: texture_2d<f32>;
+@group(0) @binding(2) var samp: sampler;
+@group(0) @binding(3) var<uniform> tint: Tint;
+@group(0) @binding(4) var<uniform> unused: Tint;
+
+fn tinted(color: vec4<f32>) -> vec4<f32> {
+    return color * tint.color;
+}
+
+@vertex
+fn vs_main(@location(0) pos: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return tinted(camera.view_proj * vec4(pos, 1.0));
+}
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    if pos.x > 0.0 {
+        return tinted(textureSample(tex, samp, pos.xy));
+    }
+    return vec4(0.0);
+}
+";
+
+#[test]
+fn visibility_from_usage() {
+    let refl = ComputeReflector::new_compute(ShaderSource::Wgsl(RENDER_SRC.into())).unwrap();
+    let visibility = |binding| {
+        refl.get_bind_group_layout_entry(0, binding)
+            .unwrap()
+            .visibility
+    };
+
+    assert_eq!(visibility(0), ShaderStages::VERTEX);
+    assert_eq!(visibility(1), ShaderStages::FRAGMENT);
+    assert_eq!(visibility(2), ShaderStages::FRAGMENT);
+    // reached through a helper function from both stages
+    assert_eq!(visibility(3), ShaderStages::VERTEX_FRAGMENT);
+    // unreferenced bindings stay visible to every stage in the module
+    assert_eq!(visibility(4), ShaderStages::VERTEX_FRAGMENT);
+}
+
+#[test]
+fn compute_helpers_are_followed() {
+    let src = r"
+#version 450
+layout(set=0, binding=0) buffer Data { float values[]; } data;
+layout(push_constant) uniform Push { float add; } pc;
+
+void apply(uint idx) {
+    for (int i = 0; i < 2; i++) {
+        data.values[idx] += pc.add;
+    }
+}
+
+layout(local_size_x=1, local_size_y=1, local_size_z=1) in;
+void main() {
+    apply(gl_GlobalInvocationID.x);
+}
+";
+    let source = wgpu::ShaderSource::Glsl {
+        shader: src.into(),
+        stage: wgpu::naga::ShaderStage::Compute,
+        defines: Default::default(),
+    };
+    let refl = ComputeReflector::new_compute(source).unwrap();
+
+    let data = refl.get_bind_group_layout_entry(0, 0).unwrap();
+    assert_eq!(data.visibility, ShaderStages::COMPUTE);
+    assert_eq!(
+        refl.push_constant_range().unwrap()[0].stages,
+        ShaderStages::COMPUTE
+    );
+}